@@ -0,0 +1,134 @@
+//! Compares the snake's per-frame checks done by scanning the entities with the same checks
+//! done on [`SnakeBoard`].
+//!
+//! `cargo bench --bench snake_board`
+
+use std::{
+  collections::{HashMap, HashSet},
+  hint::black_box,
+  time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use game_club::games::{BoardItem, SnakeBoard};
+
+const ITERATIONS: u32 = 200;
+/// Part of the arena covered by the snake.
+const SNAKE_FILL: f32 = 0.5;
+const FOOD_COUNT: usize = 3;
+
+struct Arena {
+  size: UVec2,
+  snake: Vec<UVec2>,
+  food: Vec<(Entity, UVec2)>,
+  board: SnakeBoard,
+}
+
+impl Arena {
+  /// Snake winding row by row from the bottom left corner, food on random free cells.
+  fn new(size: UVec2) -> Self {
+    let length = ((size.x * size.y) as f32 * SNAKE_FILL) as u32;
+    let snake = (0..length)
+      .map(|i| {
+        let y = i / size.x;
+        let x = if y % 2 == 0 {
+          i % size.x
+        } else {
+          size.x - 1 - i % size.x
+        };
+        UVec2::new(x, y)
+      })
+      .collect::<Vec<_>>();
+
+    let mut board = SnakeBoard::new(size);
+    for cell in &snake {
+      board.add_snake(*cell);
+    }
+
+    let food = (0..FOOD_COUNT)
+      .map(|i| {
+        let entity = Entity::from_raw_u32(i as u32 + 1).unwrap();
+        let cell = board.random_free_cell(&[]).unwrap();
+        board.set_item(cell, BoardItem::Food(entity));
+        (entity, cell)
+      })
+      .collect();
+
+    Self {
+      size,
+      snake,
+      food,
+      board,
+    }
+  }
+}
+
+/// The checks as they were done before the board: a loop over the segments, a map of the food
+/// and a set of the occupied cells rebuilt for every spawn.
+fn scan(arena: &Arena) {
+  let head = arena.snake[0];
+  let self_collision = arena.snake[1..]
+    .iter()
+    .any(|cell| *cell == head);
+
+  let foods = arena
+    .food
+    .iter()
+    .map(|(entity, cell)| (*cell, *entity))
+    .collect::<HashMap<_, _>>();
+  let eaten = arena
+    .snake
+    .iter()
+    .find_map(|cell| foods.get(cell));
+
+  let except = arena
+    .snake
+    .iter()
+    .chain(arena.food.iter().map(|(_, cell)| cell))
+    .collect::<HashSet<_>>();
+  let candidates = (0..arena.size.y)
+    .flat_map(|y| (0..arena.size.x).map(move |x| UVec2::new(x, y)))
+    .filter(|cell| !except.contains(cell))
+    .collect::<Vec<_>>();
+  let spawn = candidates[rand::random_range(0..candidates.len())];
+
+  black_box((self_collision, eaten, spawn));
+}
+
+fn board(arena: &Arena) {
+  let head = arena.snake[0];
+  let self_collision = arena.board.snake_count(head) > 1;
+  let eaten = match arena.board.item(head) {
+    BoardItem::Food(entity) => Some(entity),
+    _ => None,
+  };
+  let spawn = arena.board.random_free_cell(&[]);
+
+  black_box((self_collision, eaten, spawn));
+}
+
+fn measure(arena: &Arena, check: fn(&Arena)) -> Duration {
+  let start = Instant::now();
+  for _ in 0..ITERATIONS {
+    check(black_box(arena));
+  }
+  start.elapsed() / ITERATIONS
+}
+
+fn main() {
+  for side in [64, 256] {
+    let arena = Arena::new(UVec2::splat(side));
+
+    let scan_time = measure(&arena, scan);
+    let board_time = measure(&arena, board);
+
+    println!(
+      "{side}x{side}, snake length {}: scan {scan_time:?}, board {board_time:?} ({:.0}x)",
+      arena.snake.len(),
+      scan_time.as_secs_f64()
+        / board_time
+          .as_secs_f64()
+          .max(f64::EPSILON),
+    );
+  }
+}
@@ -0,0 +1,418 @@
+use bevy::prelude::*;
+
+use crate::{
+  dialogue::DialogueSystems, game::FontAssets, games::GameLaunchMessage, player::Player,
+  profile::Profile, scope::Unscoped, settings::settings_menu_is_open, state::ContextScope,
+};
+
+const TOAST_SECONDS: f32 = 4.0;
+const TROPHY_WALL_INTERACT_DISTANCE: f32 = 32.0;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<TrophyWallState>();
+
+    app.register_type::<TrophyWall>();
+
+    app.add_observer(achievement_progress_observer);
+    app.add_observer(achievement_unlocked_observer);
+
+    app.add_systems(
+      Update,
+      (
+        cabinet_launch_achievements_system,
+        toast_lifetime_system,
+        toggle_trophy_wall_system
+          .before(DialogueSystems)
+          .run_if(not(settings_menu_is_open)),
+      ),
+    );
+  }
+}
+
+pub struct Achievement {
+  pub id: &'static str,
+  pub name: &'static str,
+  pub description: &'static str,
+  pub icon: &'static str,
+  /// Hidden achievements show up as "???" on the trophy wall until unlocked.
+  pub hidden: bool,
+  /// Progress needed to unlock.
+  pub goal: u32,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+  Achievement {
+    id: "first_game",
+    name: "Insert Coin",
+    description: "Launch any cabinet",
+    icon: "player/single_sprite.png",
+    hidden: false,
+    goal: 1,
+  },
+  Achievement {
+    id: "club_regular",
+    name: "Club Regular",
+    description: "Launch cabinets 25 times",
+    icon: "player/single_sprite.png",
+    hidden: false,
+    goal: 25,
+  },
+  Achievement {
+    id: "snake_gourmet",
+    name: "Gourmet",
+    description: "Eat 100 pieces of food in Snake",
+    icon: "games/snake/snake_game.png",
+    hidden: false,
+    goal: 100,
+  },
+  Achievement {
+    id: "snake_long",
+    name: "Long Boi",
+    description: "Grow the snake to 30 segments",
+    icon: "games/snake/snake_game.png",
+    hidden: false,
+    goal: 30,
+  },
+  Achievement {
+    id: "snake_speed_freak",
+    name: "Speed Freak",
+    description: "Eat 5 red apples in one run",
+    icon: "games/snake/snake_game.png",
+    hidden: true,
+    goal: 5,
+  },
+  Achievement {
+    id: "snake_win",
+    name: "Ouroboros",
+    description: "Fill the whole Snake arena",
+    icon: "games/snake/snake_game.png",
+    hidden: true,
+    goal: 1,
+  },
+];
+
+pub fn find_achievement(id: &str) -> Option<&'static Achievement> {
+  ACHIEVEMENTS
+    .iter()
+    .find(|achievement| achievement.id == id)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum AchievementProgress {
+  /// Adds to the stored counter.
+  Add(u32),
+  /// Raises the stored counter to at least this value. Used for "reach N" style goals.
+  AtLeast(u32),
+}
+
+#[derive(Event)]
+pub struct AchievementProgressEvent {
+  pub id: &'static str,
+  pub progress: AchievementProgress,
+}
+
+#[derive(Event)]
+pub struct AchievementUnlockedEvent {
+  pub id: &'static str,
+}
+
+/// Tiled object that opens the list of achievements on Interact.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct TrophyWall;
+
+#[derive(Resource, Default)]
+pub struct TrophyWallState {
+  is_open: bool,
+}
+
+pub fn trophy_wall_is_open(state: Res<TrophyWallState>) -> bool {
+  state.is_open
+}
+
+#[derive(Component)]
+struct AchievementToast {
+  timer: Timer,
+}
+
+#[derive(Component)]
+struct TrophyWallUi;
+
+fn achievement_progress_observer(
+  event: On<AchievementProgressEvent>,
+  mut commands: Commands,
+  mut profile: ResMut<Profile>,
+) {
+  let Some(achievement) = find_achievement(event.id) else {
+    warn!("Unknown achievement '{}'", event.id);
+    return;
+  };
+
+  let current = profile.achievement_progress(achievement.id);
+
+  if current >= achievement.goal {
+    return;
+  }
+
+  let updated = match event.progress {
+    AchievementProgress::Add(amount) => current.saturating_add(amount),
+    AchievementProgress::AtLeast(value) => current.max(value),
+  }
+  .min(achievement.goal);
+
+  if updated == current {
+    return;
+  }
+
+  profile
+    .achievement_progress
+    .insert(achievement.id.to_string(), updated);
+
+  if updated >= achievement.goal {
+    commands.trigger(AchievementUnlockedEvent { id: achievement.id });
+  }
+}
+
+fn achievement_unlocked_observer(
+  event: On<AchievementUnlockedEvent>,
+  mut commands: Commands,
+  toast_query: Query<&AchievementToast>,
+  font_assets: Res<FontAssets>,
+  asset_server: Res<AssetServer>,
+) {
+  let Some(achievement) = find_achievement(event.id) else {
+    return;
+  };
+
+  info!("Achievement unlocked: {}", achievement.name);
+
+  // Одновременно открытые тосты складываются стопкой снизу вверх
+  let stack_index = toast_query.iter().count() as f32;
+
+  commands.spawn((
+    Name::new("AchievementToast"),
+    // Тост может появиться в мини-игре и доживает своё время после выхода из неё
+    Unscoped,
+    AchievementToast {
+      timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+    },
+    Node {
+      position_type: PositionType::Absolute,
+      right: px(16.),
+      bottom: px(16. + stack_index * 88.),
+      width: px(360.),
+      height: px(80.),
+      align_items: AlignItems::Center,
+      column_gap: px(12.),
+      padding: UiRect::all(px(8.)),
+      border: UiRect::all(px(2.)),
+      ..default()
+    },
+    BackgroundColor(Color::srgba(0.05, 0.05, 0.1, 0.9)),
+    BorderColor::all(Color::srgb(1.0, 0.85, 0.3)),
+    GlobalZIndex(10),
+    children![
+      (
+        Node {
+          width: px(56.),
+          height: px(56.),
+          ..default()
+        },
+        ImageNode::new(asset_server.load(achievement.icon)),
+      ),
+      (
+        Node {
+          flex_direction: FlexDirection::Column,
+          ..default()
+        },
+        children![
+          (
+            Text::new("Achievement unlocked!"),
+            TextFont {
+              font: font_assets.regular.clone(),
+              font_size: 18.,
+              ..Default::default()
+            },
+            TextColor(Color::srgb(1.0, 0.85, 0.3)),
+          ),
+          (
+            Text::new(achievement.name),
+            TextFont {
+              font: font_assets.regular.clone(),
+              font_size: 26.,
+              ..Default::default()
+            },
+            TextColor(Color::WHITE),
+          ),
+        ],
+      ),
+    ],
+  ));
+}
+
+fn toast_lifetime_system(
+  mut commands: Commands,
+  mut toast_query: Query<(Entity, &mut AchievementToast)>,
+  time: Res<Time>,
+) {
+  for (entity, mut toast) in &mut toast_query {
+    toast.timer.tick(time.delta());
+
+    if toast.timer.is_finished() {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+fn cabinet_launch_achievements_system(
+  mut commands: Commands,
+  mut game_launch_messages: MessageReader<GameLaunchMessage>,
+) {
+  for _ in game_launch_messages.read() {
+    commands.trigger(AchievementProgressEvent {
+      id: "first_game",
+      progress: AchievementProgress::Add(1),
+    });
+    commands.trigger(AchievementProgressEvent {
+      id: "club_regular",
+      progress: AchievementProgress::Add(1),
+    });
+  }
+}
+
+fn toggle_trophy_wall_system(
+  mut commands: Commands,
+  mut state: ResMut<TrophyWallState>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  profile: Res<Profile>,
+  font_assets: Res<FontAssets>,
+  player_query: Query<&GlobalTransform, With<Player>>,
+  trophy_wall_query: Query<&GlobalTransform, With<TrophyWall>>,
+  trophy_wall_ui_query: Query<Entity, With<TrophyWallUi>>,
+) {
+  if state.is_open {
+    if keyboard_input.any_just_pressed([KeyCode::KeyE, KeyCode::Escape]) {
+      keyboard_input.clear_just_pressed(KeyCode::KeyE);
+      keyboard_input.clear_just_pressed(KeyCode::Escape);
+
+      for entity in trophy_wall_ui_query.iter() {
+        commands.entity(entity).despawn();
+      }
+      state.is_open = false;
+    }
+    return;
+  }
+
+  if !keyboard_input.just_pressed(KeyCode::KeyE) {
+    return;
+  }
+
+  let Ok(player_transform) = player_query.single() else {
+    return;
+  };
+  let player_position = player_transform
+    .translation()
+    .truncate();
+
+  let is_near_wall = trophy_wall_query
+    .iter()
+    .any(|transform| {
+      transform
+        .translation()
+        .truncate()
+        .distance(player_position)
+        <= TROPHY_WALL_INTERACT_DISTANCE
+    });
+
+  if !is_near_wall {
+    return;
+  }
+
+  keyboard_input.clear_just_pressed(KeyCode::KeyE);
+
+  spawn_trophy_wall_ui(&mut commands, &profile, &font_assets);
+  state.is_open = true;
+}
+
+fn spawn_trophy_wall_ui(commands: &mut Commands, profile: &Profile, font_assets: &FontAssets) {
+  let text_font = |font_size: f32| TextFont {
+    font: font_assets.regular.clone(),
+    font_size,
+    ..Default::default()
+  };
+
+  let unlocked_count = ACHIEVEMENTS
+    .iter()
+    .filter(|achievement| profile.achievement_progress(achievement.id) >= achievement.goal)
+    .count();
+
+  commands
+    .spawn((
+      Name::new("TrophyWallUi"),
+      TrophyWallUi,
+      DespawnOnExit(ContextScope::Lobby),
+      Node {
+        width: percent(100),
+        height: percent(100),
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..default()
+      },
+      BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+    ))
+    .with_children(|root| {
+      root
+        .spawn((
+          Node {
+            width: percent(60),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(8.),
+            padding: UiRect::all(px(16.)),
+            border: UiRect::all(px(2.)),
+            ..default()
+          },
+          BackgroundColor(Color::srgba(0.05, 0.05, 0.1, 0.95)),
+          BorderColor::all(Color::srgb(1.0, 0.85, 0.3)),
+        ))
+        .with_children(|panel| {
+          panel.spawn((
+            Text::new(format!(
+              "Trophies {}/{}",
+              unlocked_count,
+              ACHIEVEMENTS.len()
+            )),
+            text_font(32.),
+            TextColor(Color::srgb(1.0, 0.85, 0.3)),
+          ));
+
+          for achievement in ACHIEVEMENTS {
+            let progress = profile.achievement_progress(achievement.id);
+            let is_unlocked = progress >= achievement.goal;
+
+            let (name, description) = if achievement.hidden && !is_unlocked {
+              ("???", "Hidden achievement")
+            } else {
+              (achievement.name, achievement.description)
+            };
+
+            let color = if is_unlocked {
+              Color::WHITE
+            } else {
+              Color::srgb(0.5, 0.5, 0.5)
+            };
+
+            panel.spawn((
+              Text::new(format!(
+                "{name} - {description} ({progress}/{})",
+                achievement.goal
+              )),
+              text_font(22.),
+              TextColor(color),
+            ));
+          }
+        });
+    });
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+
+pub struct WalkAnimationPlugin;
+
+impl Plugin for WalkAnimationPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<WalkCycleAtlas>();
+
+    app.add_systems(Update, animate_walk_cycle_system);
+  }
+}
+
+const ANIMATION_FRAME_COUNT: usize = 4usize;
+const ANIMATION_FRAME_SECONDS: f32 = 0.2;
+
+const WALK_CYCLE_FRAME_SIZE: UVec2 = UVec2::new(17, 27);
+
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementState {
+  #[default]
+  Idle,
+  Walking,
+}
+
+#[derive(Component, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FacingDirection {
+  #[default]
+  Down,
+  Up,
+  Left,
+  Right,
+}
+
+impl FacingDirection {
+  /// Vertical movement wins over horizontal, so diagonal walking shows the front/back sprites.
+  pub fn from_delta(delta: Vec2) -> Option<Self> {
+    if delta.y < 0.0 {
+      Some(FacingDirection::Down)
+    } else if delta.y > 0.0 {
+      Some(FacingDirection::Up)
+    } else if delta.x < 0.0 {
+      Some(FacingDirection::Left)
+    } else if delta.x > 0.0 {
+      Some(FacingDirection::Right)
+    } else {
+      None
+    }
+  }
+
+  fn animation_index_offset(&self) -> usize {
+    const DOWN_OFFSET: usize = 0usize;
+    const UP_OFFSET: usize = 1usize;
+    const LEFT_OFFSET: usize = 2usize;
+    const RIGHT_OFFSET: usize = 3usize;
+
+    match self {
+      FacingDirection::Down => DOWN_OFFSET,
+      FacingDirection::Up => UP_OFFSET,
+      FacingDirection::Left => LEFT_OFFSET,
+      FacingDirection::Right => RIGHT_OFFSET,
+    }
+  }
+}
+
+/// Animates a 4x4 walk-cycle sheet (columns are directions, rows are frames)
+/// such as `player/sheet.png`.
+#[derive(Component, Default)]
+#[require(MovementState, FacingDirection, AnimationTimer)]
+pub struct WalkAnimation {
+  frame_index: usize,
+}
+
+#[derive(Component, Deref, DerefMut)]
+struct AnimationTimer(Timer);
+
+impl Default for AnimationTimer {
+  fn default() -> Self {
+    AnimationTimer(Timer::from_seconds(
+      ANIMATION_FRAME_SECONDS,
+      TimerMode::Repeating,
+    ))
+  }
+}
+
+#[derive(Resource)]
+pub struct WalkCycleAtlas {
+  pub layout: Handle<TextureAtlasLayout>,
+}
+
+impl FromWorld for WalkCycleAtlas {
+  fn from_world(world: &mut World) -> Self {
+    let layout = TextureAtlasLayout::from_grid(
+      WALK_CYCLE_FRAME_SIZE,
+      ANIMATION_FRAME_COUNT as u32,
+      4,
+      Some(UVec2::splat(1)),
+      None,
+    );
+
+    let mut texture_atlas_layouts = world.resource_mut::<Assets<TextureAtlasLayout>>();
+
+    Self {
+      layout: texture_atlas_layouts.add(layout),
+    }
+  }
+}
+
+impl WalkCycleAtlas {
+  pub fn sprite(&self, texture_sheet: Handle<Image>) -> Sprite {
+    Sprite::from_atlas_image(
+      texture_sheet,
+      TextureAtlas {
+        layout: self.layout.clone(),
+        index: FacingDirection::Down.animation_index_offset(),
+      },
+    )
+  }
+}
+
+fn animate_walk_cycle_system(
+  time: Res<Time>,
+  mut query: Query<(
+    &mut AnimationTimer,
+    &mut Sprite,
+    &mut WalkAnimation,
+    &MovementState,
+    &FacingDirection,
+  )>,
+) {
+  for (mut timer, mut sprite, mut animation, movement_state, direction) in &mut query {
+    timer.tick(time.delta());
+
+    let Some(atlas) = &mut sprite.texture_atlas else {
+      continue;
+    };
+
+    match *movement_state {
+      MovementState::Idle => {
+        animation.frame_index = 0;
+        let offset = direction.animation_index_offset();
+        atlas.index = animation.frame_index * ANIMATION_FRAME_COUNT + offset;
+      }
+      MovementState::Walking => {
+        if timer.just_finished() {
+          animation.frame_index = if animation.frame_index == ANIMATION_FRAME_COUNT - 1 {
+            0
+          } else {
+            animation.frame_index + 1
+          };
+
+          let offset = direction.animation_index_offset();
+          atlas.index = animation.frame_index * ANIMATION_FRAME_COUNT + offset;
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,303 @@
+use bevy::{audio::Volume, prelude::*};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+  games::{CurrentGameState, GameType},
+  loading::{AssetCollection, AssetCollectionAppExt},
+  scope::Unscoped,
+  settings::Settings,
+  state::ContextScope,
+};
+
+const MUSIC_CROSSFADE_SECONDS: f32 = 1.5;
+const DEFAULT_MAX_INSTANCES: usize = 4;
+const PITCH_VARIATION_SEED: u64 = 42;
+
+pub struct AudioMixerPlugin;
+
+impl Plugin for AudioMixerPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_asset_collection::<MusicAssets>();
+    app.init_resource::<CurrentMusic>();
+    app.insert_resource(PitchVariationRng(StdRng::seed_from_u64(
+      PITCH_VARIATION_SEED,
+    )));
+
+    app.add_observer(play_sound_observer);
+    app.add_observer(play_music_observer);
+
+    app.add_systems(
+      Update,
+      (
+        switch_music_for_current_game_system.run_if(resource_changed::<CurrentGameState>),
+        music_crossfade_system,
+        apply_bus_volume_system.run_if(resource_changed::<Settings>),
+      ),
+    );
+  }
+}
+
+/// Mixer channel a sound belongs to. Each bus has its own volume in [`Settings`].
+#[derive(Component, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum AudioBus {
+  Music,
+  Sfx,
+  Ui,
+}
+
+/// Plays a one-shot sound that despawns itself when finished.
+#[derive(Event)]
+pub struct PlaySoundEvent {
+  pub sound: Handle<AudioSource>,
+  pub bus: AudioBus,
+  /// How many copies of this sound may play at once. Extra requests are dropped.
+  pub max_instances: usize,
+  /// Playback speed is picked from `1.0 ± pitch_variation`, so repeated sounds don't get tiring.
+  pub pitch_variation: f32,
+}
+
+impl PlaySoundEvent {
+  pub fn sfx(sound: Handle<AudioSource>) -> Self {
+    Self {
+      sound,
+      bus: AudioBus::Sfx,
+      max_instances: DEFAULT_MAX_INSTANCES,
+      pitch_variation: 0.0,
+    }
+  }
+
+  pub fn ui(sound: Handle<AudioSource>) -> Self {
+    Self {
+      sound,
+      bus: AudioBus::Ui,
+      max_instances: 1,
+      pitch_variation: 0.0,
+    }
+  }
+
+  pub fn with_pitch_variation(mut self, pitch_variation: f32) -> Self {
+    self.pitch_variation = pitch_variation;
+    self
+  }
+}
+
+/// Crossfades from the current music track to `track`. `None` fades the music out.
+#[derive(Event)]
+pub struct PlayMusicEvent {
+  pub track: Option<Handle<AudioSource>>,
+}
+
+/// Music for the lobby and for each cabinet. Missing tracks mean silence.
+#[derive(Resource)]
+pub struct MusicAssets {
+  pub lobby: Option<Handle<AudioSource>>,
+  pub snake: Option<Handle<AudioSource>>,
+}
+
+impl FromWorld for MusicAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      lobby: None,
+      snake: Some(asset_server.load("games/snake/sounds/muzicRandom.bfxr")),
+    }
+  }
+}
+
+impl AssetCollection for MusicAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    [&self.lobby, &self.snake]
+      .into_iter()
+      .flatten()
+      .map(|handle| handle.clone().untyped())
+      .collect()
+  }
+}
+
+impl MusicAssets {
+  fn track_for(&self, game: Option<GameType>) -> Option<Handle<AudioSource>> {
+    match game {
+      None => self.lobby.clone(),
+      Some(GameType::Snake) => self.snake.clone(),
+      // Своей музыки у тетриса, арканоида, пинбола и аэрохоккея пока нет
+      Some(GameType::Tetris | GameType::Breakout | GameType::Pinball | GameType::AirHockey) => None,
+    }
+  }
+}
+
+#[derive(Resource, Default)]
+struct CurrentMusic {
+  track: Option<Handle<AudioSource>>,
+}
+
+/// Seeded so the same sequence of sounds always gets the same pitches.
+#[derive(Resource)]
+struct PitchVariationRng(StdRng);
+
+#[derive(Component)]
+struct SoundInstance {
+  sound: AssetId<AudioSource>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MusicFade {
+  In,
+  Playing,
+  Out,
+}
+
+#[derive(Component)]
+struct MusicTrack {
+  fade: MusicFade,
+  timer: Timer,
+}
+
+impl MusicTrack {
+  fn fade(fade: MusicFade) -> Self {
+    Self {
+      fade,
+      timer: Timer::from_seconds(MUSIC_CROSSFADE_SECONDS, TimerMode::Once),
+    }
+  }
+
+  fn volume_factor(&self) -> f32 {
+    match self.fade {
+      MusicFade::In => self.timer.fraction(),
+      MusicFade::Playing => 1.0,
+      MusicFade::Out => 1.0 - self.timer.fraction(),
+    }
+  }
+}
+
+fn play_sound_observer(
+  event: On<PlaySoundEvent>,
+  mut commands: Commands,
+  mut rng: ResMut<PitchVariationRng>,
+  settings: Res<Settings>,
+  scope: Res<State<ContextScope>>,
+  sound_instance_query: Query<&SoundInstance>,
+) {
+  let sound = event.sound.id();
+
+  let playing = sound_instance_query
+    .iter()
+    .filter(|instance| instance.sound == sound)
+    .count();
+
+  if playing >= event.max_instances {
+    return;
+  }
+
+  let speed = if event.pitch_variation > 0.0 {
+    1.0
+      + rng
+        .0
+        .random_range(-event.pitch_variation..=event.pitch_variation)
+  } else {
+    1.0
+  };
+
+  let mut sound_entity = commands.spawn((
+    Name::new("Sound"),
+    event.bus,
+    SoundInstance { sound },
+    AudioPlayer::new(event.sound.clone()),
+    PlaybackSettings::DESPAWN
+      .with_volume(Volume::Linear(settings.bus_volume(event.bus)))
+      .with_speed(speed),
+  ));
+
+  // Звук интерфейса часто играет на том же нажатии, что и закрывает контекст
+  if event.bus == AudioBus::Ui {
+    sound_entity.insert(Unscoped);
+  } else {
+    sound_entity.insert(DespawnOnExit(*scope.get()));
+  }
+}
+
+fn play_music_observer(
+  event: On<PlayMusicEvent>,
+  mut commands: Commands,
+  mut current_music: ResMut<CurrentMusic>,
+  mut music_track_query: Query<&mut MusicTrack>,
+) {
+  if current_music.track == event.track {
+    return;
+  }
+
+  // Недоигравший fade-in трек затухает с текущей громкости, а не с полной
+  for mut track in &mut music_track_query {
+    if track.fade != MusicFade::Out {
+      let remaining = track.volume_factor();
+      *track = MusicTrack::fade(MusicFade::Out);
+      let duration = track.timer.duration();
+      track
+        .timer
+        .set_elapsed(duration.mul_f32(1.0 - remaining));
+    }
+  }
+
+  current_music.track = event.track.clone();
+
+  let Some(handle) = event.track.clone() else {
+    return;
+  };
+
+  commands.spawn((
+    Name::new("Music"),
+    AudioBus::Music,
+    // Треки лобби и мини-игр переходят друг в друга плавно
+    Unscoped,
+    MusicTrack::fade(MusicFade::In),
+    AudioPlayer::new(handle),
+    PlaybackSettings::LOOP.with_volume(Volume::SILENT),
+  ));
+}
+
+fn switch_music_for_current_game_system(
+  mut commands: Commands,
+  game_state: Res<CurrentGameState>,
+  music_assets: Res<MusicAssets>,
+) {
+  commands.trigger(PlayMusicEvent {
+    track: music_assets.track_for(game_state.current_game),
+  });
+}
+
+fn music_crossfade_system(
+  mut commands: Commands,
+  mut music_track_query: Query<(Entity, &mut MusicTrack, Option<&mut AudioSink>)>,
+  settings: Res<Settings>,
+  time: Res<Time>,
+) {
+  let bus_volume = settings.bus_volume(AudioBus::Music);
+
+  for (entity, mut track, sink) in &mut music_track_query {
+    track.timer.tick(time.delta());
+
+    if track.timer.is_finished() {
+      match track.fade {
+        MusicFade::In => track.fade = MusicFade::Playing,
+        MusicFade::Out => {
+          commands.entity(entity).despawn();
+          continue;
+        }
+        MusicFade::Playing => {}
+      }
+    }
+
+    if let Some(mut sink) = sink {
+      sink.set_volume(Volume::Linear(bus_volume * track.volume_factor()));
+    }
+  }
+}
+
+fn apply_bus_volume_system(
+  settings: Res<Settings>,
+  mut sink_query: Query<(&AudioBus, &mut AudioSink), Without<MusicTrack>>,
+) {
+  for (bus, mut sink) in &mut sink_query {
+    sink.set_volume(Volume::Linear(settings.bus_volume(*bus)));
+  }
+}
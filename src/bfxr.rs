@@ -0,0 +1,827 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use bevy::{
+  asset::{AssetLoader, LoadContext, io::Reader},
+  prelude::*,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::Deserialize;
+
+const SAMPLE_RATE: u32 = 44100;
+const SUPERSAMPLES: u32 = 8;
+const PHASER_BUFFER_LENGTH: usize = 1024;
+const NOISE_BUFFER_LENGTH: usize = 32;
+/// Noise is seeded so the same file always renders to the same samples.
+const NOISE_SEED: u64 = 0x5f3759df;
+
+/// Loads `.bfxr` files as [`AudioSource`]s by rendering them with an sfxr-style synthesizer.
+pub struct BfxrPlugin;
+
+impl Plugin for BfxrPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_asset_loader::<BfxrLoader>();
+  }
+}
+
+#[derive(Deserialize, Debug)]
+struct BfxrFile {
+  params: BfxrParams,
+}
+
+/// Parameters as Bfxr saves them. Almost all of them are in `0..=1`, slides and sweeps in `-1..=1`.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct BfxrParams {
+  #[serde(rename = "masterVolume")]
+  pub master_volume: f32,
+  #[serde(rename = "waveType")]
+  pub wave_type: u32,
+  #[serde(rename = "attackTime")]
+  pub attack_time: f32,
+  #[serde(rename = "sustainTime")]
+  pub sustain_time: f32,
+  #[serde(rename = "sustainPunch")]
+  pub sustain_punch: f32,
+  #[serde(rename = "decayTime")]
+  pub decay_time: f32,
+  #[serde(rename = "compressionAmount")]
+  pub compression_amount: f32,
+  pub frequency_start: f32,
+  pub frequency_slide: f32,
+  pub frequency_acceleration: f32,
+  pub min_frequency_relative_to_starting_frequency: f32,
+  #[serde(rename = "vibratoDepth")]
+  pub vibrato_depth: f32,
+  #[serde(rename = "vibratoSpeed")]
+  pub vibrato_speed: f32,
+  pub pitch_jump_repeat_speed: f32,
+  pub pitch_jump_amount: f32,
+  pub pitch_jump_onset_percent: f32,
+  pub pitch_jump_2_amount: f32,
+  pub pitch_jump_onset2_percent: f32,
+  pub overtones: f32,
+  #[serde(rename = "overtoneFalloff")]
+  pub overtone_falloff: f32,
+  #[serde(rename = "squareDuty")]
+  pub square_duty: f32,
+  #[serde(rename = "dutySweep")]
+  pub duty_sweep: f32,
+  #[serde(rename = "repeatSpeed")]
+  pub repeat_speed: f32,
+  #[serde(rename = "flangerOffset")]
+  pub flanger_offset: f32,
+  #[serde(rename = "flangerSweep")]
+  pub flanger_sweep: f32,
+  #[serde(rename = "lpFilterCutoff")]
+  pub lp_filter_cutoff: f32,
+  #[serde(rename = "lpFilterCutoffSweep")]
+  pub lp_filter_cutoff_sweep: f32,
+  #[serde(rename = "lpFilterResonance")]
+  pub lp_filter_resonance: f32,
+  #[serde(rename = "hpFilterCutoff")]
+  pub hp_filter_cutoff: f32,
+  #[serde(rename = "hpFilterCutoffSweep")]
+  pub hp_filter_cutoff_sweep: f32,
+  #[serde(rename = "bitCrush")]
+  pub bit_crush: f32,
+  #[serde(rename = "bitCrushSweep")]
+  pub bit_crush_sweep: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WaveType {
+  Square,
+  Saw,
+  Sine,
+  WhiteNoise,
+  Triangle,
+  PinkNoise,
+  Tan,
+  Whistle,
+  Breaker,
+  BitNoise,
+  /// 1-bit noise with the short feedback loop of the NES noise channel, which repeats often
+  /// enough to sound like a metallic buzz.
+  Buzz,
+}
+
+impl WaveType {
+  fn from_index(index: u32) -> Option<Self> {
+    Some(match index {
+      0 => WaveType::Square,
+      1 => WaveType::Saw,
+      2 => WaveType::Sine,
+      3 => WaveType::WhiteNoise,
+      4 => WaveType::Triangle,
+      5 => WaveType::PinkNoise,
+      6 => WaveType::Tan,
+      7 => WaveType::Whistle,
+      8 => WaveType::Breaker,
+      9 => WaveType::BitNoise,
+      10 => WaveType::Buzz,
+      _ => return None,
+    })
+  }
+}
+
+/// Renders `params` to mono samples in `-1..=1` at 44.1 kHz. A wave type this synthesizer
+/// doesn't know is an error rather than silently played as another wave.
+pub fn synthesize(params: &BfxrParams) -> Result<Vec<f32>, BfxrError> {
+  let wave_type =
+    WaveType::from_index(params.wave_type).ok_or(BfxrError::WaveType(params.wave_type))?;
+
+  Ok(Synth::new(params, wave_type).render())
+}
+
+/// Wraps mono samples into a 16-bit PCM WAV file that the audio backend can decode.
+pub fn encode_wav(samples: &[f32]) -> Vec<u8> {
+  const CHANNELS: u16 = 1;
+  const BITS_PER_SAMPLE: u16 = 16;
+  const BLOCK_ALIGN: u16 = CHANNELS * BITS_PER_SAMPLE / 8;
+
+  let data_length = samples.len() as u32 * BLOCK_ALIGN as u32;
+  let mut bytes = Vec::with_capacity(44 + data_length as usize);
+
+  bytes.extend_from_slice(b"RIFF");
+  bytes.extend_from_slice(&(36 + data_length).to_le_bytes());
+  bytes.extend_from_slice(b"WAVE");
+
+  bytes.extend_from_slice(b"fmt ");
+  bytes.extend_from_slice(&16u32.to_le_bytes());
+  bytes.extend_from_slice(&1u16.to_le_bytes());
+  bytes.extend_from_slice(&CHANNELS.to_le_bytes());
+  bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+  bytes.extend_from_slice(&(SAMPLE_RATE * BLOCK_ALIGN as u32).to_le_bytes());
+  bytes.extend_from_slice(&BLOCK_ALIGN.to_le_bytes());
+  bytes.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+
+  bytes.extend_from_slice(b"data");
+  bytes.extend_from_slice(&data_length.to_le_bytes());
+  for sample in samples {
+    let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+    bytes.extend_from_slice(&sample.to_le_bytes());
+  }
+
+  bytes
+}
+
+/// Port of the sfxr/Bfxr synthesis loop. Periods are measured in supersamples.
+struct Synth<'a> {
+  params: &'a BfxrParams,
+  wave_type: WaveType,
+  rng: StdRng,
+
+  period: f64,
+  max_period: f64,
+  slide: f64,
+  delta_slide: f64,
+
+  square_duty: f32,
+  duty_sweep: f32,
+
+  change_amount: f64,
+  change_time: u32,
+  change_limit: u32,
+  change_reached: bool,
+  change_amount_2: f64,
+  change_time_2: u32,
+  change_limit_2: u32,
+  change_reached_2: bool,
+  change_period: u32,
+  change_period_time: u32,
+
+  vibrato_phase: f32,
+  vibrato_speed: f32,
+  vibrato_amplitude: f32,
+
+  envelope_lengths: [u32; 3],
+  envelope_stage: usize,
+  envelope_time: u32,
+
+  phaser_on: bool,
+  phaser_offset: f32,
+  phaser_delta_offset: f32,
+  phaser_buffer: [f32; PHASER_BUFFER_LENGTH],
+  phaser_position: usize,
+
+  lp_filter_position: f32,
+  lp_filter_delta_position: f32,
+  lp_filter_cutoff: f32,
+  lp_filter_delta_cutoff: f32,
+  lp_filter_damping: f32,
+  lp_filter_on: bool,
+
+  hp_filter_position: f32,
+  hp_filter_cutoff: f32,
+  hp_filter_delta_cutoff: f32,
+
+  overtones: u32,
+  overtone_falloff: f32,
+
+  bit_crush_frequency: f32,
+  bit_crush_frequency_sweep: f32,
+  bit_crush_phase: f32,
+  bit_crush_last: f32,
+
+  compression_factor: f32,
+
+  noise_buffer: [f32; NOISE_BUFFER_LENGTH],
+  pink_noise: [f32; 7],
+  bit_noise_register: u32,
+
+  phase: u32,
+  repeat_time: u32,
+  repeat_limit: u32,
+}
+
+impl<'a> Synth<'a> {
+  fn new(params: &'a BfxrParams, wave_type: WaveType) -> Self {
+    let mut synth = Self {
+      params,
+      wave_type,
+      rng: StdRng::seed_from_u64(NOISE_SEED),
+      period: 0.0,
+      max_period: 0.0,
+      slide: 0.0,
+      delta_slide: 0.0,
+      square_duty: 0.0,
+      duty_sweep: 0.0,
+      change_amount: 1.0,
+      change_time: 0,
+      change_limit: 0,
+      change_reached: false,
+      change_amount_2: 1.0,
+      change_time_2: 0,
+      change_limit_2: 0,
+      change_reached_2: false,
+      change_period: 0,
+      change_period_time: 0,
+      vibrato_phase: 0.0,
+      vibrato_speed: 0.0,
+      vibrato_amplitude: 0.0,
+      envelope_lengths: [0; 3],
+      envelope_stage: 0,
+      envelope_time: 0,
+      phaser_on: false,
+      phaser_offset: 0.0,
+      phaser_delta_offset: 0.0,
+      phaser_buffer: [0.0; PHASER_BUFFER_LENGTH],
+      phaser_position: 0,
+      lp_filter_position: 0.0,
+      lp_filter_delta_position: 0.0,
+      lp_filter_cutoff: 0.0,
+      lp_filter_delta_cutoff: 0.0,
+      lp_filter_damping: 0.0,
+      lp_filter_on: false,
+      hp_filter_position: 0.0,
+      hp_filter_cutoff: 0.0,
+      hp_filter_delta_cutoff: 0.0,
+      overtones: 0,
+      overtone_falloff: 0.0,
+      bit_crush_frequency: 0.0,
+      bit_crush_frequency_sweep: 0.0,
+      bit_crush_phase: 0.0,
+      bit_crush_last: 0.0,
+      compression_factor: 1.0,
+      noise_buffer: [0.0; NOISE_BUFFER_LENGTH],
+      pink_noise: [0.0; 7],
+      bit_noise_register: 0x7fff,
+      phase: 0,
+      repeat_time: 0,
+      repeat_limit: 0,
+    };
+
+    synth.reset(true);
+    synth
+  }
+
+  /// Resets pitch state. A full reset also restarts envelope, filters and effects; a partial one
+  /// is what the repeat parameter triggers mid-sound.
+  fn reset(&mut self, total: bool) {
+    let p = self.params;
+
+    self.period = 100.0 / ((p.frequency_start as f64).powi(2) + 0.001);
+    self.max_period =
+      100.0 / ((p.min_frequency_relative_to_starting_frequency as f64).powi(2) + 0.001);
+
+    self.slide = 1.0 - (p.frequency_slide as f64).powi(3) * 0.01;
+    self.delta_slide = -(p.frequency_acceleration as f64).powi(3) * 0.000001;
+
+    if self.wave_type == WaveType::Square {
+      self.square_duty = 0.5 - p.square_duty * 0.5;
+      self.duty_sweep = -p.duty_sweep * 0.00005;
+    }
+
+    self.change_period = ((1.0 - p.pitch_jump_repeat_speed + 0.1) / 1.1 * 20000.0 + 32.0) as u32;
+    self.change_period_time = 0;
+
+    self.change_amount = jump_multiplier(p.pitch_jump_amount);
+    self.change_amount_2 = jump_multiplier(p.pitch_jump_2_amount);
+    self.change_time = 0;
+    self.change_time_2 = 0;
+    self.change_reached = false;
+    self.change_reached_2 = false;
+    self.change_limit = jump_limit(
+      p.pitch_jump_amount,
+      p.pitch_jump_onset_percent,
+      self.change_period,
+    );
+    self.change_limit_2 = jump_limit(
+      p.pitch_jump_2_amount,
+      p.pitch_jump_onset2_percent,
+      self.change_period,
+    );
+
+    if !total {
+      return;
+    }
+
+    self.phase = 0;
+
+    self.lp_filter_position = 0.0;
+    self.lp_filter_delta_position = 0.0;
+    self.lp_filter_cutoff = p.lp_filter_cutoff.powi(3) * 0.1;
+    self.lp_filter_delta_cutoff = 1.0 + p.lp_filter_cutoff_sweep * 0.0001;
+    self.lp_filter_damping = (5.0 / (1.0 + p.lp_filter_resonance.powi(2) * 20.0)
+      * (0.01 + self.lp_filter_cutoff))
+      .min(0.8);
+    self.lp_filter_damping = 1.0 - self.lp_filter_damping;
+    self.lp_filter_on = p.lp_filter_cutoff != 1.0;
+
+    self.hp_filter_position = 0.0;
+    self.hp_filter_cutoff = p.hp_filter_cutoff.powi(2) * 0.1;
+    self.hp_filter_delta_cutoff = 1.0 + p.hp_filter_cutoff_sweep * 0.0003;
+
+    self.vibrato_phase = 0.0;
+    self.vibrato_speed = p.vibrato_speed.powi(2) * 0.01;
+    self.vibrato_amplitude = p.vibrato_depth * 0.5;
+
+    self.envelope_stage = 0;
+    self.envelope_time = 0;
+    self.envelope_lengths = [
+      (p.attack_time.powi(2) * 100000.0) as u32,
+      (p.sustain_time.powi(2) * 100000.0) as u32,
+      (p.decay_time.powi(2) * 100000.0) as u32 + 10,
+    ];
+
+    self.phaser_on = p.flanger_offset != 0.0 || p.flanger_sweep != 0.0;
+    self.phaser_offset = p.flanger_offset.powi(2) * 1020.0 * p.flanger_offset.signum();
+    self.phaser_delta_offset = p.flanger_sweep.powi(2) * p.flanger_sweep.signum();
+    self.phaser_position = 0;
+    self.phaser_buffer = [0.0; PHASER_BUFFER_LENGTH];
+
+    self.overtones = (p.overtones * 10.0) as u32;
+    self.overtone_falloff = p.overtone_falloff;
+
+    self.bit_crush_frequency = 1.0 - p.bit_crush.powf(1.0 / 3.0);
+    self.bit_crush_frequency_sweep = -p.bit_crush_sweep * 0.000015;
+    self.bit_crush_phase = 0.0;
+    self.bit_crush_last = 0.0;
+
+    self.compression_factor = 1.0 / (1.0 + 4.0 * p.compression_amount);
+
+    self.regenerate_noise();
+    self.pink_noise = [0.0; 7];
+    self.bit_noise_register = 0x7fff;
+
+    self.repeat_time = 0;
+    self.repeat_limit = if p.repeat_speed == 0.0 {
+      0
+    } else {
+      ((1.0 - p.repeat_speed).powi(2) * 20000.0 + 32.0) as u32
+    };
+  }
+
+  fn regenerate_noise(&mut self) {
+    for value in &mut self.noise_buffer {
+      *value = self.rng.random_range(-1.0..=1.0);
+    }
+  }
+
+  fn render(mut self) -> Vec<f32> {
+    let mut samples = Vec::new();
+
+    while let Some(sample) = self.next_sample() {
+      samples.push(sample);
+    }
+
+    samples
+  }
+
+  fn next_sample(&mut self) -> Option<f32> {
+    self.repeat_time += 1;
+    if self.repeat_limit != 0 && self.repeat_time >= self.repeat_limit {
+      self.reset(false);
+      self.repeat_time = 0;
+    }
+
+    self.update_pitch_jumps();
+
+    self.slide += self.delta_slide;
+    self.period *= self.slide;
+
+    if self.period > self.max_period {
+      self.period = self.max_period;
+      if self
+        .params
+        .min_frequency_relative_to_starting_frequency
+        > 0.0
+      {
+        return None;
+      }
+    }
+
+    let mut period = self.period as f32;
+    if self.vibrato_amplitude > 0.0 {
+      self.vibrato_phase += self.vibrato_speed;
+      period *= 1.0 + self.vibrato_phase.sin() * self.vibrato_amplitude;
+    }
+    let period = (period as u32).max(8);
+
+    if self.wave_type == WaveType::Square {
+      self.square_duty = (self.square_duty + self.duty_sweep).clamp(0.0, 0.5);
+    }
+
+    let envelope_volume = self.update_envelope()?;
+
+    self.phaser_offset += self.phaser_delta_offset;
+    let phaser_offset = (self.phaser_offset.abs() as usize).min(PHASER_BUFFER_LENGTH - 1);
+
+    if self.hp_filter_delta_cutoff != 1.0 {
+      self.hp_filter_cutoff =
+        (self.hp_filter_cutoff * self.hp_filter_delta_cutoff).clamp(0.00001, 0.1);
+    }
+
+    let mut super_sample = 0.0;
+
+    for _ in 0..SUPERSAMPLES {
+      self.phase += 1;
+      if self.phase >= period {
+        self.phase %= period;
+        self.advance_noise();
+      }
+
+      let position = self.phase as f32 / period as f32;
+      let mut sample = self.oscillate(position);
+
+      let previous_lp_position = self.lp_filter_position;
+      self.lp_filter_cutoff = (self.lp_filter_cutoff * self.lp_filter_delta_cutoff).clamp(0.0, 0.1);
+
+      if self.lp_filter_on {
+        self.lp_filter_delta_position += (sample - self.lp_filter_position) * self.lp_filter_cutoff;
+        self.lp_filter_delta_position *= self.lp_filter_damping;
+      } else {
+        self.lp_filter_position = sample;
+        self.lp_filter_delta_position = 0.0;
+      }
+      self.lp_filter_position += self.lp_filter_delta_position;
+
+      self.hp_filter_position += self.lp_filter_position - previous_lp_position;
+      self.hp_filter_position *= 1.0 - self.hp_filter_cutoff;
+      sample = self.hp_filter_position;
+
+      if self.phaser_on {
+        self.phaser_buffer[self.phaser_position] = sample;
+        sample += self.phaser_buffer
+          [(self.phaser_position + PHASER_BUFFER_LENGTH - phaser_offset) % PHASER_BUFFER_LENGTH];
+        self.phaser_position = (self.phaser_position + 1) % PHASER_BUFFER_LENGTH;
+      }
+
+      super_sample += sample;
+    }
+
+    super_sample *= envelope_volume * self.params.master_volume / SUPERSAMPLES as f32;
+
+    self.bit_crush_phase += self.bit_crush_frequency;
+    self.bit_crush_frequency =
+      (self.bit_crush_frequency + self.bit_crush_frequency_sweep).clamp(0.0, 1.0);
+    if self.bit_crush_phase >= 1.0 {
+      self.bit_crush_phase = 0.0;
+      self.bit_crush_last = super_sample;
+    }
+    super_sample = self.bit_crush_last;
+
+    if self.compression_factor != 1.0 {
+      super_sample = super_sample.signum()
+        * super_sample
+          .abs()
+          .powf(self.compression_factor);
+    }
+
+    Some(super_sample.clamp(-1.0, 1.0))
+  }
+
+  fn update_pitch_jumps(&mut self) {
+    self.change_period_time += 1;
+    if self.change_period_time >= self.change_period {
+      self.change_period_time = 0;
+      self.change_time = 0;
+      self.change_time_2 = 0;
+
+      // Повтор скачка: сначала откатываем уже применённые множители
+      if self.change_reached {
+        self.period /= self.change_amount;
+        self.change_reached = false;
+      }
+      if self.change_reached_2 {
+        self.period /= self.change_amount_2;
+        self.change_reached_2 = false;
+      }
+    }
+
+    if !self.change_reached && self.change_limit != 0 {
+      self.change_time += 1;
+      if self.change_time >= self.change_limit {
+        self.change_reached = true;
+        self.period *= self.change_amount;
+      }
+    }
+
+    if !self.change_reached_2 && self.change_limit_2 != 0 {
+      self.change_time_2 += 1;
+      if self.change_time_2 >= self.change_limit_2 {
+        self.change_reached_2 = true;
+        self.period *= self.change_amount_2;
+      }
+    }
+  }
+
+  /// Returns `None` once the decay stage is over.
+  fn update_envelope(&mut self) -> Option<f32> {
+    self.envelope_time += 1;
+    while self.envelope_time > self.envelope_lengths[self.envelope_stage] {
+      self.envelope_time = 0;
+      self.envelope_stage += 1;
+      if self.envelope_stage == self.envelope_lengths.len() {
+        return None;
+      }
+    }
+
+    // Стадия нулевой длины (атака или удержание 0) сразу считается пройденной
+    let length = self.envelope_lengths[self.envelope_stage];
+    let progress = if length == 0 {
+      1.0
+    } else {
+      self.envelope_time as f32 / length as f32
+    };
+
+    Some(match self.envelope_stage {
+      0 => progress,
+      1 => 1.0 + (1.0 - progress) * 2.0 * self.params.sustain_punch,
+      _ => 1.0 - progress,
+    })
+  }
+
+  fn advance_noise(&mut self) {
+    match self.wave_type {
+      WaveType::WhiteNoise | WaveType::PinkNoise => self.regenerate_noise(),
+      WaveType::BitNoise => {
+        // 15-битный LFSR, как в шумовом канале NES
+        let feedback = (self.bit_noise_register ^ (self.bit_noise_register >> 1)) & 1;
+        self.bit_noise_register = (self.bit_noise_register >> 1) | (feedback << 14);
+      }
+      WaveType::Buzz => {
+        // Короткий режим NES: обратная связь с шестого бита, период 93 шага
+        let feedback = (self.bit_noise_register ^ (self.bit_noise_register >> 6)) & 1;
+        self.bit_noise_register = (self.bit_noise_register >> 1) | (feedback << 14);
+      }
+      _ => {}
+    }
+  }
+
+  fn oscillate(&mut self, position: f32) -> f32 {
+    let mut sample = 0.0;
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+
+    for overtone in 0..=self.overtones {
+      let overtone_position = (position * (overtone + 1) as f32).fract();
+      sample += amplitude * self.wave(overtone_position);
+      total_amplitude += amplitude;
+      amplitude *= self.overtone_falloff;
+    }
+
+    sample / total_amplitude
+  }
+
+  fn wave(&mut self, position: f32) -> f32 {
+    match self.wave_type {
+      WaveType::Square => {
+        if position < self.square_duty {
+          0.5
+        } else {
+          -0.5
+        }
+      }
+      WaveType::Saw => 1.0 - position * 2.0,
+      WaveType::Sine => (position * 2.0 * PI).sin(),
+      WaveType::WhiteNoise => {
+        self.noise_buffer[(position * NOISE_BUFFER_LENGTH as f32) as usize % NOISE_BUFFER_LENGTH]
+      }
+      WaveType::Triangle => (1.0 - position * 2.0).abs() * 2.0 - 1.0,
+      WaveType::PinkNoise => {
+        let white =
+          self.noise_buffer[(position * NOISE_BUFFER_LENGTH as f32) as usize % NOISE_BUFFER_LENGTH];
+        self.pink(white)
+      }
+      WaveType::Tan => (PI * position).tan().clamp(-1.0, 1.0) * 0.5,
+      WaveType::Whistle => 0.75 * (position * 2.0 * PI).sin() + 0.25 * (position * 40.0 * PI).sin(),
+      WaveType::Breaker => {
+        let amplitude = (position + 0.75f32.sqrt()).fract();
+        -1.0 + 2.0 * (1.0 - amplitude * amplitude).abs()
+      }
+      WaveType::BitNoise | WaveType::Buzz => {
+        if self.bit_noise_register & 1 == 1 {
+          0.5
+        } else {
+          -0.5
+        }
+      }
+    }
+  }
+
+  /// Paul Kellet's economy pink noise filter.
+  fn pink(&mut self, white: f32) -> f32 {
+    let b = &mut self.pink_noise;
+    b[0] = 0.99886 * b[0] + white * 0.0555179;
+    b[1] = 0.99332 * b[1] + white * 0.0750759;
+    b[2] = 0.96900 * b[2] + white * 0.153852;
+    b[3] = 0.86650 * b[3] + white * 0.3104856;
+    b[4] = 0.55000 * b[4] + white * 0.5329522;
+    b[5] = -0.7616 * b[5] - white * 0.0168980;
+    let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+    b[6] = white * 0.115926;
+    pink * 0.11
+  }
+}
+
+fn jump_multiplier(amount: f32) -> f64 {
+  let amount = amount as f64;
+  if amount > 0.0 {
+    1.0 - amount.powi(2) * 0.9
+  } else {
+    1.0 + amount.powi(2) * 10.0
+  }
+}
+
+/// Samples until a pitch jump kicks in. Zero means the jump is disabled.
+fn jump_limit(amount: f32, onset_percent: f32, change_period: u32) -> u32 {
+  if amount == 0.0 {
+    return 0;
+  }
+
+  ((onset_percent * change_period as f32) as u32).max(1)
+}
+
+#[derive(Default, TypePath)]
+struct BfxrLoader;
+
+#[derive(Debug)]
+pub enum BfxrError {
+  Io(std::io::Error),
+  Json(serde_json::Error),
+  WaveType(u32),
+}
+
+impl std::fmt::Display for BfxrError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BfxrError::Io(error) => write!(f, "Could not read bfxr file: {error}"),
+      BfxrError::Json(error) => write!(f, "Could not parse bfxr file: {error}"),
+      BfxrError::WaveType(index) => write!(f, "Unsupported bfxr wave type {index}"),
+    }
+  }
+}
+
+impl std::error::Error for BfxrError {}
+
+impl From<std::io::Error> for BfxrError {
+  fn from(error: std::io::Error) -> Self {
+    BfxrError::Io(error)
+  }
+}
+
+impl From<serde_json::Error> for BfxrError {
+  fn from(error: serde_json::Error) -> Self {
+    BfxrError::Json(error)
+  }
+}
+
+impl AssetLoader for BfxrLoader {
+  type Asset = AudioSource;
+  type Settings = ();
+  type Error = BfxrError;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    _load_context: &mut LoadContext<'_>,
+  ) -> Result<Self::Asset, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    let file: BfxrFile = serde_json::from_slice(&bytes)?;
+    let samples = synthesize(&file.params)?;
+
+    Ok(AudioSource {
+      bytes: Arc::from(encode_wav(&samples)),
+    })
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["bfxr"]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params(file: &str) -> BfxrParams {
+    serde_json::from_str::<BfxrFile>(file)
+      .unwrap()
+      .params
+  }
+
+  /// Every envelope stage plays one sample more than its length, except the first one.
+  fn envelope_samples(params: &BfxrParams) -> usize {
+    let synth = Synth::new(params, WaveType::from_index(params.wave_type).unwrap());
+    synth
+      .envelope_lengths
+      .iter()
+      .sum::<u32>() as usize
+      + 2
+  }
+
+  fn assert_audible(samples: &[f32]) {
+    assert!(
+      samples
+        .iter()
+        .all(|sample| sample.is_finite() && (-1.0..=1.0).contains(sample))
+    );
+
+    let peak = samples
+      .iter()
+      .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    assert!(peak > 0.01, "rendered sound is silent, peak {peak}");
+  }
+
+  #[test]
+  fn renders_hit() {
+    let params = params(include_str!("../assets/games/snake/sounds/Hit.bfxr"));
+
+    let samples = synthesize(&params).unwrap();
+
+    // Без нижней границы частоты звук длится ровно всю огибающую
+    assert_eq!(samples.len(), envelope_samples(&params));
+    assert_audible(&samples);
+  }
+
+  #[test]
+  fn renders_buzz_music() {
+    let params = params(include_str!(
+      "../assets/games/snake/sounds/muzicRandom.bfxr"
+    ));
+    assert_eq!(WaveType::from_index(params.wave_type), Some(WaveType::Buzz));
+
+    let samples = synthesize(&params).unwrap();
+
+    assert_eq!(samples.len(), envelope_samples(&params));
+    assert_audible(&samples);
+  }
+
+  #[test]
+  fn rendering_is_deterministic() {
+    let params = params(include_str!(
+      "../assets/games/snake/sounds/enemy_deathBoom.bfxr"
+    ));
+
+    assert_eq!(synthesize(&params).unwrap(), synthesize(&params).unwrap());
+  }
+
+  #[test]
+  fn zero_attack_and_sustain_are_not_nan() {
+    let params = BfxrParams {
+      master_volume: 0.5,
+      frequency_start: 0.3,
+      lp_filter_cutoff: 1.0,
+      decay_time: 0.1,
+      ..default()
+    };
+
+    let samples = synthesize(&params).unwrap();
+
+    assert_audible(&samples);
+  }
+
+  #[test]
+  fn unknown_wave_type_is_an_error() {
+    let params = BfxrParams {
+      wave_type: 42,
+      ..default()
+    };
+
+    assert!(matches!(synthesize(&params), Err(BfxrError::WaveType(42))));
+  }
+}
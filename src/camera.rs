@@ -0,0 +1,162 @@
+use bevy::{
+  app::{HierarchyPropagatePlugin, Propagate},
+  camera::visibility::{Layer, RenderLayers},
+  prelude::*,
+  ui::UiSystems,
+};
+
+use crate::{scope::Unscoped, state::ContextScope};
+
+/// Layer of everything drawn on top of the current context, e.g. the pause screen.
+const OVERLAY_LAYER: Layer = 1;
+/// Minigames get a layer each, starting from this one.
+const FIRST_MINIGAME_LAYER: Layer = 2;
+/// Above any camera a context spawns.
+const OVERLAY_CAMERA_ORDER: isize = 100;
+
+/// Decides which cameras draw and what they see.
+///
+/// Every entity scoped to a context with `DespawnOnExit(scope)` is drawn on the render layers of
+/// that context, its children included, see [`context_layers`]. A camera scoped the same way
+/// sees only its context and is active only while the context is. The active camera with the
+/// highest order renders the UI.
+///
+/// Entities marked [`Overlay`] are drawn by a separate camera over whatever context is active,
+/// so a pause screen can cover a frozen minigame without touching its camera.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_plugins(HierarchyPropagatePlugin::<RenderLayers>::new(PostUpdate));
+
+    app
+      .add_observer(scope_render_layers_observer)
+      .add_observer(overlay_observer);
+
+    app.add_systems(Startup, spawn_overlay_camera);
+    app.add_systems(
+      PostUpdate,
+      (
+        activate_context_cameras_system,
+        activate_overlay_camera_system,
+      )
+        .before(UiSystems::Prepare),
+    );
+  }
+}
+
+/// Entity drawn over the current context by the overlay camera. UI nodes are rendered by it too.
+#[derive(Component, Default)]
+pub struct Overlay;
+
+#[derive(Component)]
+struct OverlayCamera;
+
+/// Render layers of the entities and cameras of a context. The lobby stays on the default layer:
+/// its map is spawned from Tiled and the loading screen is drawn there too.
+pub fn context_layers(scope: ContextScope) -> RenderLayers {
+  match scope {
+    ContextScope::None | ContextScope::Lobby => RenderLayers::default(),
+    ContextScope::Minigame(game) => RenderLayers::layer(FIRST_MINIGAME_LAYER + game as Layer),
+  }
+}
+
+fn spawn_overlay_camera(mut commands: Commands) {
+  commands.spawn((
+    Name::new("OverlayCamera"),
+    OverlayCamera,
+    Unscoped,
+    Camera2d,
+    Camera {
+      order: OVERLAY_CAMERA_ORDER,
+      clear_color: ClearColorConfig::None,
+      is_active: false,
+      ..Default::default()
+    },
+    RenderLayers::layer(OVERLAY_LAYER),
+  ));
+}
+
+/// Entities that already picked their layers, e.g. overlays, are left alone.
+fn scope_render_layers_observer(
+  add: On<Add, DespawnOnExit<ContextScope>>,
+  mut commands: Commands,
+  scope_query: Query<
+    &DespawnOnExit<ContextScope>,
+    (
+      Without<RenderLayers>,
+      Without<Propagate<RenderLayers>>,
+      Without<Overlay>,
+    ),
+  >,
+) {
+  let entity = add.event().entity;
+
+  let Ok(scope) = scope_query.get(entity) else {
+    return;
+  };
+
+  commands
+    .entity(entity)
+    .insert(Propagate(context_layers(scope.0)));
+}
+
+fn overlay_observer(
+  add: On<Add, Overlay>,
+  mut commands: Commands,
+  overlay_camera_single: Single<Entity, With<OverlayCamera>>,
+) {
+  commands
+    .entity(add.event().entity)
+    .insert((
+      Propagate(RenderLayers::layer(OVERLAY_LAYER)),
+      UiTargetCamera(*overlay_camera_single),
+    ));
+}
+
+/// A minigame spawns its camera in the frame it is launched, before the context switches, so the
+/// camera has to stay off until its context is entered.
+fn activate_context_cameras_system(
+  mut commands: Commands,
+  mut camera_query: Query<(
+    Entity,
+    &mut Camera,
+    &DespawnOnExit<ContextScope>,
+    Has<IsDefaultUiCamera>,
+  )>,
+  scope: Res<State<ContextScope>>,
+) {
+  let ui_camera = camera_query
+    .iter()
+    .filter(|(_, _, owner, _)| owner.0 == *scope.get())
+    .max_by_key(|(entity, camera, _, _)| (camera.order, *entity))
+    .map(|(entity, _, _, _)| entity);
+
+  for (entity, mut camera, owner, is_default_ui_camera) in &mut camera_query {
+    let is_active = owner.0 == *scope.get();
+    if camera.is_active != is_active {
+      camera.is_active = is_active;
+    }
+
+    let should_render_ui = ui_camera == Some(entity);
+    if should_render_ui && !is_default_ui_camera {
+      commands
+        .entity(entity)
+        .insert(IsDefaultUiCamera);
+    } else if !should_render_ui && is_default_ui_camera {
+      commands
+        .entity(entity)
+        .remove::<IsDefaultUiCamera>();
+    }
+  }
+}
+
+fn activate_overlay_camera_system(
+  mut overlay_camera_single: Single<&mut Camera, With<OverlayCamera>>,
+  overlay_query: Query<(), With<Overlay>>,
+) {
+  let is_active = !overlay_query.is_empty();
+  if overlay_camera_single.is_active != is_active {
+    overlay_camera_single.is_active = is_active;
+  }
+}
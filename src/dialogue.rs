@@ -0,0 +1,566 @@
+use std::collections::HashMap;
+
+use bevy::{
+  asset::{AssetLoader, LoadContext, io::Reader},
+  prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+  animation::MovementState, game::FontAssets, player::Player, profile::Profile, state::ContextScope,
+};
+
+const INTERACT_DISTANCE: f32 = 24.0;
+const TYPEWRITER_CHARS_PER_SECOND: f32 = 40.0;
+const MAX_BRANCH_REDIRECTS: usize = 16;
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_asset::<DialogueAsset>();
+    app.init_asset_loader::<DialogueLoader>();
+
+    app.init_resource::<ActiveDialogue>();
+
+    app.register_type::<DialogueSpeaker>();
+
+    app.add_observer(on_add_dialogue_speaker);
+
+    app.add_systems(
+      Update,
+      (
+        advance_dialogue_system.run_if(dialogue_is_active),
+        start_dialogue_system.run_if(not(dialogue_is_active)),
+        update_dialogue_ui_system,
+      )
+        .chain()
+        .in_set(DialogueSystems),
+    );
+  }
+}
+
+/// Dialogue input handling. Other systems reading the Interact key should run after it,
+/// since it consumes the key press it reacts to.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DialogueSystems;
+
+pub fn dialogue_is_active(active_dialogue: Res<ActiveDialogue>) -> bool {
+  active_dialogue.session.is_some()
+}
+
+/// Dialogue tree loaded from a `*.dialogue.ron` file.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct DialogueAsset {
+  pub start: String,
+  pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct DialogueNode {
+  pub speaker: Option<String>,
+  pub text: String,
+  /// Checked on entering the node: the first branch whose conditions hold redirects to its `next`.
+  pub branches: Vec<DialogueBranch>,
+  pub actions: Vec<DialogueAction>,
+  pub choices: Vec<DialogueChoice>,
+  /// Used when the node has no choices. `None` ends the dialogue.
+  pub next: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueBranch {
+  #[serde(default)]
+  pub conditions: Vec<DialogueCondition>,
+  pub next: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueChoice {
+  pub text: String,
+  #[serde(default)]
+  pub next: Option<String>,
+  #[serde(default)]
+  pub conditions: Vec<DialogueCondition>,
+  #[serde(default)]
+  pub actions: Vec<DialogueAction>,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum DialogueCondition {
+  Flag(String),
+  NotFlag(String),
+  BestScoreAtLeast { game: String, score: u32 },
+}
+
+impl DialogueCondition {
+  fn is_met(&self, profile: &Profile) -> bool {
+    match self {
+      DialogueCondition::Flag(flag) => profile.has_flag(flag),
+      DialogueCondition::NotFlag(flag) => !profile.has_flag(flag),
+      DialogueCondition::BestScoreAtLeast { game, score } => profile.best_score(game) >= *score,
+    }
+  }
+}
+
+fn all_conditions_met(conditions: &[DialogueCondition], profile: &Profile) -> bool {
+  conditions
+    .iter()
+    .all(|condition| condition.is_met(profile))
+}
+
+#[derive(Deserialize, Debug)]
+pub enum DialogueAction {
+  SetFlag(String),
+  ClearFlag(String),
+}
+
+impl DialogueAction {
+  fn apply(&self, profile: &mut Profile) {
+    match self {
+      DialogueAction::SetFlag(flag) => {
+        profile.flags.insert(flag.clone());
+      }
+      DialogueAction::ClearFlag(flag) => {
+        profile.flags.remove(flag);
+      }
+    }
+  }
+}
+
+#[derive(Default, TypePath)]
+struct DialogueLoader;
+
+#[derive(Debug)]
+enum DialogueLoaderError {
+  Io(std::io::Error),
+  Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for DialogueLoaderError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DialogueLoaderError::Io(error) => write!(f, "Could not read dialogue: {error}"),
+      DialogueLoaderError::Ron(error) => write!(f, "Could not parse dialogue: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for DialogueLoaderError {}
+
+impl From<std::io::Error> for DialogueLoaderError {
+  fn from(error: std::io::Error) -> Self {
+    DialogueLoaderError::Io(error)
+  }
+}
+
+impl From<ron::error::SpannedError> for DialogueLoaderError {
+  fn from(error: ron::error::SpannedError) -> Self {
+    DialogueLoaderError::Ron(error)
+  }
+}
+
+impl AssetLoader for DialogueLoader {
+  type Asset = DialogueAsset;
+  type Settings = ();
+  type Error = DialogueLoaderError;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    _load_context: &mut LoadContext<'_>,
+  ) -> Result<Self::Asset, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    Ok(ron::de::from_bytes(&bytes)?)
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["dialogue.ron"]
+  }
+}
+
+/// Tiled property: path of the dialogue asset opened when the player presses Interact nearby.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct DialogueSpeaker {
+  pub dialogue: String,
+}
+
+#[derive(Component, Deref)]
+struct DialogueHandle(Handle<DialogueAsset>);
+
+/// Marks the entity the player is currently talking to.
+#[derive(Component)]
+pub struct InDialogue;
+
+#[derive(Resource, Default)]
+pub struct ActiveDialogue {
+  session: Option<DialogueSession>,
+}
+
+struct DialogueSession {
+  dialogue: Handle<DialogueAsset>,
+  speaker_entity: Entity,
+  node: String,
+  visible_chars: f32,
+  selected_choice: usize,
+}
+
+#[derive(Component)]
+struct DialogueUi;
+
+#[derive(Component)]
+struct DialogueSpeakerText;
+
+#[derive(Component)]
+struct DialogueBodyText;
+
+#[derive(Component)]
+struct DialogueChoicesText;
+
+fn on_add_dialogue_speaker(
+  add: On<Add, DialogueSpeaker>,
+  mut commands: Commands,
+  query: Query<&DialogueSpeaker>,
+  asset_server: Res<AssetServer>,
+) {
+  let entity = add.event().entity;
+
+  let Ok(speaker) = query.get(entity) else {
+    return;
+  };
+
+  commands
+    .entity(entity)
+    .insert(DialogueHandle(asset_server.load(speaker.dialogue.clone())));
+}
+
+fn start_dialogue_system(
+  mut commands: Commands,
+  mut active_dialogue: ResMut<ActiveDialogue>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  mut profile: ResMut<Profile>,
+  dialogues: Res<Assets<DialogueAsset>>,
+  font_assets: Res<FontAssets>,
+  player: Single<&GlobalTransform, With<Player>>,
+  speaker_query: Query<(Entity, &GlobalTransform, &DialogueHandle)>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::KeyE) {
+    return;
+  }
+
+  let player_position = player.translation().truncate();
+
+  let nearest_speaker = speaker_query
+    .iter()
+    .map(|(entity, transform, handle)| {
+      let distance = transform
+        .translation()
+        .truncate()
+        .distance(player_position);
+      (entity, distance, handle)
+    })
+    .filter(|(_, distance, _)| *distance <= INTERACT_DISTANCE)
+    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+  let Some((speaker_entity, _, handle)) = nearest_speaker else {
+    return;
+  };
+
+  let Some(dialogue) = dialogues.get(&handle.0) else {
+    warn!("Dialogue for {:?} is not loaded yet", speaker_entity);
+    return;
+  };
+
+  keyboard_input.clear_just_pressed(KeyCode::KeyE);
+
+  let mut session = DialogueSession {
+    dialogue: handle.0.clone(),
+    speaker_entity,
+    node: dialogue.start.clone(),
+    visible_chars: 0.0,
+    selected_choice: 0,
+  };
+
+  if !enter_node(&mut session, dialogue, &dialogue.start, &mut profile) {
+    return;
+  }
+
+  commands
+    .entity(speaker_entity)
+    .insert((InDialogue, MovementState::Idle));
+
+  spawn_dialogue_ui(&mut commands, &font_assets);
+
+  active_dialogue.session = Some(session);
+}
+
+/// Moves the session to `node_id`, following branches and applying node actions.
+/// Returns `false` if the node does not exist.
+fn enter_node(
+  session: &mut DialogueSession,
+  dialogue: &DialogueAsset,
+  node_id: &str,
+  profile: &mut Profile,
+) -> bool {
+  let mut node_id = node_id.to_string();
+
+  for _ in 0..MAX_BRANCH_REDIRECTS {
+    let Some(node) = dialogue.nodes.get(&node_id) else {
+      error!("Dialogue node '{}' not found", node_id);
+      return false;
+    };
+
+    let redirect = node
+      .branches
+      .iter()
+      .find(|branch| all_conditions_met(&branch.conditions, profile));
+
+    if let Some(branch) = redirect {
+      node_id = branch.next.clone();
+      continue;
+    }
+
+    for action in node.actions.iter() {
+      action.apply(profile);
+    }
+
+    session.node = node_id;
+    session.visible_chars = 0.0;
+    session.selected_choice = 0;
+    return true;
+  }
+
+  error!("Too many branch redirects starting from '{}'", node_id);
+  false
+}
+
+fn available_choices<'a>(node: &'a DialogueNode, profile: &Profile) -> Vec<&'a DialogueChoice> {
+  node
+    .choices
+    .iter()
+    .filter(|choice| all_conditions_met(&choice.conditions, profile))
+    .collect()
+}
+
+fn advance_dialogue_system(
+  mut commands: Commands,
+  mut active_dialogue: ResMut<ActiveDialogue>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  mut profile: ResMut<Profile>,
+  dialogues: Res<Assets<DialogueAsset>>,
+  dialogue_ui_query: Query<Entity, With<DialogueUi>>,
+  time: Res<Time>,
+) {
+  let Some(session) = active_dialogue.session.as_mut() else {
+    return;
+  };
+
+  let node = dialogues
+    .get(&session.dialogue)
+    .and_then(|dialogue| dialogue.nodes.get(&session.node));
+
+  let Some(node) = node else {
+    end_dialogue(&mut commands, &mut active_dialogue, dialogue_ui_query);
+    return;
+  };
+
+  if keyboard_input.just_pressed(KeyCode::Escape) {
+    keyboard_input.clear_just_pressed(KeyCode::Escape);
+    end_dialogue(&mut commands, &mut active_dialogue, dialogue_ui_query);
+    return;
+  }
+
+  let text_length = node.text.chars().count() as f32;
+  session.visible_chars =
+    (session.visible_chars + TYPEWRITER_CHARS_PER_SECOND * time.delta_secs()).min(text_length);
+  let is_typing = session.visible_chars < text_length;
+
+  let choices = available_choices(node, &profile);
+
+  if !is_typing && !choices.is_empty() {
+    if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+      session.selected_choice = session
+        .selected_choice
+        .checked_sub(1)
+        .unwrap_or(choices.len() - 1);
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+      session.selected_choice = (session.selected_choice + 1) % choices.len();
+    }
+  }
+
+  const CONFIRM_INPUTS: [KeyCode; 3] = [KeyCode::KeyE, KeyCode::Enter, KeyCode::Space];
+
+  if !keyboard_input.any_just_pressed(CONFIRM_INPUTS) {
+    return;
+  }
+
+  for key in CONFIRM_INPUTS {
+    keyboard_input.clear_just_pressed(key);
+  }
+
+  if is_typing {
+    session.visible_chars = text_length;
+    return;
+  }
+
+  let next = match choices.get(session.selected_choice) {
+    Some(choice) => {
+      for action in choice.actions.iter() {
+        action.apply(&mut profile);
+      }
+      choice.next.clone()
+    }
+    None => node.next.clone(),
+  };
+
+  let dialogue = dialogues
+    .get(&session.dialogue)
+    .unwrap();
+
+  let entered = match next {
+    Some(next) => enter_node(session, dialogue, &next, &mut profile),
+    None => false,
+  };
+
+  if !entered {
+    end_dialogue(&mut commands, &mut active_dialogue, dialogue_ui_query);
+  }
+}
+
+fn end_dialogue(
+  commands: &mut Commands,
+  active_dialogue: &mut ActiveDialogue,
+  dialogue_ui_query: Query<Entity, With<DialogueUi>>,
+) {
+  if let Some(session) = active_dialogue.session.take() {
+    if let Ok(mut speaker) = commands.get_entity(session.speaker_entity) {
+      speaker.remove::<InDialogue>();
+    }
+  }
+
+  for entity in dialogue_ui_query.iter() {
+    commands.entity(entity).despawn();
+  }
+}
+
+fn spawn_dialogue_ui(commands: &mut Commands, font_assets: &FontAssets) {
+  let text_font = |font_size: f32| TextFont {
+    font: font_assets.regular.clone(),
+    font_size,
+    ..Default::default()
+  };
+
+  commands.spawn((
+    Name::new("DialogueUi"),
+    DialogueUi,
+    DespawnOnExit(ContextScope::Lobby),
+    Node {
+      width: percent(100),
+      height: percent(100),
+      flex_direction: FlexDirection::Column,
+      justify_content: JustifyContent::FlexEnd,
+      align_items: AlignItems::Center,
+      padding: UiRect::all(px(24.)),
+      ..default()
+    },
+    children![(
+      Node {
+        width: percent(80),
+        min_height: px(160.),
+        flex_direction: FlexDirection::Column,
+        row_gap: px(12.),
+        padding: UiRect::all(px(16.)),
+        border: UiRect::all(px(2.)),
+        ..default()
+      },
+      BackgroundColor(Color::srgba(0.05, 0.05, 0.1, 0.9)),
+      BorderColor::all(Color::WHITE),
+      children![
+        (
+          DialogueSpeakerText,
+          Text::default(),
+          text_font(24.),
+          TextColor(Color::srgb(1.0, 0.85, 0.3)),
+        ),
+        (
+          DialogueBodyText,
+          Text::default(),
+          text_font(28.),
+          TextColor(Color::WHITE),
+        ),
+        (
+          DialogueChoicesText,
+          Text::default(),
+          text_font(24.),
+          TextColor(Color::srgb(0.7, 0.9, 1.0)),
+        ),
+      ],
+    )],
+  ));
+}
+
+fn update_dialogue_ui_system(
+  active_dialogue: Res<ActiveDialogue>,
+  profile: Res<Profile>,
+  dialogues: Res<Assets<DialogueAsset>>,
+  mut speaker_text: Query<
+    &mut Text,
+    (
+      With<DialogueSpeakerText>,
+      Without<DialogueBodyText>,
+      Without<DialogueChoicesText>,
+    ),
+  >,
+  mut body_text: Query<&mut Text, (With<DialogueBodyText>, Without<DialogueChoicesText>)>,
+  mut choices_text: Query<&mut Text, With<DialogueChoicesText>>,
+) {
+  let Some(session) = active_dialogue.session.as_ref() else {
+    return;
+  };
+
+  let Some(node) = dialogues
+    .get(&session.dialogue)
+    .and_then(|dialogue| dialogue.nodes.get(&session.node))
+  else {
+    return;
+  };
+
+  if let Ok(mut text) = speaker_text.single_mut() {
+    text.0 = node.speaker.clone().unwrap_or_default();
+  }
+
+  if let Ok(mut text) = body_text.single_mut() {
+    text.0 = node
+      .text
+      .chars()
+      .take(session.visible_chars as usize)
+      .collect();
+  }
+
+  if let Ok(mut text) = choices_text.single_mut() {
+    let is_typing = session.visible_chars < node.text.chars().count() as f32;
+
+    text.0 = if is_typing {
+      String::new()
+    } else {
+      available_choices(node, &profile)
+        .iter()
+        .enumerate()
+        .map(|(index, choice)| {
+          let marker = if index == session.selected_choice {
+            ">"
+          } else {
+            " "
+          };
+          format!("{marker} {}", choice.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+    };
+  }
+}
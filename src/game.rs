@@ -9,8 +9,21 @@ use bevy_ecs_tiled::{
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
 
 use crate::{
+  achievements::AchievementsPlugin,
+  animation::WalkAnimationPlugin,
+  audio::AudioMixerPlugin,
+  bfxr::BfxrPlugin,
+  camera::CameraPlugin,
+  dialogue::DialoguePlugin,
   games::GamesPlugin,
+  hud::HudPlugin,
+  loading::{AssetCollection, AssetCollectionAppExt, LoadingPlugin},
+  npc::NpcPlugin,
   player::{Player, PlayerPlugin, SpawnPlayerMessage},
+  profile::ProfilePlugin,
+  scope::ScopePlugin,
+  settings::{Settings, SettingsPlugin},
+  state::{AppState, ContextScope},
   tilemap::{SpawnTilemapMessage, TilemapPlugin},
 };
 
@@ -18,7 +31,6 @@ const BACKGROUND_COLOR: Color = Color::srgb(0.1, 0.1, 0.1);
 
 pub fn run_game() {
   App::new()
-    .init_resource::<FontAssets>()
     .insert_resource(ClearColor(BACKGROUND_COLOR))
     .register_type::<ExitFromGameTriggerZone>()
     .add_observer(on_add_exit_from_game_trigger)
@@ -45,19 +57,81 @@ pub fn run_game() {
         .unwrap(),
       ),
     }))
+    .add_plugins(LoadingPlugin)
+    .add_plugins(ScopePlugin)
+    .add_plugins(CameraPlugin)
+    .init_asset_collection::<FontAssets>()
+    .init_asset_collection::<LobbyAssets>()
+    .add_plugins(ProfilePlugin)
+    .add_plugins(SettingsPlugin)
+    .add_plugins(BfxrPlugin)
+    .add_plugins(AudioMixerPlugin)
     .add_plugins(TilemapPlugin)
+    .add_plugins(WalkAnimationPlugin)
     .add_plugins(PlayerPlugin)
+    .add_plugins(NpcPlugin)
+    .add_plugins(DialoguePlugin)
     .add_plugins(GamesPlugin)
-    .add_systems(Startup, setup)
+    .add_plugins(HudPlugin)
+    .add_plugins(AchievementsPlugin)
+    .add_systems(OnEnter(AppState::Lobby), enter_lobby)
+    .add_systems(OnEnter(ContextScope::Lobby), setup)
+    .add_systems(
+      Update,
+      apply_lobby_camera_settings_system.run_if(resource_changed::<Settings>),
+    )
     .add_systems(PostUpdate, move_lobby_camera_to_player)
     .run();
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct FontAssets {
   pub regular: Handle<Font>,
 }
 
+impl FromWorld for FontAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      regular: asset_server.load("fonts/Pixixfont-Regular.otf"),
+    }
+  }
+}
+
+impl AssetCollection for FontAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![self.regular.clone().untyped()]
+  }
+}
+
+/// Everything the lobby needs on its first frame.
+#[derive(Resource)]
+pub struct LobbyAssets {
+  pub map: Handle<TiledMapAsset>,
+  pub player_sheet: Handle<Image>,
+}
+
+impl FromWorld for LobbyAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      map: asset_server.load("maps/map.tmx"),
+      player_sheet: asset_server.load("player/sheet.png"),
+    }
+  }
+}
+
+impl AssetCollection for LobbyAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![
+      self.map.clone().untyped(),
+      self.player_sheet.clone().untyped(),
+    ]
+  }
+}
+
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
 struct ExitFromGameTriggerZone {}
@@ -89,24 +163,42 @@ fn move_lobby_camera_to_player(
   camera.translation = player.translation;
 }
 
+fn apply_lobby_camera_settings_system(
+  settings: Res<Settings>,
+  mut projection: Single<&mut Projection, With<LobbyCamera>>,
+) {
+  let Projection::Orthographic(projection) = &mut **projection else {
+    return;
+  };
+
+  projection.scale = settings.camera_scale;
+  projection.scaling_mode = settings
+    .camera_scaling
+    .to_scaling_mode();
+}
+
+fn enter_lobby(mut next_scope: ResMut<NextState<ContextScope>>) {
+  next_scope.set(ContextScope::Lobby);
+}
+
+/// Spawns the lobby every time the player comes back to it. Everything spawned here,
+/// directly or through the messages, is despawned when a minigame is launched.
 fn setup(
   mut commands: Commands,
   mut spawn_tilemap_messages: MessageWriter<SpawnTilemapMessage>,
   mut spawn_player_messages: MessageWriter<SpawnPlayerMessage>,
-  mut font_assets: ResMut<FontAssets>,
-  asset_server: Res<AssetServer>,
+  settings: Res<Settings>,
 ) {
-  font_assets.regular = asset_server.load("fonts/Pixixfont-Regular.otf");
-
   let mut projection = OrthographicProjection::default_2d();
-  projection.scale = 0.3;
-  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
-    min_width: 1200.0,
-    min_height: 800.0,
-  };
+  projection.scale = settings.camera_scale;
+  projection.scaling_mode = settings
+    .camera_scaling
+    .to_scaling_mode();
 
   commands.spawn((
+    Name::new("LobbyCamera"),
     LobbyCamera,
+    DespawnOnExit(ContextScope::Lobby),
     Camera2d,
     Camera {
       ..Default::default()
@@ -0,0 +1,199 @@
+use avian2d::prelude::*;
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*, window::PrimaryWindow};
+
+use crate::state::GameState;
+
+use super::{
+  AIR_HOCKEY_SCOPE, AirHockeyCamera, AirHockeyGameState, AirHockeyLayer, Side, TABLE_SIZE,
+  opponent::{AiMallet, Difficulty},
+};
+
+pub(super) const MALLET_RADIUS: f32 = 10.0;
+const MALLET_RESTITUTION: f32 = 0.5;
+/// Top speed of a player's mallet. The AI's depends on its difficulty.
+const PLAYER_MALLET_SPEED: f32 = 520.0;
+/// Speed the keys move a mallet's target at.
+const KEY_SPEED: f32 = 220.0;
+
+/// Mallets are kinematic: they follow their [`MalletTarget`] at a limited speed and push the
+/// puck, but nothing pushes them back.
+pub(super) struct AirHockeyMalletPlugin;
+
+impl Plugin for AirHockeyMalletPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(OnEnter(AirHockeyGameState::Faceoff), reset_mallets_system)
+      .add_systems(
+        Update,
+        player_mallet_input_system.run_if(
+          in_state(AirHockeyGameState::Faceoff)
+            .or(in_state(AirHockeyGameState::Playing))
+            .and(not(in_state(GameState::Paused))),
+        ),
+      )
+      .add_systems(
+        FixedUpdate,
+        mallet_movement_system.run_if(in_state(AirHockeyGameState::Playing)),
+      )
+      .add_systems(OnExit(AirHockeyGameState::Playing), stop_mallets_system);
+  }
+}
+
+#[derive(Component)]
+pub(super) struct Mallet {
+  pub side: Side,
+  max_speed: f32,
+}
+
+impl Mallet {
+  /// Home of the mallet at a faceoff, in front of its goal.
+  pub fn home(&self) -> Vec2 {
+    Vec2::new(
+      self.side.sign() * (TABLE_SIZE.x / 2.0 - MALLET_RADIUS * 3.0),
+      0.0,
+    )
+  }
+
+  /// Nearest point of the mallet's half of the table to `point`.
+  pub fn confine(&self, point: Vec2) -> Vec2 {
+    let half = TABLE_SIZE / 2.0 - MALLET_RADIUS;
+    let (min_x, max_x) = match self.side {
+      Side::Left => (-half.x, -MALLET_RADIUS),
+      Side::Right => (MALLET_RADIUS, half.x),
+    };
+
+    Vec2::new(point.x.clamp(min_x, max_x), point.y.clamp(-half.y, half.y))
+  }
+}
+
+/// Where the mallet is heading, set by a player or by the AI.
+#[derive(Component)]
+pub(super) struct MalletTarget(pub Vec2);
+
+/// Mallet moved by a player at this keyboard. Only the left one can also follow the mouse, it
+/// does so after the mouse moves and until one of its keys is pressed.
+#[derive(Component)]
+struct PlayerMallet {
+  follows_mouse: bool,
+}
+
+/// Spawns the mallet of `side`, played by the AI of `difficulty` if there is one.
+pub(super) fn spawn_mallet(commands: &mut Commands, side: Side, difficulty: Option<Difficulty>) {
+  let max_speed = difficulty.map_or(PLAYER_MALLET_SPEED, Difficulty::max_speed);
+  let mallet = Mallet { side, max_speed };
+  let home = mallet.home();
+
+  let mut entity = commands.spawn((
+    Name::new("Mallet"),
+    DespawnOnExit(AIR_HOCKEY_SCOPE),
+    // Ракетки живут до смены режима, между матчами они только возвращаются к воротам
+    DespawnOnEnter(AirHockeyGameState::ModeSelect),
+    Transform::from_translation(home.extend(1.0)),
+    RigidBody::Kinematic,
+    Collider::circle(MALLET_RADIUS),
+    Restitution::new(MALLET_RESTITUTION),
+    CollisionLayers::new(AirHockeyLayer::Mallet, AirHockeyLayer::Puck),
+    MalletTarget(home),
+    mallet,
+  ));
+
+  match difficulty {
+    Some(difficulty) => entity.insert(AiMallet::new(difficulty)),
+    None => entity.insert(PlayerMallet {
+      follows_mouse: false,
+    }),
+  };
+}
+
+fn reset_mallets_system(mut mallet_query: Query<(&Mallet, &mut Transform, &mut MalletTarget)>) {
+  for (mallet, mut transform, mut target) in &mut mallet_query {
+    let home = mallet.home();
+    transform.translation = home.extend(transform.translation.z);
+    target.0 = home;
+  }
+}
+
+fn stop_mallets_system(mut mallet_query: Query<&mut LinearVelocity, With<Mallet>>) {
+  for mut velocity in &mut mallet_query {
+    velocity.0 = Vec2::ZERO;
+  }
+}
+
+fn keys(side: Side) -> [KeyCode; 4] {
+  match side {
+    Side::Left => [KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyA, KeyCode::KeyD],
+    Side::Right => [
+      KeyCode::ArrowUp,
+      KeyCode::ArrowDown,
+      KeyCode::ArrowLeft,
+      KeyCode::ArrowRight,
+    ],
+  }
+}
+
+fn player_mallet_input_system(
+  mut mallet_query: Query<(&Mallet, &mut PlayerMallet, &mut MalletTarget)>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  mouse_motion: Res<AccumulatedMouseMotion>,
+  window_single: Single<&Window, With<PrimaryWindow>>,
+  camera_single: Single<(&Camera, &GlobalTransform), With<AirHockeyCamera>>,
+  time: Res<Time>,
+) {
+  let (camera, camera_transform) = *camera_single;
+
+  for (mallet, mut player, mut target) in &mut mallet_query {
+    let [up, down, left, right] = keys(mallet.side);
+
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(up) {
+      direction.y += 1.0;
+    }
+    if keyboard_input.pressed(down) {
+      direction.y -= 1.0;
+    }
+    if keyboard_input.pressed(left) {
+      direction.x -= 1.0;
+    }
+    if keyboard_input.pressed(right) {
+      direction.x += 1.0;
+    }
+
+    if direction != Vec2::ZERO {
+      player.follows_mouse = false;
+      target.0 += direction.normalize() * KEY_SPEED * time.delta_secs();
+    } else if mallet.side == Side::Left && mouse_motion.delta != Vec2::ZERO {
+      player.follows_mouse = true;
+    }
+
+    if player.follows_mouse {
+      let cursor = window_single
+        .cursor_position()
+        .and_then(|cursor| {
+          camera
+            .viewport_to_world_2d(camera_transform, cursor)
+            .ok()
+        });
+
+      if let Some(cursor) = cursor {
+        target.0 = cursor;
+      }
+    }
+
+    // Цель не убегает за пределы половины, иначе клавиши «залипают» у борта
+    target.0 = mallet.confine(target.0);
+  }
+}
+
+/// Kinematic bodies are moved by their velocity, enough of it to reach the target this tick
+/// but no more than the mallet's top speed.
+fn mallet_movement_system(
+  mut mallet_query: Query<(&Mallet, &MalletTarget, &Transform, &mut LinearVelocity)>,
+  time: Res<Time>,
+) {
+  for (mallet, target, transform, mut velocity) in &mut mallet_query {
+    let offset = mallet.confine(target.0) - transform.translation.truncate();
+    let step = offset.clamp_length_max(mallet.max_speed * time.delta_secs());
+
+    velocity.0 = step / time.delta_secs();
+  }
+}
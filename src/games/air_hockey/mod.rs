@@ -0,0 +1,541 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+  game::FontAssets,
+  games::{
+    CurrentGameState, GameExitMessage, GameResultMessage, GameType,
+    gravity::ScopedGravityPlugin,
+    menu::{GameMenuBuilder, GameMenuPlugin, GameMenuSelectedEvent},
+  },
+  state::ContextScope,
+};
+
+use mallet::{AirHockeyMalletPlugin, spawn_mallet};
+use opponent::{AirHockeyOpponentPlugin, Difficulty};
+use render::AirHockeyRenderPlugin;
+
+mod mallet;
+mod opponent;
+mod render;
+
+/// Every entity of an air hockey session is despawned when the player leaves it.
+const AIR_HOCKEY_SCOPE: ContextScope = ContextScope::Minigame(GameType::AirHockey);
+
+const BACKGROUND_COLOR: Color = Color::srgb(0.05, 0.08, 0.1);
+const WALL_COLOR: Color = Color::srgb(0.3, 0.4, 0.5);
+
+/// Playing surface between the walls, centered on the origin. The goals are in the short sides.
+const TABLE_SIZE: Vec2 = Vec2::new(320.0, 180.0);
+const WALL_THICKNESS: f32 = 8.0;
+/// Room around the table, where a puck that went into a goal is still visible for a moment.
+const TABLE_MARGIN: f32 = 16.0;
+const GOAL_WIDTH: f32 = 64.0;
+const WALL_RESTITUTION: f32 = 0.9;
+
+const PUCK_RADIUS: f32 = 6.0;
+const PUCK_RESTITUTION: f32 = 0.9;
+const PUCK_FRICTION: f32 = 0.1;
+/// The air cushion isn't perfect, a puck left alone slowly stops.
+const PUCK_DAMPING: f32 = 0.4;
+/// Keeps a puck squeezed between a mallet and a wall from tunneling out of the table.
+const MAX_PUCK_SPEED: f32 = 700.0;
+
+const GOALS_TO_WIN: u32 = 7;
+/// Pause after a goal before the puck is in play again.
+const FACEOFF_SECONDS: f32 = 1.0;
+/// Every goal of the player against the AI.
+const GOAL_POINTS: u32 = 1;
+
+pub struct AirHockeyGamePlugin;
+
+impl Plugin for AirHockeyGamePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<AirHockeyGameState>();
+
+    app.add_plugins((
+      AirHockeyMalletPlugin,
+      AirHockeyOpponentPlugin,
+      AirHockeyRenderPlugin,
+      GameMenuPlugin::<AirHockeyMenuAction>::default(),
+      // Стол виден сверху, шайбу ничего никуда не тянет
+      ScopedGravityPlugin {
+        scope: AIR_HOCKEY_SCOPE,
+        gravity: Vec2::ZERO,
+      },
+    ));
+
+    app.init_resource::<AirHockeyMatch>();
+
+    app
+      .add_systems(Update, setup.run_if(switched_to_game))
+      .add_systems(
+        OnEnter(AirHockeyGameState::ModeSelect),
+        mode_select_enter_system,
+      )
+      .add_systems(OnEnter(AirHockeyGameState::Faceoff), faceoff_enter_system)
+      .add_systems(
+        Update,
+        faceoff_system.run_if(in_state(AirHockeyGameState::Faceoff)),
+      )
+      .add_systems(FixedUpdate, goal_system.run_if(air_hockey_running))
+      .add_systems(Update, update_match_info_system)
+      .add_systems(
+        OnEnter(AirHockeyGameState::MatchOver),
+        (submit_score_system, match_over_enter_system),
+      )
+      .add_systems(OnEnter(AirHockeyGameState::NotStarted), reset_match_system)
+      .add_observer(air_hockey_menu_observer);
+  }
+}
+
+#[derive(States, Debug, Clone, Hash, Eq, PartialEq, Default)]
+enum AirHockeyGameState {
+  #[default]
+  NotStarted,
+  /// Choice between the AI of some difficulty and a second player at the same keyboard.
+  ModeSelect,
+  /// The puck waits on the side of whoever conceded the last goal.
+  Faceoff,
+  Playing,
+  MatchOver,
+}
+
+/// Choices of the mode select and match over menus.
+#[derive(Clone, Copy)]
+enum AirHockeyMenuAction {
+  Start(AirHockeyMode),
+  PlayAgain,
+  ChangeMode,
+  BackToLobby,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AirHockeyMode {
+  VersusAi(Difficulty),
+  TwoPlayers,
+}
+
+impl std::fmt::Display for AirHockeyMode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AirHockeyMode::VersusAi(difficulty) => write!(f, "Versus AI: {difficulty}"),
+      AirHockeyMode::TwoPlayers => write!(f, "Two players"),
+    }
+  }
+}
+
+/// Half of the table. The player, or the first player, defends the left goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+  Left,
+  Right,
+}
+
+impl Side {
+  /// Direction from the middle of the table to this side's goal.
+  fn sign(self) -> f32 {
+    match self {
+      Side::Left => -1.0,
+      Side::Right => 1.0,
+    }
+  }
+
+  fn opponent(self) -> Self {
+    match self {
+      Side::Left => Side::Right,
+      Side::Right => Side::Left,
+    }
+  }
+
+  fn index(self) -> usize {
+    match self {
+      Side::Left => 0,
+      Side::Right => 1,
+    }
+  }
+
+  /// Center of the mouth of this side's goal.
+  fn goal(self) -> Vec2 {
+    Vec2::new(self.sign() * TABLE_SIZE.x / 2.0, 0.0)
+  }
+}
+
+/// Collision layers of the air hockey world. Mallets only hit the puck, walls and mallets aren't
+/// simulated against each other, the mallets are kept on the table by their controls instead.
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
+enum AirHockeyLayer {
+  #[default]
+  Default,
+  Wall,
+  Mallet,
+  Puck,
+}
+
+#[derive(Component)]
+struct AirHockeyCamera;
+
+#[derive(Component)]
+struct Puck;
+
+#[derive(Component)]
+struct MatchInfoText;
+
+/// One match, first to [`GOALS_TO_WIN`].
+#[derive(Resource)]
+struct AirHockeyMatch {
+  mode: AirHockeyMode,
+  /// Goals scored by the left and the right side.
+  goals: [u32; 2],
+  /// Side the puck is put on for the next faceoff.
+  serve: Side,
+  faceoff: Timer,
+}
+
+impl Default for AirHockeyMatch {
+  fn default() -> Self {
+    Self {
+      mode: AirHockeyMode::VersusAi(Difficulty::Normal),
+      goals: [0, 0],
+      serve: Side::Left,
+      faceoff: Timer::from_seconds(FACEOFF_SECONDS, TimerMode::Once),
+    }
+  }
+}
+
+impl AirHockeyMatch {
+  fn new(mode: AirHockeyMode) -> Self {
+    Self {
+      mode,
+      // Первую шайбу получает случайная сторона
+      serve: if rand::random::<bool>() {
+        Side::Left
+      } else {
+        Side::Right
+      },
+      ..default()
+    }
+  }
+
+  fn goals(&self, side: Side) -> u32 {
+    self.goals[side.index()]
+  }
+
+  fn winner(&self) -> Option<Side> {
+    [Side::Left, Side::Right]
+      .into_iter()
+      .find(|side| self.goals(*side) >= GOALS_TO_WIN)
+  }
+
+  /// Points the player earns against the AI: every goal, and a bonus for a win that grows
+  /// with the difficulty. A local match has no single player to credit, so it earns nothing.
+  fn score(&self) -> Option<u32> {
+    let AirHockeyMode::VersusAi(difficulty) = self.mode else {
+      return None;
+    };
+
+    let mut score = self.goals(Side::Left) * GOAL_POINTS;
+    if self.winner() == Some(Side::Left) {
+      score += difficulty.win_bonus();
+    }
+
+    Some(score)
+  }
+}
+
+fn switched_to_game(config: Res<CurrentGameState>) -> bool {
+  config.is_changed() && config.current_game == Some(GameType::AirHockey)
+}
+
+/// A pending state change stops the fixed ticks too, so a goal is counted once.
+fn air_hockey_running(
+  state: Res<State<AirHockeyGameState>>,
+  next_state: Res<NextState<AirHockeyGameState>>,
+) -> bool {
+  *state.get() == AirHockeyGameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+fn setup(
+  mut commands: Commands,
+  mut next_state: ResMut<NextState<AirHockeyGameState>>,
+  font_assets: Res<FontAssets>,
+) {
+  let view_size = TABLE_SIZE + (WALL_THICKNESS + TABLE_MARGIN) * 2.0;
+
+  let mut projection = OrthographicProjection::default_2d();
+  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
+    min_width: view_size.x,
+    min_height: view_size.y,
+  };
+
+  commands.spawn((
+    Name::new("AirHockeyCamera"),
+    AirHockeyCamera,
+    DespawnOnExit(AIR_HOCKEY_SCOPE),
+    Camera2d,
+    Camera {
+      order: 1,
+      clear_color: ClearColorConfig::Custom(BACKGROUND_COLOR),
+      ..Default::default()
+    },
+    Projection::Orthographic(projection),
+  ));
+
+  // Короткие борта разрезаны воротами посередине
+  let end_wall_size = Vec2::new(WALL_THICKNESS, (TABLE_SIZE.y - GOAL_WIDTH) / 2.0);
+  let end_wall_x = (TABLE_SIZE.x + WALL_THICKNESS) / 2.0;
+  let end_wall_y = (GOAL_WIDTH + end_wall_size.y) / 2.0;
+  let side_wall_size = Vec2::new(TABLE_SIZE.x + WALL_THICKNESS * 2.0, WALL_THICKNESS);
+  let side_wall_y = (TABLE_SIZE.y + WALL_THICKNESS) / 2.0;
+  let walls = [
+    (Vec2::new(0.0, side_wall_y), side_wall_size),
+    (Vec2::new(0.0, -side_wall_y), side_wall_size),
+    (Vec2::new(-end_wall_x, end_wall_y), end_wall_size),
+    (Vec2::new(-end_wall_x, -end_wall_y), end_wall_size),
+    (Vec2::new(end_wall_x, end_wall_y), end_wall_size),
+    (Vec2::new(end_wall_x, -end_wall_y), end_wall_size),
+  ];
+
+  for (translation, size) in walls {
+    commands.spawn((
+      Name::new("AirHockeyWall"),
+      DespawnOnExit(AIR_HOCKEY_SCOPE),
+      Sprite {
+        color: WALL_COLOR,
+        custom_size: Some(size),
+        ..default()
+      },
+      Transform::from_translation(translation.extend(0.0)),
+      RigidBody::Static,
+      Collider::rectangle(size.x, size.y),
+      Restitution::new(WALL_RESTITUTION),
+      CollisionLayers::new(AirHockeyLayer::Wall, AirHockeyLayer::Puck),
+    ));
+  }
+
+  commands.spawn((
+    Name::new("AirHockeyMatchInfo"),
+    MatchInfoText,
+    DespawnOnExit(AIR_HOCKEY_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 24.,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+  ));
+
+  next_state.set(AirHockeyGameState::ModeSelect);
+}
+
+fn reset_match_system(mut air_hockey_match: ResMut<AirHockeyMatch>) {
+  *air_hockey_match = AirHockeyMatch::default();
+}
+
+fn mode_select_enter_system(mut commands: Commands, font_assets: Res<FontAssets>) {
+  let mut menu = GameMenuBuilder::new()
+    .title("Air hockey")
+    .line(format!("First to {GOALS_TO_WIN} goals wins"))
+    .line("Left mallet: mouse or WASD, right mallet: arrows");
+
+  for difficulty in Difficulty::ALL {
+    menu = menu.option(
+      format!("Versus AI: {difficulty}"),
+      AirHockeyMenuAction::Start(AirHockeyMode::VersusAi(difficulty)),
+    );
+  }
+
+  menu
+    .option(
+      "Two players",
+      AirHockeyMenuAction::Start(AirHockeyMode::TwoPlayers),
+    )
+    .option("Back to lobby", AirHockeyMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(AirHockeyGameState::ModeSelect),
+    );
+}
+
+/// Spawns the mallets of a new match. They stay until the mode is changed.
+fn start_match(commands: &mut Commands, mode: AirHockeyMode) {
+  spawn_mallet(commands, Side::Left, None);
+  spawn_mallet(
+    commands,
+    Side::Right,
+    match mode {
+      AirHockeyMode::VersusAi(difficulty) => Some(difficulty),
+      AirHockeyMode::TwoPlayers => None,
+    },
+  );
+}
+
+/// Puts the puck on the serving side. It is in play once the faceoff is over.
+fn faceoff_enter_system(mut commands: Commands, mut air_hockey_match: ResMut<AirHockeyMatch>) {
+  air_hockey_match.faceoff.reset();
+
+  let translation = Vec2::new(air_hockey_match.serve.sign() * TABLE_SIZE.x / 4.0, 0.0);
+
+  commands.spawn((
+    Name::new("Puck"),
+    Puck,
+    DespawnOnExit(AIR_HOCKEY_SCOPE),
+    Transform::from_translation(translation.extend(1.0)),
+    RigidBody::Dynamic,
+    Collider::circle(PUCK_RADIUS),
+    Restitution::new(PUCK_RESTITUTION),
+    Friction::new(PUCK_FRICTION),
+    LinearDamping(PUCK_DAMPING),
+    MaxLinearSpeed(MAX_PUCK_SPEED),
+    LockedAxes::ROTATION_LOCKED,
+    SweptCcd::default(),
+    CollisionLayers::new(
+      AirHockeyLayer::Puck,
+      [AirHockeyLayer::Wall, AirHockeyLayer::Mallet],
+    ),
+  ));
+}
+
+fn faceoff_system(
+  mut air_hockey_match: ResMut<AirHockeyMatch>,
+  mut next_state: ResMut<NextState<AirHockeyGameState>>,
+  time: Res<Time>,
+) {
+  if air_hockey_match
+    .faceoff
+    .tick(time.delta())
+    .just_finished()
+  {
+    next_state.set(AirHockeyGameState::Playing);
+  }
+}
+
+/// A puck that left the table through a goal mouth scores for the other side, which then
+/// concedes the next faceoff.
+fn goal_system(
+  mut commands: Commands,
+  mut air_hockey_match: ResMut<AirHockeyMatch>,
+  mut next_state: ResMut<NextState<AirHockeyGameState>>,
+  puck_query: Query<(Entity, &Transform), With<Puck>>,
+) {
+  for (entity, transform) in &puck_query {
+    let x = transform.translation.x;
+    if x.abs() < TABLE_SIZE.x / 2.0 + PUCK_RADIUS {
+      continue;
+    }
+
+    let conceded = if x < 0.0 { Side::Left } else { Side::Right };
+    let scorer = conceded.opponent();
+
+    commands.entity(entity).despawn();
+    air_hockey_match.goals[scorer.index()] += 1;
+    air_hockey_match.serve = conceded;
+
+    next_state.set(if air_hockey_match.winner().is_some() {
+      AirHockeyGameState::MatchOver
+    } else {
+      AirHockeyGameState::Faceoff
+    });
+  }
+}
+
+fn update_match_info_system(
+  mut match_info_query: Query<&mut Text, With<MatchInfoText>>,
+  air_hockey_match: Res<AirHockeyMatch>,
+  state: Res<State<AirHockeyGameState>>,
+) {
+  for mut text in &mut match_info_query {
+    text.0 = if *state.get() == AirHockeyGameState::ModeSelect {
+      String::new()
+    } else {
+      format!(
+        "{}  {} : {}",
+        air_hockey_match.mode,
+        air_hockey_match.goals(Side::Left),
+        air_hockey_match.goals(Side::Right)
+      )
+    };
+  }
+}
+
+fn submit_score_system(
+  mut game_result_messages: MessageWriter<GameResultMessage>,
+  air_hockey_match: Res<AirHockeyMatch>,
+) {
+  let Some(score) = air_hockey_match.score() else {
+    return;
+  };
+
+  game_result_messages.write(GameResultMessage {
+    game: GameType::AirHockey,
+    score,
+  });
+}
+
+fn match_over_enter_system(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  air_hockey_match: Res<AirHockeyMatch>,
+) {
+  let title = match (air_hockey_match.mode, air_hockey_match.winner()) {
+    (AirHockeyMode::VersusAi(_), Some(Side::Left)) => "You win!",
+    (AirHockeyMode::VersusAi(_), _) => "You lose",
+    (AirHockeyMode::TwoPlayers, Some(Side::Left)) => "Left player wins!",
+    (AirHockeyMode::TwoPlayers, _) => "Right player wins!",
+  };
+
+  let mut menu = GameMenuBuilder::new()
+    .title(title)
+    .line(format!(
+      "{} : {}",
+      air_hockey_match.goals(Side::Left),
+      air_hockey_match.goals(Side::Right)
+    ));
+
+  if let Some(score) = air_hockey_match.score() {
+    menu = menu.line(format!("Score {score}"));
+  }
+
+  menu
+    .option("Play again", AirHockeyMenuAction::PlayAgain)
+    .option("Change mode", AirHockeyMenuAction::ChangeMode)
+    .option("Back to lobby", AirHockeyMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(AirHockeyGameState::MatchOver),
+    );
+}
+
+fn air_hockey_menu_observer(
+  event: On<GameMenuSelectedEvent<AirHockeyMenuAction>>,
+  mut commands: Commands,
+  mut air_hockey_match: ResMut<AirHockeyMatch>,
+  mut next_state: ResMut<NextState<AirHockeyGameState>>,
+  mut game_exit_messages: MessageWriter<GameExitMessage>,
+) {
+  match event.action {
+    AirHockeyMenuAction::Start(mode) => {
+      *air_hockey_match = AirHockeyMatch::new(mode);
+      start_match(&mut commands, mode);
+      next_state.set(AirHockeyGameState::Faceoff);
+    }
+    AirHockeyMenuAction::PlayAgain => {
+      *air_hockey_match = AirHockeyMatch::new(air_hockey_match.mode);
+      next_state.set(AirHockeyGameState::Faceoff);
+    }
+    AirHockeyMenuAction::ChangeMode => next_state.set(AirHockeyGameState::ModeSelect),
+    AirHockeyMenuAction::BackToLobby => {
+      next_state.set(AirHockeyGameState::NotStarted);
+      game_exit_messages.write(GameExitMessage);
+    }
+  }
+}
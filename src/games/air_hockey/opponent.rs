@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use super::{
+  GOAL_WIDTH, PUCK_RADIUS, Puck, air_hockey_running,
+  mallet::{MALLET_RADIUS, Mallet, MalletTarget},
+};
+
+/// How far past the puck the AI aims a strike, so it hits through the puck instead of stopping
+/// at it.
+const STRIKE_REACH: f32 = 16.0;
+
+/// The AI plays the right mallet. It looks at the puck only every so often and aims with some
+/// error, both shrinking with the difficulty along with its mallet getting faster.
+pub(super) struct AirHockeyOpponentPlugin;
+
+impl Plugin for AirHockeyOpponentPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(FixedUpdate, ai_mallet_system.run_if(air_hockey_running));
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Difficulty {
+  Easy,
+  Normal,
+  Hard,
+}
+
+impl Difficulty {
+  pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+  pub fn max_speed(self) -> f32 {
+    match self {
+      Difficulty::Easy => 160.0,
+      Difficulty::Normal => 260.0,
+      Difficulty::Hard => 380.0,
+    }
+  }
+
+  /// Time between two looks at the puck.
+  fn reaction_seconds(self) -> f32 {
+    match self {
+      Difficulty::Easy => 0.35,
+      Difficulty::Normal => 0.2,
+      Difficulty::Hard => 0.08,
+    }
+  }
+
+  /// Largest distance the AI's target may be off by.
+  fn aim_error(self) -> f32 {
+    match self {
+      Difficulty::Easy => 24.0,
+      Difficulty::Normal => 12.0,
+      Difficulty::Hard => 4.0,
+    }
+  }
+
+  /// Points for beating the AI, on top of the goals.
+  pub fn win_bonus(self) -> u32 {
+    match self {
+      Difficulty::Easy => 3,
+      Difficulty::Normal => 6,
+      Difficulty::Hard => 10,
+    }
+  }
+}
+
+impl std::fmt::Display for Difficulty {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Difficulty::Easy => write!(f, "Easy"),
+      Difficulty::Normal => write!(f, "Normal"),
+      Difficulty::Hard => write!(f, "Hard"),
+    }
+  }
+}
+
+#[derive(Component)]
+pub(super) struct AiMallet {
+  difficulty: Difficulty,
+  reaction: Timer,
+}
+
+impl AiMallet {
+  pub fn new(difficulty: Difficulty) -> Self {
+    Self {
+      difficulty,
+      reaction: Timer::from_seconds(difficulty.reaction_seconds(), TimerMode::Repeating),
+    }
+  }
+}
+
+/// Attacks a puck on its half, striking it toward the other goal, and otherwise stands between
+/// the puck and its own goal.
+fn ai_mallet_system(
+  mut mallet_query: Query<(&mut AiMallet, &Mallet, &Transform, &mut MalletTarget)>,
+  puck_query: Query<&Transform, With<Puck>>,
+  time: Res<Time>,
+) {
+  let Ok(puck_transform) = puck_query.single() else {
+    return;
+  };
+  let puck = puck_transform.translation.truncate();
+
+  for (mut ai, mallet, transform, mut target) in &mut mallet_query {
+    if !ai
+      .reaction
+      .tick(time.delta())
+      .just_finished()
+    {
+      continue;
+    }
+
+    let side = mallet.side;
+    let position = transform.translation.truncate();
+    let own_half = puck.x * side.sign() > 0.0;
+    // Шайба между ракеткой и своими воротами: бить по ней — забить себе
+    let behind = (puck.x - position.x) * side.sign() > 0.0;
+
+    let aim = if own_half && behind {
+      let dodge = if puck.y > position.y { -1.0 } else { 1.0 };
+      Vec2::new(
+        side.goal().x - side.sign() * MALLET_RADIUS,
+        puck.y + dodge * (MALLET_RADIUS + PUCK_RADIUS),
+      )
+    } else if own_half {
+      let direction = (side.opponent().goal() - puck).normalize_or(-Vec2::X * side.sign());
+      puck + direction * STRIKE_REACH
+    } else {
+      Vec2::new(
+        mallet.home().x,
+        (puck.y / 2.0).clamp(-GOAL_WIDTH / 2.0, GOAL_WIDTH / 2.0),
+      )
+    };
+
+    let error = ai.difficulty.aim_error();
+    let miss = Vec2::new(
+      rand::random_range(-error..=error),
+      rand::random_range(-error..=error),
+    );
+
+    target.0 = mallet.confine(aim + miss);
+  }
+}
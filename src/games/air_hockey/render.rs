@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::camera::context_layers;
+
+use super::{
+  AIR_HOCKEY_SCOPE, GOAL_WIDTH, PUCK_RADIUS, Puck, Side, TABLE_SIZE,
+  mallet::{MALLET_RADIUS, Mallet},
+};
+
+const LINE_WIDTH: f32 = 2.0;
+/// Radius of the circles around the center spot and in front of the goals.
+const CIRCLE_RADIUS: f32 = 32.0;
+
+const MARKING_COLOR: Color = Color::srgb(0.25, 0.35, 0.45);
+const GOAL_COLOR: Color = Color::srgb(0.9, 0.3, 0.3);
+const LEFT_MALLET_COLOR: Color = Color::srgb(0.3, 0.6, 1.0);
+const RIGHT_MALLET_COLOR: Color = Color::srgb(1.0, 0.55, 0.25);
+const PUCK_COLOR: Color = Color::WHITE;
+
+/// Round pieces and the table markings are drawn by their own gizmo group, which only the air
+/// hockey camera sees. The walls are sprites.
+pub(super) struct AirHockeyRenderPlugin;
+
+impl Plugin for AirHockeyRenderPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_gizmo_group::<AirHockeyGizmos>();
+
+    app
+      .add_systems(Startup, configure_gizmos_system)
+      .add_systems(
+        Update,
+        (
+          // Разметку рисуем только пока стол на экране
+          draw_table_system.run_if(in_state(AIR_HOCKEY_SCOPE)),
+          draw_mallets_system,
+          draw_pucks_system,
+        ),
+      );
+  }
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct AirHockeyGizmos;
+
+fn configure_gizmos_system(mut config_store: ResMut<GizmoConfigStore>) {
+  let (config, _) = config_store.config_mut::<AirHockeyGizmos>();
+  config.line.width = LINE_WIDTH;
+  config.render_layers = context_layers(AIR_HOCKEY_SCOPE);
+}
+
+fn draw_table_system(mut gizmos: Gizmos<AirHockeyGizmos>) {
+  let half = TABLE_SIZE / 2.0;
+
+  gizmos.line_2d(
+    Vec2::new(0.0, -half.y),
+    Vec2::new(0.0, half.y),
+    MARKING_COLOR,
+  );
+  gizmos.circle_2d(Vec2::ZERO, CIRCLE_RADIUS, MARKING_COLOR);
+
+  for side in [Side::Left, Side::Right] {
+    let goal = side.goal();
+    // Полукруг перед воротами смотрит в сторону центра стола
+    let facing = Rot2::radians(side.sign() * std::f32::consts::FRAC_PI_2);
+    gizmos.arc_2d(
+      Isometry2d::new(goal, facing),
+      std::f32::consts::PI,
+      CIRCLE_RADIUS,
+      MARKING_COLOR,
+    );
+    gizmos.line_2d(
+      goal - Vec2::Y * GOAL_WIDTH / 2.0,
+      goal + Vec2::Y * GOAL_WIDTH / 2.0,
+      GOAL_COLOR,
+    );
+  }
+}
+
+fn draw_mallets_system(
+  mut gizmos: Gizmos<AirHockeyGizmos>,
+  mallet_query: Query<(&Mallet, &Transform)>,
+) {
+  for (mallet, transform) in &mallet_query {
+    let center = transform.translation.truncate();
+    let color = match mallet.side {
+      Side::Left => LEFT_MALLET_COLOR,
+      Side::Right => RIGHT_MALLET_COLOR,
+    };
+
+    // Обод и ручка ракетки
+    gizmos.circle_2d(center, MALLET_RADIUS, color);
+    gizmos.circle_2d(center, MALLET_RADIUS / 2.0, color);
+  }
+}
+
+fn draw_pucks_system(
+  mut gizmos: Gizmos<AirHockeyGizmos>,
+  puck_query: Query<&Transform, With<Puck>>,
+) {
+  for transform in &puck_query {
+    let center = transform.translation.truncate();
+    gizmos.circle_2d(center, PUCK_RADIUS, PUCK_COLOR);
+    gizmos.circle_2d(center, PUCK_RADIUS / 2.0, PUCK_COLOR);
+  }
+}
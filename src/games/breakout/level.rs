@@ -0,0 +1,293 @@
+use avian2d::prelude::*;
+use bevy::{
+  asset::{AssetLoader, LoadContext, io::Reader},
+  prelude::*,
+};
+use serde::Deserialize;
+
+use crate::loading::{AssetCollection, AssetCollectionAppExt};
+
+use super::{
+  BREAKOUT_SCOPE, BreakoutGameState, BreakoutLayer, BreakoutRun, FIELD_SIZE, power_up::PowerUpKind,
+};
+
+/// Campaign levels in play order, loaded from `assets/games/breakout/levels/<id>.bricks.ron`.
+/// Clearing the last one wins the run.
+const CAMPAIGN_LEVELS: [&str; 3] = ["wall", "checkers", "fortress"];
+
+/// Every row of a level is exactly this many bricks wide, as wide as the field.
+const BRICK_COLUMNS: u32 = 12;
+const MAX_BRICK_ROWS: u32 = 10;
+const BRICK_SIZE: Vec2 = Vec2::new(FIELD_SIZE.x / BRICK_COLUMNS as f32, 10.0);
+/// Gap between the top wall and the first row of bricks.
+const BRICK_TOP_GAP: f32 = 24.0;
+/// Bricks are drawn this much smaller than their colliders, so the rows read as separate bricks.
+const BRICK_SPRITE_GAP: f32 = 1.0;
+const MAX_BRICK_HITS: u32 = 3;
+
+pub(super) struct BreakoutLevelPlugin;
+
+impl Plugin for BreakoutLevelPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_asset::<BreakoutLevelAsset>();
+    app.init_asset_loader::<BreakoutLevelLoader>();
+    app.init_asset_collection::<BreakoutLevelAssets>();
+
+    app.add_observer(load_level_observer);
+  }
+}
+
+/// Breakout level loaded from a `*.bricks.ron` file.
+#[derive(Asset, TypePath, Debug)]
+pub(super) struct BreakoutLevelAsset {
+  name: String,
+  bricks: Vec<BrickSpec>,
+}
+
+#[derive(Debug)]
+struct BrickSpec {
+  /// Column from the left and row from the top.
+  cell: UVec2,
+  brick: Brick,
+}
+
+/// On-disk form of a level. `rows` draw the bricks from top to bottom: `.` is an empty cell,
+/// `1` to `3` a brick taking that many hits, `#` a brick that never breaks, `M` and `W`
+/// one-hit bricks dropping the multi-ball and the wide paddle power-ups.
+#[derive(Deserialize, Debug)]
+struct BreakoutLevelFile {
+  name: String,
+  rows: Vec<String>,
+}
+
+#[derive(Resource)]
+struct BreakoutLevelAssets {
+  levels: Vec<Handle<BreakoutLevelAsset>>,
+}
+
+impl FromWorld for BreakoutLevelAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      levels: CAMPAIGN_LEVELS
+        .iter()
+        .map(|id| asset_server.load(format!("games/breakout/levels/{id}.bricks.ron")))
+        .collect(),
+    }
+  }
+}
+
+impl AssetCollection for BreakoutLevelAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    self
+      .levels
+      .iter()
+      .map(|handle| handle.clone().untyped())
+      .collect()
+  }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub(super) struct Brick {
+  /// Hits left before the brick breaks, `None` for a brick that never breaks.
+  pub hits: Option<u32>,
+  /// Power-up falling out of the brick when it breaks.
+  pub drop: Option<PowerUpKind>,
+}
+
+impl Brick {
+  pub fn color(&self) -> Color {
+    match (self.hits, self.drop) {
+      (None, _) => Color::srgb(0.45, 0.45, 0.5),
+      (_, Some(kind)) => kind.color(),
+      (Some(1), None) => Color::srgb(0.3, 0.8, 0.4),
+      (Some(2), None) => Color::srgb(0.95, 0.8, 0.25),
+      (Some(_), None) => Color::srgb(0.9, 0.35, 0.3),
+    }
+  }
+}
+
+/// Despawns the bricks of the previous level, spawns the bricks of level `index` and serves
+/// a new ball.
+#[derive(Event)]
+pub(super) struct LoadLevelEvent {
+  pub index: usize,
+}
+
+/// Index of the campaign level after `index`, if there is any.
+pub(super) fn next_level(index: usize) -> Option<usize> {
+  Some(index + 1).filter(|index| *index < CAMPAIGN_LEVELS.len())
+}
+
+#[derive(Default, TypePath)]
+struct BreakoutLevelLoader;
+
+#[derive(Debug)]
+enum BreakoutLevelLoaderError {
+  Io(std::io::Error),
+  Ron(ron::error::SpannedError),
+  Layout(String),
+}
+
+impl std::fmt::Display for BreakoutLevelLoaderError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BreakoutLevelLoaderError::Io(error) => write!(f, "Could not read breakout level: {error}"),
+      BreakoutLevelLoaderError::Ron(error) => write!(f, "Could not parse breakout level: {error}"),
+      BreakoutLevelLoaderError::Layout(error) => write!(f, "Invalid breakout level: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for BreakoutLevelLoaderError {}
+
+impl From<std::io::Error> for BreakoutLevelLoaderError {
+  fn from(error: std::io::Error) -> Self {
+    BreakoutLevelLoaderError::Io(error)
+  }
+}
+
+impl From<ron::error::SpannedError> for BreakoutLevelLoaderError {
+  fn from(error: ron::error::SpannedError) -> Self {
+    BreakoutLevelLoaderError::Ron(error)
+  }
+}
+
+impl AssetLoader for BreakoutLevelLoader {
+  type Asset = BreakoutLevelAsset;
+  type Settings = ();
+  type Error = BreakoutLevelLoaderError;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    _load_context: &mut LoadContext<'_>,
+  ) -> Result<Self::Asset, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    parse_level(ron::de::from_bytes(&bytes)?).map_err(BreakoutLevelLoaderError::Layout)
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["bricks.ron"]
+  }
+}
+
+fn parse_level(file: BreakoutLevelFile) -> Result<BreakoutLevelAsset, String> {
+  if file.rows.len() as u32 > MAX_BRICK_ROWS {
+    return Err(format!(
+      "level has {} rows, at most {MAX_BRICK_ROWS} fit",
+      file.rows.len()
+    ));
+  }
+
+  let mut bricks = Vec::new();
+
+  for (row_index, row) in file.rows.iter().enumerate() {
+    if row.chars().count() as u32 != BRICK_COLUMNS {
+      return Err(format!(
+        "row {row_index} is not {BRICK_COLUMNS} bricks wide"
+      ));
+    }
+
+    for (column, cell) in row.chars().enumerate() {
+      let brick = match cell {
+        '.' => continue,
+        '#' => Brick {
+          hits: None,
+          drop: None,
+        },
+        'M' => Brick {
+          hits: Some(1),
+          drop: Some(PowerUpKind::MultiBall),
+        },
+        'W' => Brick {
+          hits: Some(1),
+          drop: Some(PowerUpKind::WidePaddle),
+        },
+        _ => match cell.to_digit(10) {
+          Some(hits @ 1..=MAX_BRICK_HITS) => Brick {
+            hits: Some(hits),
+            drop: None,
+          },
+          _ => return Err(format!("unknown cell '{cell}' in row {row_index}")),
+        },
+      };
+
+      bricks.push(BrickSpec {
+        cell: UVec2::new(column as u32, row_index as u32),
+        brick,
+      });
+    }
+  }
+
+  if !bricks
+    .iter()
+    .any(|spec| spec.brick.hits.is_some())
+  {
+    return Err(String::from("level has no bricks to break"));
+  }
+
+  Ok(BreakoutLevelAsset {
+    name: file.name,
+    bricks,
+  })
+}
+
+/// Center of the brick in `cell`, counted from the top left corner of the field.
+fn brick_translation(cell: UVec2) -> Vec2 {
+  let top_left = Vec2::new(-FIELD_SIZE.x / 2.0, FIELD_SIZE.y / 2.0 - BRICK_TOP_GAP);
+
+  top_left + (cell.as_vec2() + 0.5) * BRICK_SIZE * Vec2::new(1.0, -1.0)
+}
+
+fn load_level_observer(
+  event: On<LoadLevelEvent>,
+  mut commands: Commands,
+  mut run: ResMut<BreakoutRun>,
+  mut next_state: ResMut<NextState<BreakoutGameState>>,
+  level_assets: Res<BreakoutLevelAssets>,
+  levels: Res<Assets<BreakoutLevelAsset>>,
+  brick_query: Query<Entity, With<Brick>>,
+) {
+  let index = event.index;
+  let Some(level) = levels.get(&level_assets.levels[index]) else {
+    warn!("Breakout level {} is not loaded", CAMPAIGN_LEVELS[index]);
+    return;
+  };
+
+  for entity in brick_query.iter() {
+    commands.entity(entity).despawn();
+  }
+
+  for spec in &level.bricks {
+    commands.spawn((
+      Name::new("Brick"),
+      spec.brick,
+      DespawnOnExit(BREAKOUT_SCOPE),
+      Sprite {
+        color: spec.brick.color(),
+        custom_size: Some(BRICK_SIZE - BRICK_SPRITE_GAP),
+        ..default()
+      },
+      Transform::from_translation(brick_translation(spec.cell).extend(0.0)),
+      RigidBody::Static,
+      Collider::rectangle(BRICK_SIZE.x, BRICK_SIZE.y),
+      CollisionLayers::new(BreakoutLayer::Brick, BreakoutLayer::Ball),
+    ));
+  }
+
+  run.level = index;
+  run.level_name = level.name.clone();
+  run.bricks_left = level
+    .bricks
+    .iter()
+    .filter(|spec| spec.brick.hits.is_some())
+    .count() as u32;
+  run.wide_paddle = None;
+
+  next_state.set(BreakoutGameState::Serving);
+}
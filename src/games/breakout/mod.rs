@@ -0,0 +1,619 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+  game::FontAssets,
+  games::{
+    CurrentGameState, GameExitMessage, GameResultMessage, GameType,
+    gravity::ScopedGravityPlugin,
+    menu::{GameMenuBuilder, GameMenuPlugin, GameMenuSelectedEvent},
+  },
+  profile::Profile,
+  state::{ContextScope, GameState},
+};
+
+use level::{BreakoutLevelPlugin, Brick, LoadLevelEvent, next_level};
+use power_up::{BreakoutPowerUpPlugin, spawn_power_up};
+
+mod level;
+mod power_up;
+
+/// Every entity of a breakout session is despawned when the player leaves it.
+const BREAKOUT_SCOPE: ContextScope = ContextScope::Minigame(GameType::Breakout);
+
+const BACKGROUND_COLOR: Color = Color::srgb(0.06, 0.07, 0.1);
+const WALL_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+
+/// Space between the walls, centered on the origin. The bottom is open.
+const FIELD_SIZE: Vec2 = Vec2::new(288.0, 240.0);
+const WALL_THICKNESS: f32 = 8.0;
+/// Room below the field, where a missed ball is still visible for a moment.
+const BOTTOM_MARGIN: f32 = 16.0;
+
+const PADDLE_SIZE: Vec2 = Vec2::new(40.0, 6.0);
+/// The wide paddle power-up stretches the paddle this many times.
+const WIDE_PADDLE_FACTOR: f32 = 1.6;
+/// Height of the paddle's center above the bottom of the field.
+const PADDLE_HEIGHT: f32 = 16.0;
+const PADDLE_SPEED: f32 = 260.0;
+
+const BALL_RADIUS: f32 = 3.0;
+const BALL_SPEED: f32 = 170.0;
+/// Every next level the ball is this much faster.
+const BALL_SPEED_PER_LEVEL: f32 = 20.0;
+/// Off the middle of the paddle the ball bounces up to this angle from the vertical.
+const MAX_BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+/// The ball never flies flatter than this, so it can't get stuck between the side walls.
+const MIN_VERTICAL_SPEED_RATIO: f32 = 0.3;
+
+const START_LIVES: u32 = 3;
+const BRICK_POINTS: u32 = 1;
+
+/// Gravity of the breakout world, felt only by the falling power-ups since the ball ignores it.
+const BREAKOUT_GRAVITY: Vec2 = Vec2::new(0.0, -200.0);
+
+pub struct BreakoutGamePlugin;
+
+impl Plugin for BreakoutGamePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<BreakoutGameState>();
+
+    app.add_plugins((
+      BreakoutLevelPlugin,
+      BreakoutPowerUpPlugin,
+      GameMenuPlugin::<BreakoutMenuAction>::default(),
+      ScopedGravityPlugin {
+        scope: BREAKOUT_SCOPE,
+        gravity: BREAKOUT_GRAVITY,
+      },
+    ));
+
+    app.init_resource::<BreakoutRun>();
+
+    app
+      .add_systems(Update, setup.run_if(switched_to_game))
+      .add_systems(
+        FixedUpdate,
+        (
+          paddle_movement_system
+            .run_if(in_state(BreakoutGameState::Serving).or(in_state(BreakoutGameState::Playing))),
+          (ball_speed_system, ball_drain_system)
+            .chain()
+            .run_if(breakout_running),
+        ),
+      )
+      .add_systems(OnEnter(BreakoutGameState::Serving), spawn_served_ball)
+      .add_systems(
+        Update,
+        (
+          launch_ball_system
+            .run_if(in_state(BreakoutGameState::Serving).and(not(in_state(GameState::Paused)))),
+          ball_collision_system.run_if(in_state(BreakoutGameState::Playing)),
+          resize_paddle_system.run_if(resource_changed::<BreakoutRun>),
+          update_run_info_system,
+        ),
+      )
+      .add_systems(
+        OnEnter(BreakoutGameState::GameOver),
+        (submit_score_system, game_over_enter_system),
+      )
+      .add_systems(
+        OnEnter(BreakoutGameState::Win),
+        (submit_score_system, win_enter_system),
+      )
+      .add_systems(OnEnter(BreakoutGameState::NotStarted), reset_run_system)
+      .add_observer(breakout_menu_observer);
+  }
+}
+
+#[derive(States, Debug, Clone, Hash, Eq, PartialEq, Default)]
+enum BreakoutGameState {
+  #[default]
+  NotStarted,
+  /// The ball rests on the paddle until the player launches it.
+  Serving,
+  Playing,
+  GameOver,
+  Win,
+}
+
+/// Choices of the game over and win menus.
+#[derive(Clone, Copy)]
+enum BreakoutMenuAction {
+  PlayAgain,
+  BackToLobby,
+}
+
+/// Collision layers of the breakout world. Balls don't hit each other and power-ups only hit
+/// the paddle. Nothing here is on the default layer, so the lobby's colliders are never touched.
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
+enum BreakoutLayer {
+  #[default]
+  Default,
+  Wall,
+  Brick,
+  Paddle,
+  Ball,
+  PowerUp,
+}
+
+#[derive(Component)]
+struct BreakoutCamera;
+
+#[derive(Component)]
+struct Paddle;
+
+#[derive(Component)]
+struct Ball;
+
+/// Ball drawn on the paddle while serving. It is not simulated: launching spawns a real [`Ball`].
+#[derive(Component)]
+struct ServedBall;
+
+#[derive(Component)]
+struct RunInfoText;
+
+/// One game from the first level to the game over or the win.
+#[derive(Resource)]
+struct BreakoutRun {
+  level: usize,
+  level_name: String,
+  /// Breakable bricks left on the level.
+  bricks_left: u32,
+  lives: u32,
+  score: u32,
+  /// Time left with the wide paddle, if it was caught.
+  wide_paddle: Option<Timer>,
+}
+
+impl Default for BreakoutRun {
+  fn default() -> Self {
+    Self {
+      level: 0,
+      level_name: String::new(),
+      bricks_left: 0,
+      lives: START_LIVES,
+      score: 0,
+      wide_paddle: None,
+    }
+  }
+}
+
+impl BreakoutRun {
+  fn ball_speed(&self) -> f32 {
+    BALL_SPEED + self.level as f32 * BALL_SPEED_PER_LEVEL
+  }
+
+  fn paddle_size(&self) -> Vec2 {
+    if self.wide_paddle.is_some() {
+      PADDLE_SIZE * Vec2::new(WIDE_PADDLE_FACTOR, 1.0)
+    } else {
+      PADDLE_SIZE
+    }
+  }
+}
+
+fn switched_to_game(config: Res<CurrentGameState>) -> bool {
+  config.is_changed() && config.current_game == Some(GameType::Breakout)
+}
+
+/// A pending state change stops the fixed ticks too, so a drained ball costs one life only.
+fn breakout_running(
+  state: Res<State<BreakoutGameState>>,
+  next_state: Res<NextState<BreakoutGameState>>,
+) -> bool {
+  *state.get() == BreakoutGameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+fn setup(mut commands: Commands, mut run: ResMut<BreakoutRun>, font_assets: Res<FontAssets>) {
+  let view_size = FIELD_SIZE + Vec2::new(WALL_THICKNESS * 2.0, WALL_THICKNESS + BOTTOM_MARGIN);
+
+  let mut projection = OrthographicProjection::default_2d();
+  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
+    min_width: view_size.x,
+    min_height: view_size.y,
+  };
+
+  commands.spawn((
+    Name::new("BreakoutCamera"),
+    BreakoutCamera,
+    DespawnOnExit(BREAKOUT_SCOPE),
+    Camera2d,
+    Camera {
+      order: 1,
+      clear_color: ClearColorConfig::Custom(BACKGROUND_COLOR),
+      ..Default::default()
+    },
+    Projection::Orthographic(projection),
+    // Поле и отступ снизу вместе должны попасть в кадр
+    Transform::from_xyz(0.0, (WALL_THICKNESS - BOTTOM_MARGIN) / 2.0, 0.0),
+  ));
+
+  let side_wall_size = Vec2::new(WALL_THICKNESS, FIELD_SIZE.y + WALL_THICKNESS);
+  let side_wall_y = WALL_THICKNESS / 2.0;
+  let side_wall_x = (FIELD_SIZE.x + WALL_THICKNESS) / 2.0;
+  let walls = [
+    (Vec2::new(-side_wall_x, side_wall_y), side_wall_size),
+    (Vec2::new(side_wall_x, side_wall_y), side_wall_size),
+    (
+      Vec2::new(0.0, (FIELD_SIZE.y + WALL_THICKNESS) / 2.0),
+      Vec2::new(FIELD_SIZE.x, WALL_THICKNESS),
+    ),
+  ];
+
+  for (translation, size) in walls {
+    commands.spawn((
+      Name::new("BreakoutWall"),
+      DespawnOnExit(BREAKOUT_SCOPE),
+      Sprite {
+        color: WALL_COLOR,
+        custom_size: Some(size),
+        ..default()
+      },
+      Transform::from_translation(translation.extend(0.0)),
+      RigidBody::Static,
+      Collider::rectangle(size.x, size.y),
+      CollisionLayers::new(BreakoutLayer::Wall, BreakoutLayer::Ball),
+    ));
+  }
+
+  commands.spawn((
+    Name::new("Paddle"),
+    Paddle,
+    DespawnOnExit(BREAKOUT_SCOPE),
+    Sprite {
+      color: Color::WHITE,
+      custom_size: Some(PADDLE_SIZE),
+      ..default()
+    },
+    Transform::from_xyz(0.0, -FIELD_SIZE.y / 2.0 + PADDLE_HEIGHT, 0.0),
+    RigidBody::Kinematic,
+    Collider::rectangle(PADDLE_SIZE.x, PADDLE_SIZE.y),
+    CollisionLayers::new(
+      BreakoutLayer::Paddle,
+      [BreakoutLayer::Ball, BreakoutLayer::PowerUp],
+    ),
+  ));
+
+  commands.spawn((
+    Name::new("BreakoutRunInfo"),
+    RunInfoText,
+    DespawnOnExit(BREAKOUT_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 24.,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+  ));
+
+  *run = BreakoutRun::default();
+  commands.trigger(LoadLevelEvent { index: 0 });
+}
+
+fn reset_run_system(mut run: ResMut<BreakoutRun>) {
+  *run = BreakoutRun::default();
+}
+
+/// Ball in play. The paddle steers it, so it keeps a constant speed instead of whatever the
+/// bounces leave it with, see [`ball_speed_system`].
+fn spawn_ball(commands: &mut Commands, translation: Vec2, velocity: Vec2) {
+  commands.spawn((
+    Name::new("Ball"),
+    Ball,
+    DespawnOnExit(BREAKOUT_SCOPE),
+    // Мяч в игре живёт до потери жизни, конца уровня или конца игры
+    DespawnOnExit(BreakoutGameState::Playing),
+    Sprite {
+      color: Color::WHITE,
+      custom_size: Some(Vec2::splat(BALL_RADIUS * 2.0)),
+      ..default()
+    },
+    Transform::from_translation(translation.extend(1.0)),
+    (
+      RigidBody::Dynamic,
+      Collider::circle(BALL_RADIUS),
+      // Упругий мяч без трения отскакивает от всего под тем же углом
+      Restitution::new(1.0).with_combine_rule(CoefficientCombine::Max),
+      Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+      GravityScale(0.0),
+      LockedAxes::ROTATION_LOCKED,
+      LinearVelocity(velocity),
+      SweptCcd::default(),
+      CollisionEventsEnabled,
+      CollisionLayers::new(
+        BreakoutLayer::Ball,
+        [
+          BreakoutLayer::Wall,
+          BreakoutLayer::Brick,
+          BreakoutLayer::Paddle,
+        ],
+      ),
+    ),
+  ));
+}
+
+/// Where the ball sits on top of the paddle before it is launched.
+fn served_ball_offset() -> Vec2 {
+  Vec2::new(0.0, PADDLE_SIZE.y / 2.0 + BALL_RADIUS + 1.0)
+}
+
+fn spawn_served_ball(mut commands: Commands, paddle_single: Single<Entity, With<Paddle>>) {
+  commands
+    .entity(*paddle_single)
+    .with_child((
+      Name::new("ServedBall"),
+      ServedBall,
+      DespawnOnExit(BreakoutGameState::Serving),
+      Sprite {
+        color: Color::WHITE,
+        custom_size: Some(Vec2::splat(BALL_RADIUS * 2.0)),
+        ..default()
+      },
+      Transform::from_translation(served_ball_offset().extend(1.0)),
+    ));
+}
+
+/// The paddle is kinematic, so it is moved by its velocity, slowed down to stop at the walls.
+fn paddle_movement_system(
+  paddle_single: Single<(&mut LinearVelocity, &Transform), With<Paddle>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  run: Res<BreakoutRun>,
+  time: Res<Time>,
+) {
+  let (mut velocity, transform) = paddle_single.into_inner();
+
+  let mut direction = 0.0;
+  if keyboard_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+    direction -= 1.0;
+  }
+  if keyboard_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+    direction += 1.0;
+  }
+
+  let max_x = (FIELD_SIZE.x - run.paddle_size().x) / 2.0;
+  let x = transform.translation.x;
+  let target_x = (x + direction * PADDLE_SPEED * time.delta_secs()).clamp(-max_x, max_x);
+
+  velocity.0 = Vec2::new((target_x - x) / time.delta_secs(), 0.0);
+}
+
+fn resize_paddle_system(
+  paddle_single: Single<(&mut Sprite, &mut Collider), With<Paddle>>,
+  run: Res<BreakoutRun>,
+) {
+  let (mut sprite, mut collider) = paddle_single.into_inner();
+  let size = run.paddle_size();
+
+  if sprite.custom_size != Some(size) {
+    sprite.custom_size = Some(size);
+    *collider = Collider::rectangle(size.x, size.y);
+  }
+}
+
+fn launch_ball_system(
+  mut commands: Commands,
+  mut next_state: ResMut<NextState<BreakoutGameState>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  paddle_single: Single<&Transform, With<Paddle>>,
+  run: Res<BreakoutRun>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::Space) {
+    return;
+  }
+
+  let angle = rand::random_range(-MAX_BOUNCE_ANGLE..MAX_BOUNCE_ANGLE) / 2.0;
+  let velocity = Vec2::from_angle(-angle).rotate(Vec2::Y) * run.ball_speed();
+
+  spawn_ball(
+    &mut commands,
+    paddle_single.translation.truncate() + served_ball_offset(),
+    velocity,
+  );
+
+  next_state.set(BreakoutGameState::Playing);
+}
+
+fn ball_speed_system(
+  mut ball_query: Query<&mut LinearVelocity, With<Ball>>,
+  run: Res<BreakoutRun>,
+) {
+  let speed = run.ball_speed();
+
+  for mut velocity in &mut ball_query {
+    let mut direction = velocity.0.normalize_or(Vec2::Y);
+
+    if direction.y.abs() < MIN_VERTICAL_SPEED_RATIO {
+      direction.y = MIN_VERTICAL_SPEED_RATIO.copysign(direction.y);
+      direction.x = (1.0 - direction.y * direction.y)
+        .sqrt()
+        .copysign(direction.x);
+    }
+
+    velocity.0 = direction * speed;
+  }
+}
+
+/// Losing the last ball in play costs a life.
+fn ball_drain_system(
+  mut commands: Commands,
+  mut run: ResMut<BreakoutRun>,
+  mut next_state: ResMut<NextState<BreakoutGameState>>,
+  ball_query: Query<(Entity, &Transform), With<Ball>>,
+) {
+  let mut balls_left = 0;
+
+  for (entity, transform) in &ball_query {
+    if transform.translation.y < -FIELD_SIZE.y / 2.0 - BOTTOM_MARGIN {
+      commands.entity(entity).despawn();
+    } else {
+      balls_left += 1;
+    }
+  }
+
+  if balls_left > 0 {
+    return;
+  }
+
+  run.lives = run.lives.saturating_sub(1);
+  run.wide_paddle = None;
+
+  next_state.set(if run.lives == 0 {
+    BreakoutGameState::GameOver
+  } else {
+    BreakoutGameState::Serving
+  });
+}
+
+/// Balls chip the bricks they hit and bounce off the paddle at an angle that depends on where
+/// they hit it, so the player can aim.
+fn ball_collision_system(
+  mut commands: Commands,
+  mut collision_messages: MessageReader<CollisionStart>,
+  mut run: ResMut<BreakoutRun>,
+  mut next_state: ResMut<NextState<BreakoutGameState>>,
+  mut ball_query: Query<(&Transform, &mut LinearVelocity), With<Ball>>,
+  mut brick_query: Query<(&mut Brick, &mut Sprite, &Transform)>,
+  paddle_single: Single<(Entity, &Transform), With<Paddle>>,
+) {
+  let (paddle, paddle_transform) = *paddle_single;
+
+  for event in collision_messages.read() {
+    let (ball, other) = if ball_query.contains(event.collider1) {
+      (event.collider1, event.collider2)
+    } else if ball_query.contains(event.collider2) {
+      (event.collider2, event.collider1)
+    } else {
+      continue;
+    };
+
+    if other == paddle {
+      let Ok((transform, mut velocity)) = ball_query.get_mut(ball) else {
+        continue;
+      };
+
+      let offset =
+        (transform.translation.x - paddle_transform.translation.x) / (run.paddle_size().x / 2.0);
+      let angle = offset.clamp(-1.0, 1.0) * MAX_BOUNCE_ANGLE;
+      velocity.0 = Vec2::from_angle(-angle).rotate(Vec2::Y) * run.ball_speed();
+      continue;
+    }
+
+    let Ok((mut brick, mut sprite, transform)) = brick_query.get_mut(other) else {
+      continue;
+    };
+
+    // Кирпич, разбитый другим мячом в этом же кадре, ещё не удалён
+    let hits = match brick.hits {
+      None | Some(0) => continue,
+      Some(hits) => hits - 1,
+    };
+
+    brick.hits = Some(hits);
+    run.score += BRICK_POINTS;
+
+    if hits > 0 {
+      sprite.color = brick.color();
+      continue;
+    }
+
+    commands.entity(other).despawn();
+    if let Some(kind) = brick.drop {
+      spawn_power_up(&mut commands, kind, transform.translation.truncate());
+    }
+
+    run.bricks_left -= 1;
+    if run.bricks_left > 0 {
+      continue;
+    }
+
+    match next_level(run.level) {
+      Some(index) => commands.trigger(LoadLevelEvent { index }),
+      None => next_state.set(BreakoutGameState::Win),
+    }
+  }
+}
+
+fn update_run_info_system(
+  mut run_info_query: Query<&mut Text, With<RunInfoText>>,
+  run: Res<BreakoutRun>,
+  profile: Res<Profile>,
+) {
+  for mut text in &mut run_info_query {
+    text.0 = format!(
+      "{}  Lives {}  Score {}  Best {}",
+      run.level_name,
+      run.lives,
+      run.score,
+      profile
+        .best_score(&GameType::Breakout.to_string())
+        .max(run.score)
+    );
+  }
+}
+
+fn submit_score_system(
+  mut game_result_messages: MessageWriter<GameResultMessage>,
+  run: Res<BreakoutRun>,
+) {
+  game_result_messages.write(GameResultMessage {
+    game: GameType::Breakout,
+    score: run.score,
+  });
+}
+
+fn game_over_enter_system(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  run: Res<BreakoutRun>,
+) {
+  GameMenuBuilder::new()
+    .title("Game over")
+    .line(format!("Score {}  reached {}", run.score, run.level_name))
+    .option("Play again", BreakoutMenuAction::PlayAgain)
+    .option("Back to lobby", BreakoutMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(BreakoutGameState::GameOver),
+    );
+}
+
+fn win_enter_system(mut commands: Commands, font_assets: Res<FontAssets>, run: Res<BreakoutRun>) {
+  GameMenuBuilder::new()
+    .title("All bricks cleared!")
+    .line(format!("Score {}  lives left {}", run.score, run.lives))
+    .option("Play again", BreakoutMenuAction::PlayAgain)
+    .option("Back to lobby", BreakoutMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(BreakoutGameState::Win),
+    );
+}
+
+fn breakout_menu_observer(
+  event: On<GameMenuSelectedEvent<BreakoutMenuAction>>,
+  mut commands: Commands,
+  mut run: ResMut<BreakoutRun>,
+  mut next_state: ResMut<NextState<BreakoutGameState>>,
+  mut game_exit_messages: MessageWriter<GameExitMessage>,
+) {
+  match event.action {
+    BreakoutMenuAction::PlayAgain => {
+      *run = BreakoutRun::default();
+      commands.trigger(LoadLevelEvent { index: 0 });
+    }
+    BreakoutMenuAction::BackToLobby => {
+      next_state.set(BreakoutGameState::NotStarted);
+      game_exit_messages.write(GameExitMessage);
+    }
+  }
+}
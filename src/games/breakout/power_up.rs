@@ -0,0 +1,137 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+  BREAKOUT_SCOPE, Ball, BreakoutGameState, BreakoutLayer, BreakoutRun, FIELD_SIZE, Paddle,
+  breakout_running, spawn_ball,
+};
+
+const POWER_UP_SIZE: Vec2 = Vec2::new(14.0, 6.0);
+/// Power-ups fall under the breakout gravity, damped so that they don't speed up for too long.
+const POWER_UP_DAMPING: f32 = 1.5;
+const POWER_UP_POINTS: u32 = 5;
+/// Every ball in play splits into itself and two more, turned this far to either side.
+const MULTI_BALL_SPREAD: f32 = std::f32::consts::FRAC_PI_6;
+/// Multi-ball stops adding balls past this many.
+const MAX_BALLS: usize = 12;
+const WIDE_PADDLE_SECONDS: f32 = 15.0;
+
+pub(super) struct BreakoutPowerUpPlugin;
+
+impl Plugin for BreakoutPowerUpPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        FixedUpdate,
+        (wide_paddle_timer_system, missed_power_up_system).run_if(breakout_running),
+      )
+      .add_systems(
+        Update,
+        catch_power_up_system.run_if(in_state(BreakoutGameState::Playing)),
+      );
+  }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum PowerUpKind {
+  MultiBall,
+  WidePaddle,
+}
+
+impl PowerUpKind {
+  pub fn color(&self) -> Color {
+    match self {
+      PowerUpKind::MultiBall => Color::srgb(0.3, 0.8, 0.95),
+      PowerUpKind::WidePaddle => Color::srgb(0.85, 0.4, 0.9),
+    }
+  }
+}
+
+/// Power-up falling from a broken brick. Catching it with the paddle applies it.
+pub(super) fn spawn_power_up(commands: &mut Commands, kind: PowerUpKind, translation: Vec2) {
+  commands.spawn((
+    Name::new("PowerUp"),
+    kind,
+    DespawnOnExit(BREAKOUT_SCOPE),
+    // Непойманные бонусы пропадают вместе с мячом
+    DespawnOnExit(BreakoutGameState::Playing),
+    Sprite {
+      color: kind.color(),
+      custom_size: Some(POWER_UP_SIZE),
+      ..default()
+    },
+    Transform::from_translation(translation.extend(1.0)),
+    RigidBody::Dynamic,
+    Collider::rectangle(POWER_UP_SIZE.x, POWER_UP_SIZE.y),
+    Sensor,
+    LockedAxes::ROTATION_LOCKED,
+    LinearDamping(POWER_UP_DAMPING),
+    CollisionEventsEnabled,
+    CollisionLayers::new(BreakoutLayer::PowerUp, BreakoutLayer::Paddle),
+  ));
+}
+
+fn catch_power_up_system(
+  mut commands: Commands,
+  mut collision_messages: MessageReader<CollisionStart>,
+  mut run: ResMut<BreakoutRun>,
+  power_up_query: Query<&PowerUpKind>,
+  paddle_query: Query<(), With<Paddle>>,
+  ball_query: Query<(&Transform, &LinearVelocity), With<Ball>>,
+) {
+  for event in collision_messages.read() {
+    let (power_up, kind) = match (
+      power_up_query.get(event.collider1),
+      power_up_query.get(event.collider2),
+    ) {
+      (Ok(kind), _) if paddle_query.contains(event.collider2) => (event.collider1, *kind),
+      (_, Ok(kind)) if paddle_query.contains(event.collider1) => (event.collider2, *kind),
+      _ => continue,
+    };
+
+    commands.entity(power_up).despawn();
+    run.score += POWER_UP_POINTS;
+
+    match kind {
+      PowerUpKind::MultiBall => {
+        let mut balls = ball_query.iter().count();
+
+        for (transform, velocity) in &ball_query {
+          for angle in [-MULTI_BALL_SPREAD, MULTI_BALL_SPREAD] {
+            if balls >= MAX_BALLS {
+              break;
+            }
+
+            let velocity = Vec2::from_angle(angle).rotate(velocity.0);
+            spawn_ball(&mut commands, transform.translation.truncate(), velocity);
+            balls += 1;
+          }
+        }
+      }
+      PowerUpKind::WidePaddle => {
+        run.wide_paddle = Some(Timer::from_seconds(WIDE_PADDLE_SECONDS, TimerMode::Once));
+      }
+    }
+  }
+}
+
+fn wide_paddle_timer_system(mut run: ResMut<BreakoutRun>, time: Res<Time>) {
+  let Some(timer) = &mut run.wide_paddle else {
+    return;
+  };
+
+  if timer.tick(time.delta()).just_finished() {
+    run.wide_paddle = None;
+  }
+}
+
+fn missed_power_up_system(
+  mut commands: Commands,
+  power_up_query: Query<(Entity, &Transform), With<PowerUpKind>>,
+) {
+  for (entity, transform) in &power_up_query {
+    if transform.translation.y < -FIELD_SIZE.y / 2.0 - POWER_UP_SIZE.y {
+      commands.entity(entity).despawn();
+    }
+  }
+}
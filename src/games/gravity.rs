@@ -0,0 +1,45 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::state::ContextScope;
+
+/// Gives a minigame simulated with avian2d its own gravity while its context is active. The
+/// gravity the lobby had is put back when the session ends.
+pub(super) struct ScopedGravityPlugin {
+  pub scope: ContextScope,
+  pub gravity: Vec2,
+}
+
+impl Plugin for ScopedGravityPlugin {
+  fn build(&self, app: &mut App) {
+    let gravity = self.gravity;
+
+    app
+      .add_systems(
+        OnEnter(self.scope),
+        move |mut commands: Commands, mut current: ResMut<Gravity>| {
+          commands.insert_resource(LobbyGravity(current.0));
+          current.0 = gravity;
+        },
+      )
+      .add_systems(OnExit(self.scope), restore_lobby_gravity_system);
+  }
+
+  // Каждая физическая мини-игра добавляет свой экземпляр
+  fn is_unique(&self) -> bool {
+    false
+  }
+}
+
+/// Gravity the lobby had before the session started.
+#[derive(Resource)]
+struct LobbyGravity(Vec2);
+
+fn restore_lobby_gravity_system(
+  mut commands: Commands,
+  mut gravity: ResMut<Gravity>,
+  lobby_gravity: Res<LobbyGravity>,
+) {
+  gravity.0 = lobby_gravity.0;
+  commands.remove_resource::<LobbyGravity>();
+}
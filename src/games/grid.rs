@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::state::ContextScope;
+
+/// Gap between the tiles in the tileset of every grid game.
+const TILESET_SPACING: f32 = 1.0;
+
+/// Grid games draw their fields as tilemap layers, one tile per cell.
+pub(super) struct GridPlugin;
+
+impl Plugin for GridPlugin {
+  fn build(&self, app: &mut App) {
+    if !app.is_plugin_added::<TilemapPlugin>() {
+      app.add_plugins(TilemapPlugin);
+    }
+  }
+}
+
+/// Tilemap layer of a grid game, centered on `translation`.
+pub(super) struct GridLayer {
+  pub name: &'static str,
+  pub size: UVec2,
+  pub texture: Handle<Image>,
+  pub tile_size: f32,
+  pub translation: Vec3,
+  /// The layer and its tiles are despawned with this context.
+  pub scope: ContextScope,
+}
+
+/// Spawns a layer with a tile in every cell, as `tile` describes it.
+pub(super) fn spawn_grid_layer(
+  commands: &mut Commands,
+  layer: GridLayer,
+  tile: impl Fn(TilePos) -> (TileTextureIndex, TileColor, TileVisible),
+) -> Entity {
+  let map_size = TilemapSize {
+    x: layer.size.x,
+    y: layer.size.y,
+  };
+  let tilemap_entity = commands
+    .spawn(DespawnOnExit(layer.scope))
+    .id();
+  let mut tile_storage = TileStorage::empty(map_size);
+
+  for x in 0..map_size.x {
+    for y in 0..map_size.y {
+      let position = TilePos { x, y };
+      let (texture_index, color, visible) = tile(position);
+
+      let tile_entity = commands
+        .spawn((
+          TileBundle {
+            position,
+            tilemap_id: TilemapId(tilemap_entity),
+            texture_index,
+            color,
+            visible,
+            ..Default::default()
+          },
+          // Тайлы не дочерние карте, поэтому помечаем каждый
+          DespawnOnExit(layer.scope),
+        ))
+        .id();
+      tile_storage.set(&position, tile_entity);
+    }
+  }
+
+  let tile_size = TilemapTileSize {
+    x: layer.tile_size,
+    y: layer.tile_size,
+  };
+
+  commands.entity(tilemap_entity).insert((
+    Name::new(layer.name),
+    TilemapBundle {
+      grid_size: tile_size.into(),
+      map_type: TilemapType::Square,
+      size: map_size,
+      spacing: TilemapSpacing {
+        x: TILESET_SPACING,
+        y: TILESET_SPACING,
+      },
+      storage: tile_storage,
+      texture: TilemapTexture::Single(layer.texture),
+      tile_size,
+      anchor: TilemapAnchor::Center,
+      transform: Transform::from_translation(layer.translation),
+      ..Default::default()
+    },
+  ));
+
+  tilemap_entity
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+/// Delayed auto shift for a held key: it fires once when pressed, again after `delay`
+/// and then every `interval` until released.
+#[derive(Clone, Debug)]
+pub(super) struct KeyRepeat {
+  delay: Duration,
+  interval: Duration,
+  held: Option<Duration>,
+}
+
+impl KeyRepeat {
+  pub const fn new(delay: Duration, interval: Duration) -> Self {
+    Self {
+      delay,
+      interval,
+      held: None,
+    }
+  }
+
+  /// How many times the key fired during the last `delta`. With a zero interval a key held
+  /// past the delay fires as many times as the caller can use, e.g. to slide a piece to the wall.
+  pub fn update(&mut self, pressed: bool, delta: Duration) -> u32 {
+    if !pressed {
+      self.held = None;
+      return 0;
+    }
+
+    let Some(held) = self.held else {
+      self.held = Some(Duration::ZERO);
+      return 1;
+    };
+
+    let now = held + delta;
+    self.held = Some(now);
+
+    if self.interval.is_zero() {
+      return if now >= self.delay { u32::MAX } else { 0 };
+    }
+
+    self
+      .fired_by(now)
+      .saturating_sub(self.fired_by(held))
+  }
+
+  fn fired_by(&self, held: Duration) -> u32 {
+    if held < self.delay {
+      return 1;
+    }
+
+    let repeats = (held - self.delay).as_nanos() / self.interval.as_nanos();
+
+    2u32.saturating_add(repeats.min(u32::MAX as u128) as u32)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DELAY: Duration = Duration::from_millis(300);
+  const INTERVAL: Duration = Duration::from_millis(100);
+
+  fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+  }
+
+  #[test]
+  fn fires_once_on_press() {
+    let mut repeat = KeyRepeat::new(DELAY, INTERVAL);
+
+    assert_eq!(repeat.update(true, ms(16)), 1);
+    assert_eq!(repeat.update(true, ms(16)), 0);
+  }
+
+  #[test]
+  fn waits_for_the_delay_before_repeating() {
+    let mut repeat = KeyRepeat::new(DELAY, INTERVAL);
+    repeat.update(true, Duration::ZERO);
+
+    assert_eq!(repeat.update(true, ms(299)), 0);
+    assert_eq!(repeat.update(true, ms(1)), 1);
+  }
+
+  #[test]
+  fn repeats_every_interval_after_the_delay() {
+    let mut repeat = KeyRepeat::new(DELAY, INTERVAL);
+    repeat.update(true, Duration::ZERO);
+    repeat.update(true, DELAY);
+
+    assert_eq!(repeat.update(true, ms(99)), 0);
+    assert_eq!(repeat.update(true, ms(1)), 1);
+    assert_eq!(repeat.update(true, INTERVAL), 1);
+    // Длинный кадр засчитывает все пропущенные повторы
+    assert_eq!(repeat.update(true, ms(350)), 3);
+  }
+
+  #[test]
+  fn release_resets_the_delay() {
+    let mut repeat = KeyRepeat::new(DELAY, INTERVAL);
+    repeat.update(true, Duration::ZERO);
+    repeat.update(true, ms(500));
+
+    assert_eq!(repeat.update(false, ms(16)), 0);
+    assert_eq!(repeat.update(true, ms(16)), 1);
+    assert_eq!(repeat.update(true, ms(200)), 0);
+  }
+
+  #[test]
+  fn zero_interval_fires_without_limit_after_the_delay() {
+    let mut repeat = KeyRepeat::new(DELAY, Duration::ZERO);
+    repeat.update(true, Duration::ZERO);
+
+    assert_eq!(repeat.update(true, ms(100)), 0);
+    assert_eq!(repeat.update(true, DELAY), u32::MAX);
+  }
+}
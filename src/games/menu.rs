@@ -0,0 +1,330 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{game::FontAssets, state::GameState};
+
+const SELECTED_OPTION_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// Keyboard menu shown over a minigame, e.g. after game over or a win. Up/Down move the selection
+/// and Enter triggers [`GameMenuSelectedEvent`] with the chosen action.
+pub struct GameMenuPlugin<A>(PhantomData<A>);
+
+impl<A> Default for GameMenuPlugin<A> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<A: Clone + Send + Sync + 'static> Plugin for GameMenuPlugin<A> {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      Update,
+      (
+        game_menu_input_system::<A>.run_if(not(in_state(GameState::Paused))),
+        update_game_menu_system::<A>,
+      )
+        .chain(),
+    );
+  }
+}
+
+#[derive(Component)]
+pub struct GameMenu<A: Send + Sync + 'static> {
+  options: Vec<A>,
+  selected: usize,
+}
+
+#[derive(Component)]
+struct GameMenuOption {
+  menu: Entity,
+  index: usize,
+  label: String,
+}
+
+#[derive(Event)]
+pub struct GameMenuSelectedEvent<A: Send + Sync + 'static> {
+  pub action: A,
+}
+
+/// Lays out a menu from top to bottom: image, title, info lines, then the options.
+pub struct GameMenuBuilder<A> {
+  image: Option<Handle<Image>>,
+  title: Option<String>,
+  lines: Vec<String>,
+  options: Vec<(String, A)>,
+}
+
+impl<A> Default for GameMenuBuilder<A> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<A> GameMenuBuilder<A> {
+  pub fn new() -> Self {
+    Self {
+      image: None,
+      title: None,
+      lines: Vec::new(),
+      options: Vec::new(),
+    }
+  }
+
+  pub fn image(mut self, image: Handle<Image>) -> Self {
+    self.image = Some(image);
+    self
+  }
+
+  pub fn title(mut self, title: impl Into<String>) -> Self {
+    self.title = Some(title.into());
+    self
+  }
+
+  pub fn line(mut self, line: impl Into<String>) -> Self {
+    self.lines.push(line.into());
+    self
+  }
+
+  pub fn option(mut self, label: impl Into<String>, action: A) -> Self {
+    self
+      .options
+      .push((label.into(), action));
+    self
+  }
+}
+
+impl<A: Clone + Send + Sync + 'static> GameMenuBuilder<A> {
+  /// Spawns the menu with `bundle` on its root, so the caller can tag it for cleanup.
+  pub fn spawn(
+    self,
+    commands: &mut Commands,
+    font_assets: &FontAssets,
+    bundle: impl Bundle,
+  ) -> Entity {
+    let text = |text: String, font_size: f32| {
+      (
+        Text::new(text),
+        TextFont {
+          font: font_assets.regular.clone(),
+          font_size,
+          ..Default::default()
+        },
+        TextColor(Color::WHITE),
+      )
+    };
+
+    let (labels, actions): (Vec<_>, Vec<_>) = self.options.into_iter().unzip();
+
+    let menu = commands
+      .spawn((
+        Name::new("GameMenu"),
+        GameMenu {
+          options: actions,
+          selected: 0,
+        },
+        Node {
+          width: percent(100),
+          height: percent(100),
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::Center,
+          justify_content: JustifyContent::Center,
+          row_gap: px(8.),
+          ..default()
+        },
+        bundle,
+      ))
+      .id();
+
+    commands
+      .entity(menu)
+      .with_children(|parent| {
+        if let Some(image) = self.image {
+          parent.spawn((
+            Node {
+              height: px(300.),
+              ..default()
+            },
+            children![ImageNode { image, ..default() }],
+          ));
+        }
+
+        if let Some(title) = self.title {
+          parent.spawn(text(title, 40.));
+        }
+
+        for line in self.lines {
+          parent.spawn(text(line, 24.));
+        }
+
+        for (index, label) in labels.into_iter().enumerate() {
+          parent.spawn((
+            GameMenuOption {
+              menu,
+              index,
+              label: label.clone(),
+            },
+            text(label, 32.),
+          ));
+        }
+      });
+
+    menu
+  }
+}
+
+fn game_menu_input_system<A: Clone + Send + Sync + 'static>(
+  mut commands: Commands,
+  mut menu_query: Query<&mut GameMenu<A>>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+) {
+  for mut menu in &mut menu_query {
+    let last = menu.options.len().saturating_sub(1);
+
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+      menu.selected = menu.selected.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+      menu.selected = (menu.selected + 1).min(last);
+    }
+
+    // Enter поглощается, чтобы следующий экран не принял его за своё нажатие
+    if !keyboard_input.clear_just_pressed(KeyCode::Enter) {
+      continue;
+    }
+
+    if let Some(action) = menu.options.get(menu.selected) {
+      commands.trigger(GameMenuSelectedEvent {
+        action: action.clone(),
+      });
+    }
+  }
+}
+
+fn update_game_menu_system<A: Send + Sync + 'static>(
+  mut option_query: Query<(&GameMenuOption, &mut Text, &mut TextColor)>,
+  menu_query: Query<&GameMenu<A>, Changed<GameMenu<A>>>,
+) {
+  for (option, mut text, mut color) in &mut option_query {
+    let Ok(menu) = menu_query.get(option.menu) else {
+      continue;
+    };
+
+    let selected = option.index == menu.selected;
+
+    text.0 = if selected {
+      format!("> {}", option.label)
+    } else {
+      format!("  {}", option.label)
+    };
+    color.0 = if selected {
+      SELECTED_OPTION_COLOR
+    } else {
+      Color::WHITE
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::system::RunSystemOnce;
+
+  use super::*;
+
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  enum TestAction {
+    PlayAgain,
+    BackToLobby,
+  }
+
+  #[derive(Resource, Default)]
+  struct Selected(Vec<TestAction>);
+
+  fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, GameMenuPlugin::<TestAction>::default()));
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<Selected>();
+    app.add_observer(
+      |event: On<GameMenuSelectedEvent<TestAction>>, mut selected: ResMut<Selected>| {
+        selected.0.push(event.action);
+      },
+    );
+
+    app
+      .world_mut()
+      .run_system_once(|mut commands: Commands| {
+        let font_assets = FontAssets {
+          regular: Handle::default(),
+        };
+        GameMenuBuilder::new()
+          .option("Play again", TestAction::PlayAgain)
+          .option("Back to lobby", TestAction::BackToLobby)
+          .spawn(&mut commands, &font_assets, ());
+      })
+      .unwrap();
+
+    app
+  }
+
+  fn press(app: &mut App, key: KeyCode) {
+    let mut keyboard_input = app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>();
+    keyboard_input.clear();
+    keyboard_input.press(key);
+    keyboard_input.release(key);
+    app.update();
+  }
+
+  #[test]
+  fn enter_selects_the_first_option() {
+    let mut app = app();
+
+    press(&mut app, KeyCode::Enter);
+
+    assert_eq!(
+      app.world().resource::<Selected>().0,
+      [TestAction::PlayAgain]
+    );
+  }
+
+  #[test]
+  fn arrows_move_the_selection() {
+    let mut app = app();
+
+    press(&mut app, KeyCode::ArrowDown);
+    press(&mut app, KeyCode::ArrowDown);
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::ArrowUp);
+    press(&mut app, KeyCode::Enter);
+
+    assert_eq!(
+      app.world().resource::<Selected>().0,
+      [TestAction::BackToLobby, TestAction::PlayAgain]
+    );
+  }
+
+  #[test]
+  fn enter_is_consumed_by_the_menu() {
+    let mut app = app();
+
+    press(&mut app, KeyCode::Enter);
+    app.update();
+
+    assert_eq!(
+      app
+        .world()
+        .resource::<Selected>()
+        .0
+        .len(),
+      1
+    );
+    assert!(
+      !app
+        .world()
+        .resource::<ButtonInput<KeyCode>>()
+        .just_pressed(KeyCode::Enter)
+    );
+  }
+}
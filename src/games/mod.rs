@@ -3,24 +3,54 @@ use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 
 use crate::{
-  games::snake::SnakeGamePlugin,
-  player::{DespawnPlayerMessage, Player},
-  tilemap::DespawnTilemapMessage,
+  dialogue::{DialogueSystems, dialogue_is_active},
+  games::{
+    air_hockey::AirHockeyGamePlugin, breakout::BreakoutGamePlugin, grid::GridPlugin,
+    pause::PausePlugin, pinball::PinballGamePlugin, snake::SnakeGamePlugin,
+    tetris::TetrisGamePlugin,
+  },
+  npc::ShowSpeechBubbleEvent,
+  player::Player,
+  profile::Profile,
+  settings::settings_menu_is_open,
+  state::ContextScope,
 };
 
+mod air_hockey;
+mod breakout;
+mod gravity;
+mod grid;
+mod input_repeat;
+mod menu;
+mod pause;
+mod pinball;
 mod snake;
+mod tetris;
+
+pub use snake::{BoardItem, SNAKE_SKIN_NAMES, SnakeBoard};
+
+const POINTS_PER_TOKEN: u32 = 5;
+/// A broke player gets the balance topped up to this on every return to the lobby, so the
+/// cheapest cabinet can always be played and the save never gets stuck at zero.
+const FREE_TOKENS: u32 = 1;
+/// Rate of [`FixedUpdate`], where the minigames are simulated. Bevy's default, pinned here
+/// because snake speeds and falling pieces are counted in these ticks.
+const TICKS_PER_SECOND: f64 = 64.0;
 
 pub struct GamesPlugin;
 
 impl Plugin for GamesPlugin {
   fn build(&self, app: &mut App) {
     app.init_resource::<CurrentGameState>();
+    app.insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND));
 
     app.register_type::<GameType>();
     app.register_type::<GameMachine>();
 
     app.add_message::<GameMachineTriggerZoneEnterMessage>();
     app.add_message::<GameLaunchMessage>();
+    app.add_message::<GameResultMessage>();
+    app.add_message::<GameExitMessage>();
 
     app.add_observer(on_add_game_machine);
 
@@ -28,12 +58,23 @@ impl Plugin for GamesPlugin {
       Update,
       (
         check_game_machine_trigger_zone_collision_with_player_system,
-        launch_game_system,
+        launch_game_system.run_if(not(dialogue_is_active).and(not(settings_menu_is_open))),
       )
-        .chain(),
+        .chain()
+        .after(DialogueSystems),
     );
 
-    app.add_plugins(SnakeGamePlugin);
+    app.add_systems(Update, (record_game_result_system, exit_game_system));
+    app.add_systems(OnEnter(ContextScope::Lobby), free_tokens_system);
+
+    app.add_plugins((GridPlugin, PausePlugin));
+    app.add_plugins((
+      SnakeGamePlugin,
+      TetrisGamePlugin,
+      BreakoutGamePlugin,
+      PinballGamePlugin,
+      AirHockeyGamePlugin,
+    ));
   }
 }
 
@@ -48,17 +89,32 @@ impl Default for CurrentGameState {
   }
 }
 
-#[derive(Default, Clone, Copy, Eq, PartialEq, Debug, Reflect)]
+/// Whole number of fixed ticks closest to `seconds`, at least one.
+fn seconds_to_ticks(seconds: f32) -> u32 {
+  (seconds * TICKS_PER_SECOND as f32)
+    .round()
+    .max(1.0) as u32
+}
+
+#[derive(Default, Clone, Copy, Eq, PartialEq, Hash, Debug, Reflect)]
 #[reflect(Default)]
 pub enum GameType {
   #[default]
   Snake,
+  Tetris,
+  Breakout,
+  Pinball,
+  AirHockey,
 }
 
 impl std::fmt::Display for GameType {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       GameType::Snake => write!(f, "Snake"),
+      GameType::Tetris => write!(f, "Tetris"),
+      GameType::Breakout => write!(f, "Breakout"),
+      GameType::Pinball => write!(f, "Pinball"),
+      GameType::AirHockey => write!(f, "Air Hockey"),
     }
   }
 }
@@ -68,10 +124,32 @@ pub struct GameLaunchMessage {
   pub game: GameType,
 }
 
+/// Sent by a minigame when a run ends.
+#[derive(Message)]
+pub struct GameResultMessage {
+  pub game: GameType,
+  pub score: u32,
+}
+
+/// Sent by a minigame when the player leaves it. Leaving the minigame's [`ContextScope`]
+/// despawns the session, entering the lobby scope spawns the lobby back.
+#[derive(Message)]
+pub struct GameExitMessage;
+
 #[derive(Component, Default, Debug, Reflect)]
 #[reflect(Component)]
 struct GameMachine {
   game: GameType,
+  /// Tokens spent on every launch.
+  cost: u32,
+  /// Profile flag required to play. Empty means the cabinet is always unlocked.
+  unlock_requirement: String,
+}
+
+impl GameMachine {
+  fn is_unlocked(&self, profile: &Profile) -> bool {
+    self.unlock_requirement.is_empty() || profile.has_flag(&self.unlock_requirement)
+  }
 }
 
 #[derive(Component, Debug)]
@@ -86,11 +164,12 @@ struct GameMachineTriggerZoneEnterMessage {
 }
 
 fn launch_game_system(
+  mut commands: Commands,
   mut trigger_zone_messages: MessageReader<GameMachineTriggerZoneEnterMessage>,
   mut game_launch_messages: MessageWriter<GameLaunchMessage>,
-  mut despawn_tilemap_messages: MessageWriter<DespawnTilemapMessage>,
-  mut despawn_player_messages: MessageWriter<DespawnPlayerMessage>,
+  mut next_scope: ResMut<NextState<ContextScope>>,
   mut game_state: ResMut<CurrentGameState>,
+  mut profile: ResMut<Profile>,
   keyboard_input: Res<ButtonInput<KeyCode>>,
   game_machine_query: Query<&GameMachine>,
 ) {
@@ -107,19 +186,80 @@ fn launch_game_system(
       continue;
     };
 
+    if !game_machine.is_unlocked(&profile) {
+      commands.trigger(ShowSpeechBubbleEvent {
+        speaker: message.game_machine_entity,
+        text: String::from("Locked"),
+        seconds: 2.0,
+      });
+      break;
+    }
+
+    if !profile.spend_tokens(game_machine.cost) {
+      commands.trigger(ShowSpeechBubbleEvent {
+        speaker: message.game_machine_entity,
+        text: format!(
+          "Not enough tokens: {} needed, you have {}",
+          game_machine.cost, profile.tokens
+        ),
+        seconds: 2.0,
+      });
+      break;
+    }
+
     game_state.current_game = Some(game_machine.game);
 
     game_launch_messages.write(GameLaunchMessage {
       game: game_machine.game,
     });
 
-    despawn_tilemap_messages.write(DespawnTilemapMessage);
-    despawn_player_messages.write(DespawnPlayerMessage);
+    next_scope.set(ContextScope::Minigame(game_machine.game));
 
     break;
   }
 }
 
+fn record_game_result_system(
+  mut game_result_messages: MessageReader<GameResultMessage>,
+  mut profile: ResMut<Profile>,
+) {
+  for message in game_result_messages.read() {
+    if profile.submit_score(&message.game.to_string(), message.score) {
+      info!("New {} record: {}", message.game, message.score);
+    }
+
+    let earned_tokens = message.score / POINTS_PER_TOKEN;
+    if earned_tokens > 0 {
+      profile.tokens += earned_tokens;
+      info!("Earned {} tokens in {}", earned_tokens, message.game);
+    }
+  }
+}
+
+fn free_tokens_system(mut profile: ResMut<Profile>) {
+  if profile.tokens < FREE_TOKENS {
+    info!(
+      "Out of tokens, the house gives {}",
+      FREE_TOKENS - profile.tokens
+    );
+    profile.tokens = FREE_TOKENS;
+  }
+}
+
+fn exit_game_system(
+  mut game_exit_messages: MessageReader<GameExitMessage>,
+  mut next_scope: ResMut<NextState<ContextScope>>,
+  mut game_state: ResMut<CurrentGameState>,
+) {
+  if game_exit_messages.is_empty() {
+    return;
+  }
+  game_exit_messages.clear();
+
+  game_state.current_game = None;
+  next_scope.set(ContextScope::Lobby);
+}
+
 fn check_game_machine_trigger_zone_collision_with_player_system(
   mut trigger_zone_messages: MessageWriter<GameMachineTriggerZoneEnterMessage>,
   colliding_entities_query: Query<(Entity, &GameMachineInteractionZone, &CollidingEntities)>,
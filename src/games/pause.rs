@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+use crate::{camera::Overlay, game::FontAssets, state::GameState};
+
+/// Escape pauses any minigame. Virtual time stops, which freezes the fixed-step simulation and
+/// every animation, while the pause screen is drawn over the game as an [`Overlay`].
+pub(super) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+  fn build(&self, app: &mut App) {
+    app.add_sub_state::<GameState>();
+
+    app
+      .add_systems(
+        Update,
+        toggle_pause_system.run_if(resource_exists::<State<GameState>>),
+      )
+      .add_systems(OnEnter(GameState::Paused), pause_system)
+      .add_systems(OnExit(GameState::Paused), resume_system);
+  }
+}
+
+fn toggle_pause_system(
+  mut next_state: ResMut<NextState<GameState>>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  state: Res<State<GameState>>,
+) {
+  if !keyboard_input.clear_just_pressed(KeyCode::Escape) {
+    return;
+  }
+
+  next_state.set(match state.get() {
+    GameState::Playing => GameState::Paused,
+    GameState::Paused => GameState::Playing,
+  });
+}
+
+fn pause_system(
+  mut commands: Commands,
+  mut time: ResMut<Time<Virtual>>,
+  font_assets: Res<FontAssets>,
+) {
+  time.pause();
+
+  let text = |text: &str, font_size: f32| {
+    (
+      Text::new(text),
+      TextFont {
+        font: font_assets.regular.clone(),
+        font_size,
+        ..Default::default()
+      },
+      TextColor(Color::WHITE),
+    )
+  };
+
+  commands.spawn((
+    Name::new("PauseUi"),
+    Overlay,
+    DespawnOnExit(GameState::Paused),
+    Node {
+      width: percent(100),
+      height: percent(100),
+      flex_direction: FlexDirection::Column,
+      align_items: AlignItems::Center,
+      justify_content: JustifyContent::Center,
+      row_gap: px(8.),
+      ..default()
+    },
+    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+    children![text("Paused", 40.), text("Esc to resume", 24.)],
+  ));
+}
+
+/// Also runs when the minigame is left while paused, since [`GameState`] is removed then.
+fn resume_system(mut time: ResMut<Time<Virtual>>) {
+  time.unpause();
+}
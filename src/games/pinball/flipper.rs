@@ -0,0 +1,156 @@
+use avian2d::prelude::*;
+use bevy::{prelude::*, transform::TransformSystems};
+
+use super::{PINBALL_SCOPE, PinballGameState, PinballLayer, PinballRun};
+
+pub(super) const FLIPPER_RADIUS: f32 = 3.0;
+const FLIPPER_RESTITUTION: f32 = 0.2;
+/// Angle of the flipper below the horizontal when it is down, and above it when it is up.
+const FLIPPER_DOWN_ANGLE: f32 = 0.5;
+const FLIPPER_UP_ANGLE: f32 = 0.45;
+/// The flipper swings up fast and falls back slower.
+const FLIPPER_UP_SPEED: f32 = 28.0;
+const FLIPPER_DOWN_SPEED: f32 = 12.0;
+
+pub(super) struct PinballFlipperPlugin;
+
+impl Plugin for PinballFlipperPlugin {
+  fn build(&self, app: &mut App) {
+    app.register_type::<FlipperSide>();
+    app.register_type::<PinballFlipper>();
+
+    app
+      .add_systems(
+        PostUpdate,
+        build_flippers_system.after(TransformSystems::Propagate),
+      )
+      .add_systems(
+        FixedUpdate,
+        flipper_control_system.run_if(in_state(PinballGameState::Playing)),
+      );
+  }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub(super) enum FlipperSide {
+  #[default]
+  Left,
+  Right,
+}
+
+impl FlipperSide {
+  fn keys(&self) -> [KeyCode; 2] {
+    match self {
+      FlipperSide::Left => [KeyCode::ArrowLeft, KeyCode::KeyZ],
+      FlipperSide::Right => [KeyCode::ArrowRight, KeyCode::Slash],
+    }
+  }
+
+  /// Direction the flipper turns in to swing up: the left one counterclockwise, the right one
+  /// clockwise.
+  fn up(&self) -> f32 {
+    match self {
+      FlipperSide::Left => 1.0,
+      FlipperSide::Right => -1.0,
+    }
+  }
+}
+
+/// Pivot of a flipper, a point object of the table map. The flipper points from it to the
+/// middle of the table.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballFlipper {
+  side: FlipperSide,
+  length: f32,
+}
+
+/// Dynamic body of a flipper, pinned to a static anchor at its pivot by a revolute joint.
+/// Its angle limits stop the flipper at the top and the bottom of the swing.
+#[derive(Component)]
+pub(super) struct Flipper {
+  side: FlipperSide,
+  length: f32,
+}
+
+impl Flipper {
+  /// End of the flipper relative to its pivot, before it is rotated.
+  pub fn tip(&self) -> Vec2 {
+    Vec2::X * self.length * self.side.up()
+  }
+}
+
+/// The pivot's flipper has been spawned.
+#[derive(Component)]
+struct FlipperBuilt;
+
+/// Flippers are spawned outside of the map's hierarchy, so they wait until the pivots have their
+/// place on the table.
+fn build_flippers_system(
+  mut commands: Commands,
+  pivot_query: Query<(Entity, &PinballFlipper, &GlobalTransform), Without<FlipperBuilt>>,
+) {
+  for (entity, pivot, transform) in &pivot_query {
+    let translation = transform.translation().truncate();
+    let flipper = Flipper {
+      side: pivot.side,
+      length: pivot.length,
+    };
+    // Угол шарнира — поворот флиппера относительно неподвижного якоря
+    let down = -FLIPPER_DOWN_ANGLE * pivot.side.up();
+    let up = FLIPPER_UP_ANGLE * pivot.side.up();
+
+    let anchor = commands
+      .spawn((
+        Name::new("FlipperAnchor"),
+        DespawnOnExit(PINBALL_SCOPE),
+        RigidBody::Static,
+        Transform::from_translation(translation.extend(0.0)),
+      ))
+      .id();
+
+    let body = commands
+      .spawn((
+        Name::new("Flipper"),
+        DespawnOnExit(PINBALL_SCOPE),
+        RigidBody::Dynamic,
+        Collider::capsule_endpoints(FLIPPER_RADIUS, Vec2::ZERO, flipper.tip()),
+        Restitution::new(FLIPPER_RESTITUTION),
+        GravityScale(0.0),
+        CollisionLayers::new(PinballLayer::Table, PinballLayer::Ball),
+        Transform::from_translation(translation.extend(1.0))
+          .with_rotation(Quat::from_rotation_z(down)),
+        flipper,
+      ))
+      .id();
+
+    commands.spawn((
+      Name::new("FlipperJoint"),
+      DespawnOnExit(PINBALL_SCOPE),
+      RevoluteJoint::new(anchor, body).with_angle_limits(down.min(up), down.max(up)),
+    ));
+
+    commands
+      .entity(entity)
+      .insert(FlipperBuilt);
+  }
+}
+
+/// A tilted table doesn't answer the flippers until the ball is lost.
+fn flipper_control_system(
+  mut flipper_query: Query<(&Flipper, &mut AngularVelocity)>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  run: Res<PinballRun>,
+) {
+  for (flipper, mut angular_velocity) in &mut flipper_query {
+    let up = flipper.side.up();
+    let raised = !run.tilted && keyboard_input.any_pressed(flipper.side.keys());
+
+    angular_velocity.0 = if raised {
+      up * FLIPPER_UP_SPEED
+    } else {
+      -up * FLIPPER_DOWN_SPEED
+    };
+  }
+}
@@ -0,0 +1,491 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::{
+  game::FontAssets,
+  games::{
+    CurrentGameState, GameExitMessage, GameResultMessage, GameType,
+    gravity::ScopedGravityPlugin,
+    menu::{GameMenuBuilder, GameMenuPlugin, GameMenuSelectedEvent},
+  },
+  loading::{AssetCollection, AssetCollectionAppExt},
+  state::{ContextScope, GameState},
+};
+
+use flipper::PinballFlipperPlugin;
+use render::PinballRenderPlugin;
+use table::{PLUNGER_SIZE, PinballDrain, PinballPlunger, PinballTablePlugin};
+
+mod flipper;
+mod render;
+mod table;
+
+/// Every entity of a pinball session is despawned when the player leaves it.
+const PINBALL_SCOPE: ContextScope = ContextScope::Minigame(GameType::Pinball);
+
+const BACKGROUND_COLOR: Color = Color::srgb(0.04, 0.04, 0.08);
+/// Size of `table.tmx`, centered on the origin.
+const TABLE_SIZE: Vec2 = Vec2::new(240.0, 400.0);
+const TABLE_MARGIN: f32 = 8.0;
+
+/// Gravity along the slope of the table.
+const PINBALL_GRAVITY: Vec2 = Vec2::new(0.0, -320.0);
+
+const BALL_RADIUS: f32 = 4.0;
+/// Keeps the ball from tunneling through rails when the flippers and bumpers stack their kicks.
+const MAX_BALL_SPEED: f32 = 900.0;
+const START_BALLS: u32 = 3;
+
+/// Holding the plunger this long pulls it all the way back.
+const PLUNGER_PULL_SECONDS: f32 = 1.0;
+const MIN_LAUNCH_SPEED: f32 = 250.0;
+const MAX_LAUNCH_SPEED: f32 = 650.0;
+/// Balls closer than this to the plunger are launched when it is released.
+const PLUNGER_REACH: f32 = 12.0;
+/// A ball lost this soon after its first launch comes back to the plunger, once.
+const BALL_SAVE_SECONDS: f32 = 8.0;
+
+/// A nudge pushes the ball up and to a random side.
+const NUDGE_SPEED: Vec2 = Vec2::new(60.0, 80.0);
+/// Every nudge adds one to the tilt meter, which drains at this rate.
+const TILT_DECAY_PER_SECOND: f32 = 0.5;
+/// The table tilts when the meter reaches this.
+const TILT_LIMIT: f32 = 3.0;
+
+pub struct PinballGamePlugin;
+
+impl Plugin for PinballGamePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<PinballGameState>();
+
+    app.add_plugins((
+      PinballTablePlugin,
+      PinballFlipperPlugin,
+      PinballRenderPlugin,
+      GameMenuPlugin::<PinballMenuAction>::default(),
+      ScopedGravityPlugin {
+        scope: PINBALL_SCOPE,
+        gravity: PINBALL_GRAVITY,
+      },
+    ));
+
+    app.init_asset_collection::<PinballGameAssets>();
+
+    app.init_resource::<PinballRun>();
+
+    app
+      .add_systems(Update, setup.run_if(switched_to_game))
+      .add_systems(
+        Update,
+        (
+          serve_ball_system,
+          drain_system,
+          nudge_system.run_if(not(in_state(GameState::Paused))),
+        )
+          .run_if(in_state(PinballGameState::Playing)),
+      )
+      .add_systems(
+        FixedUpdate,
+        (plunger_system, ball_save_system, tilt_decay_system).run_if(pinball_running),
+      )
+      .add_systems(Update, update_run_info_system)
+      .add_systems(
+        OnEnter(PinballGameState::GameOver),
+        (submit_score_system, game_over_enter_system),
+      )
+      .add_systems(OnEnter(PinballGameState::NotStarted), reset_run_system)
+      .add_observer(pinball_menu_observer);
+  }
+}
+
+#[derive(States, Debug, Clone, Hash, Eq, PartialEq, Default)]
+enum PinballGameState {
+  #[default]
+  NotStarted,
+  Playing,
+  GameOver,
+}
+
+/// Choices of the game over menu.
+#[derive(Clone, Copy)]
+enum PinballMenuAction {
+  PlayAgain,
+  BackToLobby,
+}
+
+/// Collision layers of the pinball world. Nothing here is on the default layer, so the lobby's
+/// colliders are never touched.
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
+enum PinballLayer {
+  #[default]
+  Default,
+  Table,
+  Ball,
+}
+
+#[derive(Resource)]
+struct PinballGameAssets {
+  table: Handle<TiledMapAsset>,
+}
+
+impl FromWorld for PinballGameAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      table: asset_server.load("games/pinball/table.tmx"),
+    }
+  }
+}
+
+impl AssetCollection for PinballGameAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![self.table.clone().untyped()]
+  }
+}
+
+#[derive(Component)]
+struct PinballCamera;
+
+#[derive(Component)]
+struct Ball;
+
+#[derive(Component)]
+struct RunInfoText;
+
+/// One game from the first ball to the last one drained.
+#[derive(Resource)]
+struct PinballRun {
+  balls_left: u32,
+  score: u32,
+  /// Every point scored is multiplied by this. Knocking down the targets raises it, losing
+  /// the ball resets it.
+  multiplier: u32,
+  /// How far the plunger is pulled back, from 0 to 1.
+  plunger_pull: f32,
+  /// The first launch of every ball starts the ball save.
+  ball_save_ready: bool,
+  /// Time left to lose the ball without losing it, after a launch.
+  ball_save: Option<Timer>,
+  tilt: f32,
+  /// The flippers are dead and nothing scores until the ball is lost.
+  tilted: bool,
+}
+
+impl Default for PinballRun {
+  fn default() -> Self {
+    Self {
+      balls_left: START_BALLS,
+      score: 0,
+      multiplier: 1,
+      plunger_pull: 0.0,
+      ball_save_ready: true,
+      ball_save: None,
+      tilt: 0.0,
+      tilted: false,
+    }
+  }
+}
+
+impl PinballRun {
+  fn award(&mut self, points: u32) {
+    if !self.tilted {
+      self.score += points * self.multiplier;
+    }
+  }
+}
+
+fn switched_to_game(config: Res<CurrentGameState>) -> bool {
+  config.is_changed() && config.current_game == Some(GameType::Pinball)
+}
+
+/// A pending state change stops the fixed ticks too, as in the other minigames.
+fn pinball_running(
+  state: Res<State<PinballGameState>>,
+  next_state: Res<NextState<PinballGameState>>,
+) -> bool {
+  *state.get() == PinballGameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+fn setup(
+  mut commands: Commands,
+  mut run: ResMut<PinballRun>,
+  mut next_state: ResMut<NextState<PinballGameState>>,
+  pinball_game_assets: Res<PinballGameAssets>,
+  font_assets: Res<FontAssets>,
+) {
+  let view_size = TABLE_SIZE + TABLE_MARGIN * 2.0;
+
+  let mut projection = OrthographicProjection::default_2d();
+  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
+    min_width: view_size.x,
+    min_height: view_size.y,
+  };
+
+  commands.spawn((
+    Name::new("PinballCamera"),
+    PinballCamera,
+    DespawnOnExit(PINBALL_SCOPE),
+    Camera2d,
+    Camera {
+      order: 1,
+      clear_color: ClearColorConfig::Custom(BACKGROUND_COLOR),
+      ..Default::default()
+    },
+    Projection::Orthographic(projection),
+  ));
+
+  // Стол собирается из объектов карты, см. PinballTablePlugin
+  commands.spawn((
+    Name::new("PinballTable"),
+    TiledMap(pinball_game_assets.table.clone()),
+    TilemapAnchor::Center,
+    DespawnOnExit(PINBALL_SCOPE),
+  ));
+
+  commands.spawn((
+    Name::new("PinballRunInfo"),
+    RunInfoText,
+    DespawnOnExit(PINBALL_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 24.,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+  ));
+
+  *run = PinballRun::default();
+  next_state.set(PinballGameState::Playing);
+}
+
+fn reset_run_system(mut run: ResMut<PinballRun>) {
+  *run = PinballRun::default();
+}
+
+/// Where a new ball rests on the plunger.
+fn served_ball_offset() -> Vec2 {
+  Vec2::new(0.0, PLUNGER_SIZE.y / 2.0 + BALL_RADIUS + 1.0)
+}
+
+/// Puts a new ball on the plunger whenever none is in play. The plunger appears a few frames
+/// after the session starts, once the table map is spawned.
+fn serve_ball_system(
+  mut commands: Commands,
+  ball_query: Query<(), With<Ball>>,
+  plunger_single: Single<&GlobalTransform, With<PinballPlunger>>,
+) {
+  if !ball_query.is_empty() {
+    return;
+  }
+
+  let translation = plunger_single.translation().truncate() + served_ball_offset();
+
+  commands.spawn((
+    Name::new("PinballBall"),
+    Ball,
+    DespawnOnExit(PINBALL_SCOPE),
+    Transform::from_translation(translation.extend(2.0)),
+    RigidBody::Dynamic,
+    Collider::circle(BALL_RADIUS),
+    Restitution::new(0.3),
+    Friction::new(0.1),
+    MaxLinearSpeed(MAX_BALL_SPEED),
+    SweptCcd::default(),
+    CollisionEventsEnabled,
+    CollisionLayers::new(PinballLayer::Ball, PinballLayer::Table),
+  ));
+}
+
+/// Holding Space or Down pulls the plunger back, releasing it launches the ball resting on it.
+fn plunger_system(
+  mut run: ResMut<PinballRun>,
+  mut ball_query: Query<(&Transform, &mut LinearVelocity), With<Ball>>,
+  plunger_single: Single<&GlobalTransform, With<PinballPlunger>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  time: Res<Time>,
+) {
+  if keyboard_input.any_pressed([KeyCode::Space, KeyCode::ArrowDown]) {
+    run.plunger_pull = (run.plunger_pull + time.delta_secs() / PLUNGER_PULL_SECONDS).min(1.0);
+    return;
+  }
+
+  if run.plunger_pull == 0.0 {
+    return;
+  }
+
+  let rest = plunger_single.translation().truncate() + served_ball_offset();
+  let speed = MIN_LAUNCH_SPEED + (MAX_LAUNCH_SPEED - MIN_LAUNCH_SPEED) * run.plunger_pull;
+  run.plunger_pull = 0.0;
+
+  for (transform, mut velocity) in &mut ball_query {
+    if transform
+      .translation
+      .truncate()
+      .distance(rest)
+      > PLUNGER_REACH
+    {
+      continue;
+    }
+
+    velocity.0 = Vec2::Y * speed;
+
+    // Спасённый мяч запускается заново уже без страховки
+    if std::mem::take(&mut run.ball_save_ready) {
+      run.ball_save = Some(Timer::from_seconds(BALL_SAVE_SECONDS, TimerMode::Once));
+    }
+  }
+}
+
+fn ball_save_system(mut run: ResMut<PinballRun>, time: Res<Time>) {
+  let Some(timer) = &mut run.ball_save else {
+    return;
+  };
+
+  if timer.tick(time.delta()).just_finished() {
+    run.ball_save = None;
+  }
+}
+
+/// Nudging the table with Up helps the ball out of trouble, but nudging too often tilts it.
+fn nudge_system(
+  mut run: ResMut<PinballRun>,
+  mut ball_query: Query<&mut LinearVelocity, With<Ball>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+  if run.tilted || !keyboard_input.just_pressed(KeyCode::ArrowUp) {
+    return;
+  }
+
+  let side = if rand::random::<bool>() { 1.0 } else { -1.0 };
+  for mut velocity in &mut ball_query {
+    velocity.0 += NUDGE_SPEED * Vec2::new(side, 1.0);
+  }
+
+  run.tilt += 1.0;
+  if run.tilt >= TILT_LIMIT {
+    info!("Pinball table tilted");
+    run.tilted = true;
+  }
+}
+
+fn tilt_decay_system(mut run: ResMut<PinballRun>, time: Res<Time>) {
+  if run.tilt > 0.0 {
+    run.tilt = (run.tilt - TILT_DECAY_PER_SECOND * time.delta_secs()).max(0.0);
+  }
+}
+
+/// A lost ball is served again while the ball save lasts. Otherwise it costs a ball, the
+/// multiplier and the tilt.
+fn drain_system(
+  mut commands: Commands,
+  mut collision_messages: MessageReader<CollisionStart>,
+  mut run: ResMut<PinballRun>,
+  mut next_state: ResMut<NextState<PinballGameState>>,
+  ball_query: Query<(), With<Ball>>,
+  drain_query: Query<(), With<PinballDrain>>,
+) {
+  for event in collision_messages.read() {
+    let ball = if ball_query.contains(event.collider1) && drain_query.contains(event.collider2) {
+      event.collider1
+    } else if ball_query.contains(event.collider2) && drain_query.contains(event.collider1) {
+      event.collider2
+    } else {
+      continue;
+    };
+
+    commands.entity(ball).despawn();
+
+    if !run.tilted && run.ball_save.take().is_some() {
+      info!("Pinball ball saved");
+      continue;
+    }
+
+    run.balls_left = run.balls_left.saturating_sub(1);
+    run.multiplier = 1;
+    run.tilt = 0.0;
+    run.tilted = false;
+    run.ball_save_ready = true;
+    run.ball_save = None;
+
+    if run.balls_left == 0 {
+      next_state.set(PinballGameState::GameOver);
+    }
+  }
+}
+
+fn update_run_info_system(
+  mut run_info_query: Query<&mut Text, With<RunInfoText>>,
+  run: Res<PinballRun>,
+) {
+  for mut text in &mut run_info_query {
+    let ball = (START_BALLS - run.balls_left + 1).min(START_BALLS);
+    text.0 = format!(
+      "Score {}\nBall {ball}/{START_BALLS}  x{}",
+      run.score, run.multiplier
+    );
+
+    if run.ball_save.is_some() {
+      text.0.push_str("\nBall save");
+    }
+
+    if run.tilted {
+      text.0.push_str("\nTILT");
+    } else if run.tilt >= TILT_LIMIT - 1.0 {
+      text.0.push_str("\nDanger");
+    }
+  }
+}
+
+fn submit_score_system(
+  mut game_result_messages: MessageWriter<GameResultMessage>,
+  run: Res<PinballRun>,
+) {
+  game_result_messages.write(GameResultMessage {
+    game: GameType::Pinball,
+    score: run.score,
+  });
+}
+
+fn game_over_enter_system(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  run: Res<PinballRun>,
+) {
+  GameMenuBuilder::new()
+    .title("Game over")
+    .line(format!("Score {}", run.score))
+    .option("Play again", PinballMenuAction::PlayAgain)
+    .option("Back to lobby", PinballMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(PinballGameState::GameOver),
+    );
+}
+
+fn pinball_menu_observer(
+  event: On<GameMenuSelectedEvent<PinballMenuAction>>,
+  mut run: ResMut<PinballRun>,
+  mut next_state: ResMut<NextState<PinballGameState>>,
+  mut game_exit_messages: MessageWriter<GameExitMessage>,
+) {
+  match event.action {
+    PinballMenuAction::PlayAgain => {
+      *run = PinballRun::default();
+      next_state.set(PinballGameState::Playing);
+    }
+    PinballMenuAction::BackToLobby => {
+      next_state.set(PinballGameState::NotStarted);
+      game_exit_messages.write(GameExitMessage);
+    }
+  }
+}
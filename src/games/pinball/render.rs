@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::camera::context_layers;
+
+use super::{
+  BALL_RADIUS, Ball, PINBALL_SCOPE, PinballRun,
+  flipper::{FLIPPER_RADIUS, Flipper},
+  table::{
+    Flash, PLUNGER_SIZE, PinballBumper, PinballDrain, PinballPlunger, PinballRamp, PinballTarget,
+    PinballWall, TargetDown, outline, shape_center,
+  },
+};
+
+const LINE_WIDTH: f32 = 2.0;
+/// How far the plunger's head is drawn pulled back at full charge.
+const PLUNGER_TRAVEL: f32 = 10.0;
+
+const WALL_COLOR: Color = Color::srgb(0.3, 0.75, 0.95);
+const BUMPER_COLOR: Color = Color::srgb(0.95, 0.35, 0.75);
+const TARGET_COLOR: Color = Color::srgb(0.95, 0.85, 0.3);
+const TARGET_DOWN_COLOR: Color = Color::srgb(0.35, 0.32, 0.15);
+const RAMP_COLOR: Color = Color::srgb(0.4, 0.9, 0.45);
+const DRAIN_COLOR: Color = Color::srgb(0.45, 0.12, 0.12);
+const FLASH_COLOR: Color = Color::WHITE;
+const PLUNGER_COLOR: Color = Color::srgb(0.95, 0.55, 0.25);
+
+/// The table has no sprites: every part is drawn as glowing lines by its own gizmo group, which
+/// only the pinball camera sees.
+pub(super) struct PinballRenderPlugin;
+
+impl Plugin for PinballRenderPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_gizmo_group::<PinballGizmos>();
+
+    app
+      .add_systems(Startup, configure_gizmos_system)
+      .add_systems(
+        Update,
+        (
+          draw_table_system,
+          draw_flippers_system,
+          draw_plunger_system,
+          draw_balls_system,
+        ),
+      );
+  }
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct PinballGizmos;
+
+fn configure_gizmos_system(mut config_store: ResMut<GizmoConfigStore>) {
+  let (config, _) = config_store.config_mut::<PinballGizmos>();
+  config.line.width = LINE_WIDTH;
+  config.render_layers = context_layers(PINBALL_SCOPE);
+}
+
+fn draw_object(
+  gizmos: &mut Gizmos<PinballGizmos>,
+  transform: &GlobalTransform,
+  object: &TiledObject,
+  color: Color,
+) {
+  let to_world = |point: Vec2| {
+    transform
+      .transform_point(point.extend(0.0))
+      .truncate()
+  };
+
+  if let TiledObject::Ellipse { width, .. } = object {
+    gizmos.circle_2d(to_world(shape_center(object)), width / 2.0, color);
+  } else {
+    gizmos.linestrip_2d(
+      outline(object)
+        .into_iter()
+        .map(to_world),
+      color,
+    );
+  }
+}
+
+fn draw_table_system(
+  mut gizmos: Gizmos<PinballGizmos>,
+  wall_query: Query<(&GlobalTransform, &TiledObject), With<PinballWall>>,
+  bumper_query: Query<(&GlobalTransform, &TiledObject, Has<Flash>), With<PinballBumper>>,
+  target_query: Query<(&GlobalTransform, &TiledObject, Has<TargetDown>), With<PinballTarget>>,
+  ramp_query: Query<(&GlobalTransform, &TiledObject, Has<Flash>), With<PinballRamp>>,
+  drain_query: Query<(&GlobalTransform, &TiledObject), With<PinballDrain>>,
+) {
+  let lit = |flash: bool, color: Color| if flash { FLASH_COLOR } else { color };
+
+  for (transform, object) in &wall_query {
+    draw_object(&mut gizmos, transform, object, WALL_COLOR);
+  }
+  for (transform, object, flash) in &bumper_query {
+    draw_object(&mut gizmos, transform, object, lit(flash, BUMPER_COLOR));
+  }
+  for (transform, object, down) in &target_query {
+    let color = if down {
+      TARGET_DOWN_COLOR
+    } else {
+      TARGET_COLOR
+    };
+    draw_object(&mut gizmos, transform, object, color);
+  }
+  for (transform, object, flash) in &ramp_query {
+    draw_object(&mut gizmos, transform, object, lit(flash, RAMP_COLOR));
+  }
+  for (transform, object) in &drain_query {
+    draw_object(&mut gizmos, transform, object, DRAIN_COLOR);
+  }
+}
+
+fn draw_flippers_system(
+  mut gizmos: Gizmos<PinballGizmos>,
+  flipper_query: Query<(&Flipper, &GlobalTransform)>,
+) {
+  for (flipper, transform) in &flipper_query {
+    let to_world = |point: Vec2| {
+      transform
+        .transform_point(point.extend(0.0))
+        .truncate()
+    };
+    let tip = flipper.tip();
+    let side = tip.perp().normalize_or_zero() * FLIPPER_RADIUS;
+
+    gizmos.line_2d(to_world(side), to_world(tip + side), Color::WHITE);
+    gizmos.line_2d(to_world(-side), to_world(tip - side), Color::WHITE);
+    gizmos.circle_2d(to_world(Vec2::ZERO), FLIPPER_RADIUS, Color::WHITE);
+    gizmos.circle_2d(to_world(tip), FLIPPER_RADIUS, Color::WHITE);
+  }
+}
+
+fn draw_plunger_system(
+  mut gizmos: Gizmos<PinballGizmos>,
+  plunger_query: Query<&GlobalTransform, With<PinballPlunger>>,
+  run: Res<PinballRun>,
+) {
+  for transform in &plunger_query {
+    let head = transform.translation().truncate() - Vec2::Y * run.plunger_pull * PLUNGER_TRAVEL;
+    gizmos.rect_2d(head, PLUNGER_SIZE, PLUNGER_COLOR);
+  }
+}
+
+fn draw_balls_system(mut gizmos: Gizmos<PinballGizmos>, ball_query: Query<&Transform, With<Ball>>) {
+  for transform in &ball_query {
+    let center = transform.translation.truncate();
+    // Линии гизмо не заливаются, поэтому рисуем шар парой колец
+    gizmos.circle_2d(center, BALL_RADIUS, Color::WHITE);
+    gizmos.circle_2d(center, BALL_RADIUS / 2.0, Color::WHITE);
+  }
+}
@@ -0,0 +1,303 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use super::{Ball, PinballGameState, PinballLayer, PinballRun};
+
+const WALL_RESTITUTION: f32 = 0.4;
+const BUMPER_RESTITUTION: f32 = 0.8;
+const TARGET_RESTITUTION: f32 = 0.5;
+/// Bumpers and ramps stay lit for this long after a hit.
+const FLASH_SECONDS: f32 = 0.15;
+/// Knocking down the whole bank of targets raises the multiplier by one, up to this.
+const MAX_MULTIPLIER: u32 = 5;
+
+/// Turns the objects of the table map into colliders. The kind of every part is a component
+/// set as a property of the object in Tiled, its shape is the object's shape: rectangles,
+/// ellipses and convex polygons are solid, polylines are thin rails.
+pub(super) struct PinballTablePlugin;
+
+impl Plugin for PinballTablePlugin {
+  fn build(&self, app: &mut App) {
+    app.register_type::<PinballWall>();
+    app.register_type::<PinballBumper>();
+    app.register_type::<PinballTarget>();
+    app.register_type::<PinballRamp>();
+    app.register_type::<PinballDrain>();
+    app.register_type::<PinballPlunger>();
+
+    app
+      .add_observer(table_part_observer::<PinballWall>)
+      .add_observer(table_part_observer::<PinballBumper>)
+      .add_observer(table_part_observer::<PinballTarget>)
+      .add_observer(table_part_observer::<PinballRamp>)
+      .add_observer(table_part_observer::<PinballDrain>)
+      .add_observer(table_part_observer::<PinballPlunger>);
+
+    app
+      .add_systems(OnEnter(PinballGameState::Playing), raise_targets_system)
+      .add_systems(
+        Update,
+        (
+          table_collision_system.run_if(in_state(PinballGameState::Playing)),
+          flash_system,
+        ),
+      );
+  }
+}
+
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballWall {}
+
+/// Kicks the ball away from its center. Round bumpers and slingshots are both bumpers.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballBumper {
+  points: u32,
+  /// Speed the ball leaves the bumper with, at least.
+  kick: f32,
+}
+
+/// Drop target. It falls when hit and the bank stands up again once all of it is down.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballTarget {
+  points: u32,
+}
+
+/// Gate at the end of a ramp, scoring every ball that runs through it.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballRamp {
+  points: u32,
+}
+
+/// Area below the flippers. A ball falling into it is lost.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballDrain {}
+
+/// Bottom of the plunger lane, where new balls are served and launched from.
+#[derive(Component, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub(super) struct PinballPlunger {}
+
+/// Size of the plunger's head, which the served ball rests on.
+pub(super) const PLUNGER_SIZE: Vec2 = Vec2::new(16.0, 6.0);
+
+/// Target knocked down, it doesn't collide until the bank is raised.
+#[derive(Component)]
+pub(super) struct TargetDown;
+
+/// Part that was just hit and is drawn lit.
+#[derive(Component)]
+pub(super) struct Flash(Timer);
+
+/// How an object of the table map collides.
+trait TablePart: Component {
+  /// Sensors only report the ball passing through them.
+  const SENSOR: bool = false;
+  const RESTITUTION: f32 = WALL_RESTITUTION;
+
+  fn collider(object: &TiledObject) -> Option<Collider> {
+    object_collider(object)
+  }
+}
+
+impl TablePart for PinballWall {}
+
+impl TablePart for PinballBumper {
+  const RESTITUTION: f32 = BUMPER_RESTITUTION;
+}
+
+impl TablePart for PinballTarget {
+  const RESTITUTION: f32 = TARGET_RESTITUTION;
+}
+
+impl TablePart for PinballRamp {
+  const SENSOR: bool = true;
+}
+
+impl TablePart for PinballDrain {
+  const SENSOR: bool = true;
+}
+
+impl TablePart for PinballPlunger {
+  // В Tiled плунжер — точка, размер головки задаётся здесь
+  fn collider(_: &TiledObject) -> Option<Collider> {
+    Some(Collider::rectangle(PLUNGER_SIZE.x, PLUNGER_SIZE.y))
+  }
+}
+
+/// Outline of a shape object relative to its entity. Closed shapes end where they start.
+/// Tiled places rectangles and ellipses by their top left corner.
+pub(super) fn outline(object: &TiledObject) -> Vec<Vec2> {
+  match object {
+    TiledObject::Rectangle { width, height } => vec![
+      Vec2::ZERO,
+      Vec2::new(*width, 0.0),
+      Vec2::new(*width, -*height),
+      Vec2::new(0.0, -*height),
+      Vec2::ZERO,
+    ],
+    TiledObject::Polygon { vertices } => vertices
+      .iter()
+      .chain(vertices.first())
+      .copied()
+      .collect(),
+    TiledObject::Polyline { vertices } => vertices.clone(),
+    _ => Vec::new(),
+  }
+}
+
+/// Center of a shape object relative to its entity.
+pub(super) fn shape_center(object: &TiledObject) -> Vec2 {
+  match object {
+    TiledObject::Rectangle { width, height } | TiledObject::Ellipse { width, height } => {
+      Vec2::new(*width, -*height) / 2.0
+    }
+    TiledObject::Polygon { vertices } if !vertices.is_empty() => {
+      vertices.iter().sum::<Vec2>() / vertices.len() as f32
+    }
+    _ => Vec2::ZERO,
+  }
+}
+
+fn object_collider(object: &TiledObject) -> Option<Collider> {
+  match object {
+    // Эллипсы на столе — круглые бамперы, так что хватает круга по ширине
+    TiledObject::Ellipse { width, .. } => Some(Collider::compound(vec![(
+      Position::new(shape_center(object)),
+      Rotation::default(),
+      Collider::circle(width / 2.0),
+    )])),
+    TiledObject::Rectangle { .. } | TiledObject::Polygon { .. } => {
+      Collider::convex_hull(outline(object))
+    }
+    TiledObject::Polyline { vertices } => Some(Collider::polyline(vertices.clone(), None)),
+    _ => None,
+  }
+}
+
+fn table_part_observer<T: TablePart>(
+  add: On<Add, T>,
+  mut commands: Commands,
+  object_query: Query<(&TiledObject, Option<&Name>)>,
+) {
+  let entity = add.event().entity;
+
+  let Ok((object, name)) = object_query.get(entity) else {
+    return;
+  };
+
+  let Some(collider) = T::collider(object) else {
+    warn!(
+      "Pinball table object {} has no shape a collider can be made of",
+      name.map_or("unnamed", Name::as_str)
+    );
+    return;
+  };
+
+  commands.entity(entity).insert((
+    RigidBody::Static,
+    collider,
+    Restitution::new(T::RESTITUTION),
+    CollisionLayers::new(PinballLayer::Table, PinballLayer::Ball),
+  ));
+
+  if T::SENSOR {
+    commands.entity(entity).insert(Sensor);
+  }
+}
+
+fn raise_targets_system(mut commands: Commands, target_query: Query<Entity, With<TargetDown>>) {
+  for entity in &target_query {
+    commands
+      .entity(entity)
+      .remove::<(TargetDown, ColliderDisabled)>();
+  }
+}
+
+fn table_collision_system(
+  mut commands: Commands,
+  mut collision_messages: MessageReader<CollisionStart>,
+  mut run: ResMut<PinballRun>,
+  mut ball_query: Query<(&Transform, &mut LinearVelocity), With<Ball>>,
+  bumper_query: Query<(&PinballBumper, &TiledObject, &GlobalTransform)>,
+  target_query: Query<(Entity, &PinballTarget), Without<TargetDown>>,
+  ramp_query: Query<&PinballRamp>,
+) {
+  let mut standing_targets = target_query.iter().count();
+
+  for event in collision_messages.read() {
+    let (ball, part) = if ball_query.contains(event.collider1) {
+      (event.collider1, event.collider2)
+    } else if ball_query.contains(event.collider2) {
+      (event.collider2, event.collider1)
+    } else {
+      continue;
+    };
+
+    if let Ok((bumper, object, transform)) = bumper_query.get(part) {
+      let Ok((ball_transform, mut velocity)) = ball_query.get_mut(ball) else {
+        continue;
+      };
+
+      let center = transform
+        .transform_point(shape_center(object).extend(0.0))
+        .truncate();
+      let direction = (ball_transform.translation.truncate() - center).normalize_or(Vec2::Y);
+      velocity.0 = direction * velocity.0.length().max(bumper.kick);
+
+      run.award(bumper.points);
+      commands
+        .entity(part)
+        .insert(Flash::new());
+    } else if let Ok((_, target)) = target_query.get(part) {
+      run.award(target.points);
+      commands
+        .entity(part)
+        .insert((TargetDown, ColliderDisabled));
+
+      standing_targets = standing_targets.saturating_sub(1);
+      if standing_targets > 0 {
+        continue;
+      }
+
+      // Весь ряд сбит: множитель растёт, мишени встают обратно
+      run.multiplier = (run.multiplier + 1).min(MAX_MULTIPLIER);
+      for (entity, _) in &target_query {
+        commands
+          .entity(entity)
+          .remove::<(TargetDown, ColliderDisabled)>();
+      }
+      standing_targets = target_query.iter().count();
+    } else if let Ok(ramp) = ramp_query.get(part) {
+      run.award(ramp.points);
+      commands
+        .entity(part)
+        .insert(Flash::new());
+    }
+  }
+}
+
+impl Flash {
+  fn new() -> Self {
+    Self(Timer::from_seconds(FLASH_SECONDS, TimerMode::Once))
+  }
+}
+
+fn flash_system(
+  mut commands: Commands,
+  mut flash_query: Query<(Entity, &mut Flash)>,
+  time: Res<Time>,
+) {
+  for (entity, mut flash) in &mut flash_query {
+    if flash.0.tick(time.delta()).is_finished() {
+      commands
+        .entity(entity)
+        .remove::<Flash>();
+    }
+  }
+}
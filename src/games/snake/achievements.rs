@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::achievements::{AchievementProgress, AchievementProgressEvent};
+
+use super::{Food, FoodEatenEvent, RequestStartGameEvent, SnakeGameState, SnakeGrowEvent};
+
+pub struct SnakeAchievementsPlugin;
+
+impl Plugin for SnakeAchievementsPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<RunStats>();
+
+    app
+      .add_observer(reset_run_stats_observer)
+      .add_observer(snake_grow_achievements_observer)
+      .add_observer(food_eaten_achievements_observer)
+      .add_systems(OnEnter(SnakeGameState::Win), win_achievements_system);
+  }
+}
+
+#[derive(Resource, Default)]
+struct RunStats {
+  red_food_eaten: u32,
+}
+
+fn reset_run_stats_observer(_: On<RequestStartGameEvent>, mut run_stats: ResMut<RunStats>) {
+  *run_stats = RunStats::default();
+}
+
+fn snake_grow_achievements_observer(event: On<SnakeGrowEvent>, mut commands: Commands) {
+  commands.trigger(AchievementProgressEvent {
+    id: "snake_long",
+    progress: AchievementProgress::AtLeast(event.length),
+  });
+}
+
+fn food_eaten_achievements_observer(
+  event: On<FoodEatenEvent>,
+  mut commands: Commands,
+  mut run_stats: ResMut<RunStats>,
+  food_query: Query<&Food>,
+) {
+  commands.trigger(AchievementProgressEvent {
+    id: "snake_gourmet",
+    progress: AchievementProgress::Add(1),
+  });
+
+  if let Ok(Food::Red { .. }) = food_query.get(event.food_entity) {
+    run_stats.red_food_eaten += 1;
+
+    commands.trigger(AchievementProgressEvent {
+      id: "snake_speed_freak",
+      progress: AchievementProgress::AtLeast(run_stats.red_food_eaten),
+    });
+  }
+}
+
+fn win_achievements_system(mut commands: Commands) {
+  commands.trigger(AchievementProgressEvent {
+    id: "snake_win",
+    progress: AchievementProgress::Add(1),
+  });
+}
@@ -0,0 +1,366 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::games::grid::{GridLayer, spawn_grid_layer};
+
+use super::{
+  BoardItem, CurrentLevel, Food, GridPosition, PowerUp, RequestStartGameEvent, SNAKE_SCOPE,
+  SnakeBoard, SnakeBody, SnakeGameAssets, SnakeGameState, SnakeSkin, level::ArenaBounds,
+};
+
+/// Side of one arena cell in world units. Every tileset of the arena uses tiles of this size.
+pub(super) const TILE_SIZE: f32 = 8.0;
+/// Width of the wall drawn around the arena, in tiles.
+const FRAME_TILES: u32 = 1;
+
+const OBSTACLE_COLOR: Color = Color::srgb(0.45, 0.4, 0.55);
+const PORTAL_COLORS: [Color; 4] = [
+  Color::srgb(0.3, 0.8, 1.0),
+  Color::srgb(1.0, 0.5, 0.9),
+  Color::srgb(1.0, 0.85, 0.3),
+  Color::srgb(0.5, 1.0, 0.5),
+];
+
+/// Tiles of `arena_tiles.png`.
+mod arena_tile {
+  pub const FLOOR: u32 = 0;
+  pub const WALL: u32 = 1;
+  /// White block, tinted for obstacles and portals.
+  pub const BLOCK: u32 = 2;
+}
+
+pub(super) struct SnakeArenaPlugin;
+
+impl Plugin for SnakeArenaPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(OnExit(SnakeGameState::NotStarted), spawn_arena)
+      .add_systems(OnEnter(SnakeGameState::Dying), hide_snake_layer_system)
+      .add_observer(show_snake_layer_observer)
+      .add_systems(
+        Update,
+        (
+          // Уровни бывают разного размера, поэтому слои арены создаются заново
+          spawn_arena.run_if(in_state(SNAKE_SCOPE).and(resource_changed::<CurrentLevel>)),
+          update_item_layers_system,
+          blink_power_up_tiles_system,
+          draw_snake_layer_system,
+        )
+          .chain(),
+      );
+  }
+}
+
+/// Every tilemap of the arena, replaced together when the level changes.
+#[derive(Component)]
+struct ArenaLayer;
+
+/// Floor, walls, obstacles and portals, with a frame of walls around the arena.
+#[derive(Component)]
+struct FloorLayer;
+
+#[derive(Component)]
+struct FoodLayer;
+
+#[derive(Component)]
+struct PowerUpLayer;
+
+/// The snake from [`SnakeBody`], one tile per segment.
+#[derive(Component)]
+struct SnakeLayer;
+
+/// Size of the world area the camera has to show: the arena with its frame.
+pub(super) fn arena_world_size(bounds: &ArenaBounds) -> Vec2 {
+  (bounds.size + FRAME_TILES * 2).as_vec2() * TILE_SIZE
+}
+
+fn floor_tile(level: &CurrentLevel, position: TilePos) -> (TileTextureIndex, TileColor) {
+  let wall = (TileTextureIndex(arena_tile::WALL), TileColor(Color::WHITE));
+
+  // Рамка вокруг арены
+  let (Some(x), Some(y)) = (
+    position.x.checked_sub(FRAME_TILES),
+    position.y.checked_sub(FRAME_TILES),
+  ) else {
+    return wall;
+  };
+  let position = GridPosition::new(x, y);
+
+  if !level.bounds.contains(&position) {
+    return wall;
+  }
+
+  if level.obstacles.contains(&position) {
+    return (
+      TileTextureIndex(arena_tile::BLOCK),
+      TileColor(OBSTACLE_COLOR),
+    );
+  }
+
+  if let Some(i) = level
+    .portals
+    .iter()
+    .position(|(a, b)| *a == position || *b == position)
+  {
+    return (
+      TileTextureIndex(arena_tile::BLOCK),
+      TileColor(PORTAL_COLORS[i % PORTAL_COLORS.len()]),
+    );
+  }
+
+  (TileTextureIndex(arena_tile::FLOOR), TileColor(Color::WHITE))
+}
+
+fn food_tile_index(food: &Food) -> u32 {
+  match food {
+    Food::Green { .. } => 0,
+    Food::Red { .. } => 1,
+    Food::Blue { .. } => 2,
+  }
+}
+
+fn spawn_arena(
+  mut commands: Commands,
+  snake_game_assets: Res<SnakeGameAssets>,
+  current_level: Res<CurrentLevel>,
+  arena_layer_query: Query<(Entity, &TileStorage), With<ArenaLayer>>,
+) {
+  for (entity, storage) in &arena_layer_query {
+    // Тайлы не дочерние карте
+    for tile_entity in storage.iter().flatten() {
+      commands.entity(*tile_entity).despawn();
+    }
+    commands.entity(entity).despawn();
+  }
+
+  let arena_size = current_level.bounds.size;
+  let hidden = |_| {
+    (
+      TileTextureIndex(0),
+      TileColor::default(),
+      TileVisible(false),
+    )
+  };
+
+  let floor = spawn_grid_layer(
+    &mut commands,
+    GridLayer {
+      name: "ArenaFloor",
+      size: arena_size + FRAME_TILES * 2,
+      texture: snake_game_assets.arena_tiles.clone(),
+      tile_size: TILE_SIZE,
+      translation: Vec3::new(0.0, 0.0, -1.0),
+      scope: SNAKE_SCOPE,
+    },
+    |position| {
+      let (texture_index, color) = floor_tile(&current_level, position);
+      (texture_index, color, TileVisible(true))
+    },
+  );
+  commands
+    .entity(floor)
+    .insert((ArenaLayer, FloorLayer));
+
+  let food = spawn_grid_layer(
+    &mut commands,
+    GridLayer {
+      name: "ArenaFood",
+      size: arena_size,
+      texture: snake_game_assets.food.clone(),
+      tile_size: TILE_SIZE,
+      translation: Vec3::new(0.0, 0.0, -0.5),
+      scope: SNAKE_SCOPE,
+    },
+    hidden,
+  );
+  commands
+    .entity(food)
+    .insert((ArenaLayer, FoodLayer));
+
+  let power_ups = spawn_grid_layer(
+    &mut commands,
+    GridLayer {
+      name: "ArenaPowerUps",
+      size: arena_size,
+      texture: snake_game_assets.effects.clone(),
+      tile_size: TILE_SIZE,
+      translation: Vec3::new(0.0, 0.0, -0.5),
+      scope: SNAKE_SCOPE,
+    },
+    hidden,
+  );
+  commands
+    .entity(power_ups)
+    .insert((ArenaLayer, PowerUpLayer));
+
+  let snake = spawn_grid_layer(
+    &mut commands,
+    GridLayer {
+      name: "ArenaSnake",
+      size: arena_size,
+      texture: snake_game_assets
+        .snake_skin_sheet
+        .clone(),
+      tile_size: TILE_SIZE,
+      translation: Vec3::ZERO,
+      scope: SNAKE_SCOPE,
+    },
+    hidden,
+  );
+  commands
+    .entity(snake)
+    .insert((ArenaLayer, SnakeLayer));
+}
+
+/// Flip that turns a tile counterclockwise by `quarter_turns`.
+pub(super) fn quarter_turns_flip(quarter_turns: u8) -> TileFlip {
+  match quarter_turns % 4 {
+    0 => TileFlip::default(),
+    1 => TileFlip {
+      x: false,
+      y: true,
+      d: true,
+    },
+    2 => TileFlip {
+      x: true,
+      y: true,
+      d: false,
+    },
+    _ => TileFlip {
+      x: true,
+      y: false,
+      d: true,
+    },
+  }
+}
+
+/// Redraws the food and power-up tiles of the cells whose item changed on the board.
+fn update_item_layers_system(
+  mut tile_query: Query<(&mut TileTextureIndex, &mut TileVisible)>,
+  mut board: ResMut<SnakeBoard>,
+  food_layer_single: Single<&TileStorage, With<FoodLayer>>,
+  power_up_layer_single: Single<&TileStorage, With<PowerUpLayer>>,
+  food_query: Query<&Food>,
+  power_up_query: Query<&PowerUp>,
+) {
+  // Сама доска от отрисовки не меняется
+  let changed = board
+    .bypass_change_detection()
+    .take_changed_items();
+
+  for cell in changed {
+    let position = TilePos {
+      x: cell.x,
+      y: cell.y,
+    };
+
+    let (food, power_up) = match board.item(cell) {
+      BoardItem::None => (None, None),
+      BoardItem::Food(entity) => (
+        food_query
+          .get(entity)
+          .ok()
+          .map(food_tile_index),
+        None,
+      ),
+      BoardItem::PowerUp(entity) => (
+        None,
+        power_up_query
+          .get(entity)
+          .ok()
+          .map(|power_up| power_up.kind.icon_index() as u32),
+      ),
+    };
+
+    for (storage, index) in [
+      (*food_layer_single, food),
+      (*power_up_layer_single, power_up),
+    ] {
+      let Some(tile_entity) = storage.get(&position) else {
+        continue;
+      };
+      let Ok((mut texture_index, mut visible)) = tile_query.get_mut(tile_entity) else {
+        continue;
+      };
+
+      visible.0 = index.is_some();
+      if let Some(index) = index {
+        texture_index.0 = index;
+      }
+    }
+  }
+}
+
+/// Power-ups blink by toggling their [`Visibility`]; the tile follows it.
+fn blink_power_up_tiles_system(
+  mut tile_query: Query<&mut TileVisible>,
+  power_up_layer_single: Single<&TileStorage, With<PowerUpLayer>>,
+  power_up_query: Query<(&GridPosition, &Visibility), (With<PowerUp>, Changed<Visibility>)>,
+) {
+  for (position, visibility) in power_up_query.iter() {
+    let Some(tile_entity) = power_up_layer_single.get(&TilePos {
+      x: position.x,
+      y: position.y,
+    }) else {
+      continue;
+    };
+
+    if let Ok(mut visible) = tile_query.get_mut(tile_entity) {
+      visible.0 = *visibility != Visibility::Hidden;
+    }
+  }
+}
+
+/// Redraws the cells of the snake layer whose pieces changed in [`SnakeBody`].
+fn draw_snake_layer_system(
+  mut tile_query: Query<(&mut TileTextureIndex, &mut TileFlip, &mut TileVisible)>,
+  mut body: ResMut<SnakeBody>,
+  snake_layer_single: Single<&TileStorage, With<SnakeLayer>>,
+  board: Res<SnakeBoard>,
+  snake_skin: Res<SnakeSkin>,
+) {
+  // Сама змейка от отрисовки не меняется
+  let changed = body
+    .bypass_change_detection()
+    .take_changed_cells();
+
+  for position in changed {
+    // Клетки прошлого уровня могут не поместиться в новый слой
+    let Some(tile_entity) = snake_layer_single.checked_get(&TilePos {
+      x: position.x,
+      y: position.y,
+    }) else {
+      continue;
+    };
+    let Ok((mut texture_index, mut flip, mut visible)) = tile_query.get_mut(tile_entity) else {
+      continue;
+    };
+
+    // Пустую клетку не ищем в теле
+    let piece = if board.snake_count(position.cell()) == 0 {
+      None
+    } else {
+      body.piece_at(&position)
+    };
+
+    visible.0 = piece.is_some();
+    if let Some(piece) = piece {
+      texture_index.0 = snake_skin.tile_index(piece.part);
+      *flip = quarter_turns_flip(piece.quarter_turns);
+    }
+  }
+}
+
+/// Tiles cannot fly apart, so the death animation hides the layer and bursts sprites instead.
+fn hide_snake_layer_system(mut snake_layer_single: Single<&mut Visibility, With<SnakeLayer>>) {
+  **snake_layer_single = Visibility::Hidden;
+}
+
+fn show_snake_layer_observer(
+  _: On<RequestStartGameEvent>,
+  mut snake_layer_query: Query<&mut Visibility, With<SnakeLayer>>,
+) {
+  for mut visibility in &mut snake_layer_query {
+    *visibility = Visibility::Inherited;
+  }
+}
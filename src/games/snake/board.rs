@@ -0,0 +1,416 @@
+use bevy::prelude::*;
+
+use super::{CurrentLevel, Food, GridPosition, PowerUp};
+
+pub(super) struct SnakeBoardPlugin;
+
+impl Plugin for SnakeBoardPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<SnakeBoard>();
+
+    app
+      .add_observer(on_add_food)
+      .add_observer(on_remove_food)
+      .add_observer(on_add_power_up)
+      .add_observer(on_remove_power_up);
+  }
+}
+
+/// Pickup lying on a board cell. A cell holds at most one.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardItem {
+  #[default]
+  None,
+  Food(Entity),
+  PowerUp(Entity),
+}
+
+/// Occupancy grid of the arena. The snake updates it along with [`SnakeBody`](super::SnakeBody),
+/// food and power-ups when they are spawned, moved or despawned, so lookups by cell never scan
+/// the entities.
+///
+/// Free cells are kept in a list with a back index, so a random free cell is picked
+/// without walking the whole grid. Cells whose item changed are remembered until the arena
+/// redraws them, see [`SnakeBoard::take_changed_items`].
+#[derive(Resource)]
+pub struct SnakeBoard {
+  size: UVec2,
+  blocked: Vec<bool>,
+  /// Segments can overlap for a few steps after growing, so this is a count.
+  snake: Vec<u16>,
+  items: Vec<BoardItem>,
+  free: Vec<u32>,
+  /// Index of every cell in `free`, or [`Self::NOT_FREE`].
+  free_slot: Vec<u32>,
+  open_cells: u32,
+  changed_items: Vec<UVec2>,
+}
+
+/// Starts as the board of the current level, every new level replaces it with its own.
+impl FromWorld for SnakeBoard {
+  fn from_world(world: &mut World) -> Self {
+    world
+      .get_resource_or_init::<CurrentLevel>()
+      .board()
+  }
+}
+
+impl SnakeBoard {
+  const NOT_FREE: u32 = u32::MAX;
+
+  /// Empty board where every cell is free.
+  pub fn new(size: UVec2) -> Self {
+    let cell_count = (size.x * size.y) as usize;
+
+    Self {
+      size,
+      blocked: vec![false; cell_count],
+      snake: vec![0; cell_count],
+      items: vec![BoardItem::None; cell_count],
+      free: (0..cell_count as u32).collect(),
+      free_slot: (0..cell_count as u32).collect(),
+      open_cells: cell_count as u32,
+      // Новая доска перерисовывается целиком
+      changed_items: (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+        .collect(),
+    }
+  }
+
+  pub fn size(&self) -> UVec2 {
+    self.size
+  }
+
+  fn index(&self, cell: UVec2) -> usize {
+    debug_assert!(cell.x < self.size.x && cell.y < self.size.y);
+
+    (cell.y * self.size.x + cell.x) as usize
+  }
+
+  fn cell(&self, index: u32) -> UVec2 {
+    UVec2::new(index % self.size.x, index / self.size.x)
+  }
+
+  /// Marks a wall, an obstacle or a portal. Nothing can be placed there afterwards.
+  pub fn block(&mut self, cell: UVec2) {
+    let index = self.index(cell);
+
+    if !self.blocked[index] {
+      self.blocked[index] = true;
+      self.open_cells -= 1;
+      self.update_free(index);
+    }
+  }
+
+  pub fn is_blocked(&self, cell: UVec2) -> bool {
+    self.blocked[self.index(cell)]
+  }
+
+  /// Cells that are not blocked, whether occupied or not.
+  pub fn open_cell_count(&self) -> u32 {
+    self.open_cells
+  }
+
+  pub fn add_snake(&mut self, cell: UVec2) {
+    let index = self.index(cell);
+
+    self.snake[index] += 1;
+    self.update_free(index);
+  }
+
+  pub fn remove_snake(&mut self, cell: UVec2) {
+    let index = self.index(cell);
+
+    self.snake[index] = self.snake[index].saturating_sub(1);
+    self.update_free(index);
+  }
+
+  /// Moves one segment from `from` to `to`.
+  pub fn move_snake(&mut self, from: UVec2, to: UVec2) {
+    if from != to {
+      self.remove_snake(from);
+      self.add_snake(to);
+    }
+  }
+
+  pub fn snake_count(&self, cell: UVec2) -> u16 {
+    self.snake[self.index(cell)]
+  }
+
+  pub fn item(&self, cell: UVec2) -> BoardItem {
+    self.items[self.index(cell)]
+  }
+
+  pub fn set_item(&mut self, cell: UVec2, item: BoardItem) {
+    let index = self.index(cell);
+
+    if self.items[index] != item {
+      self.items[index] = item;
+      self.changed_items.push(cell);
+    }
+    self.update_free(index);
+  }
+
+  /// Clears `cell` if it still holds `item`. It may already hold something else
+  /// when the item has been moved away before it was despawned.
+  pub fn remove_item(&mut self, cell: UVec2, item: BoardItem) {
+    if self.item(cell) == item {
+      self.set_item(cell, BoardItem::None);
+    }
+  }
+
+  pub fn move_item(&mut self, from: UVec2, to: UVec2) {
+    let item = self.item(from);
+
+    self.set_item(from, BoardItem::None);
+    self.set_item(to, item);
+  }
+
+  /// Cells whose item has changed since the last call, possibly repeated.
+  pub fn take_changed_items(&mut self) -> Vec<UVec2> {
+    std::mem::take(&mut self.changed_items)
+  }
+
+  /// Not blocked, no snake and no item.
+  pub fn is_free(&self, cell: UVec2) -> bool {
+    self.free_slot[self.index(cell)] != Self::NOT_FREE
+  }
+
+  pub fn free_cell_count(&self) -> usize {
+    self.free.len()
+  }
+
+  /// Random free cell that is not in `except`. `except` is meant for the few cells picked
+  /// earlier in the same frame, before the board has seen the spawned entities.
+  pub fn random_free_cell(&self, except: &[UVec2]) -> Option<UVec2> {
+    if self.free.is_empty() {
+      return None;
+    }
+
+    let start = rand::random_range(0..self.free.len());
+
+    (0..self.free.len())
+      .map(|offset| self.cell(self.free[(start + offset) % self.free.len()]))
+      .find(|cell| !except.contains(cell))
+  }
+
+  fn update_free(&mut self, index: usize) {
+    let is_free =
+      !self.blocked[index] && self.snake[index] == 0 && self.items[index] == BoardItem::None;
+    let slot = self.free_slot[index];
+
+    match (is_free, slot == Self::NOT_FREE) {
+      (true, true) => {
+        self.free_slot[index] = self.free.len() as u32;
+        self.free.push(index as u32);
+      }
+      (false, false) => {
+        // swap_remove: последняя свободная клетка занимает место удалённой
+        self.free.swap_remove(slot as usize);
+        if let Some(moved) = self.free.get(slot as usize) {
+          self.free_slot[*moved as usize] = slot;
+        }
+        self.free_slot[index] = Self::NOT_FREE;
+      }
+      _ => {}
+    }
+  }
+}
+
+fn on_add_food(
+  add: On<Add, Food>,
+  mut board: ResMut<SnakeBoard>,
+  position_query: Query<&GridPosition>,
+) {
+  if let Ok(position) = position_query.get(add.entity) {
+    board.set_item(position.cell(), BoardItem::Food(add.entity));
+  }
+}
+
+fn on_remove_food(
+  remove: On<Remove, Food>,
+  mut board: ResMut<SnakeBoard>,
+  position_query: Query<&GridPosition>,
+) {
+  if let Ok(position) = position_query.get(remove.entity) {
+    board.remove_item(position.cell(), BoardItem::Food(remove.entity));
+  }
+}
+
+fn on_add_power_up(
+  add: On<Add, PowerUp>,
+  mut board: ResMut<SnakeBoard>,
+  position_query: Query<&GridPosition>,
+) {
+  if let Ok(position) = position_query.get(add.entity) {
+    board.set_item(position.cell(), BoardItem::PowerUp(add.entity));
+  }
+}
+
+fn on_remove_power_up(
+  remove: On<Remove, PowerUp>,
+  mut board: ResMut<SnakeBoard>,
+  position_query: Query<&GridPosition>,
+) {
+  if let Ok(position) = position_query.get(remove.entity) {
+    board.remove_item(position.cell(), BoardItem::PowerUp(remove.entity));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SIZE: UVec2 = UVec2::new(4, 3);
+
+  fn food(index: u32) -> BoardItem {
+    BoardItem::Food(Entity::from_raw_u32(index).unwrap())
+  }
+
+  /// The free list holds exactly the free cells and every back index points at its cell.
+  fn assert_free_list(board: &SnakeBoard) {
+    let mut free_count = 0;
+
+    for y in 0..board.size.y {
+      for x in 0..board.size.x {
+        let cell = UVec2::new(x, y);
+        let index = board.index(cell);
+        let expected =
+          !board.blocked[index] && board.snake[index] == 0 && board.items[index] == BoardItem::None;
+
+        assert_eq!(board.is_free(cell), expected, "{cell}");
+        if expected {
+          free_count += 1;
+          assert_eq!(board.free[board.free_slot[index] as usize], index as u32);
+        }
+      }
+    }
+
+    assert_eq!(board.free_cell_count(), free_count);
+  }
+
+  #[test]
+  fn new_board_is_free() {
+    let board = SnakeBoard::new(SIZE);
+
+    assert_eq!(board.free_cell_count(), 12);
+    assert_eq!(board.open_cell_count(), 12);
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn blocked_cells_are_not_open() {
+    let mut board = SnakeBoard::new(SIZE);
+
+    board.block(UVec2::new(1, 1));
+    board.block(UVec2::new(1, 1));
+
+    assert!(board.is_blocked(UVec2::new(1, 1)));
+    assert_eq!(board.open_cell_count(), 11);
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn moving_the_snake_frees_the_cell_it_left() {
+    let mut board = SnakeBoard::new(SIZE);
+    let (from, to) = (UVec2::new(0, 0), UVec2::new(1, 0));
+    board.add_snake(from);
+
+    board.move_snake(from, to);
+
+    assert!(board.is_free(from));
+    assert!(!board.is_free(to));
+    assert_eq!(board.snake_count(to), 1);
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn grown_segments_share_the_tail_cell() {
+    let mut board = SnakeBoard::new(SIZE);
+    let tail = UVec2::new(2, 2);
+    board.add_snake(tail);
+
+    // Выросшие сегменты лежат в клетке хвоста, пока не расползутся
+    board.add_snake(tail);
+    board.add_snake(tail);
+    assert_eq!(board.snake_count(tail), 3);
+
+    board.remove_snake(tail);
+    board.remove_snake(tail);
+    assert!(!board.is_free(tail));
+    assert_free_list(&board);
+
+    board.remove_snake(tail);
+    assert!(board.is_free(tail));
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn spawned_food_takes_its_cell() {
+    let mut board = SnakeBoard::new(SIZE);
+    let cell = UVec2::new(3, 1);
+    board.take_changed_items();
+
+    board.set_item(cell, food(1));
+
+    assert_eq!(board.item(cell), food(1));
+    assert!(!board.is_free(cell));
+    assert_eq!(board.take_changed_items(), [cell]);
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn despawned_food_frees_its_cell() {
+    let mut board = SnakeBoard::new(SIZE);
+    let cell = UVec2::new(3, 1);
+    board.set_item(cell, food(1));
+
+    board.remove_item(cell, food(1));
+
+    assert_eq!(board.item(cell), BoardItem::None);
+    assert!(board.is_free(cell));
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn despawning_moved_food_keeps_the_new_item() {
+    let mut board = SnakeBoard::new(SIZE);
+    let cell = UVec2::new(3, 1);
+    board.set_item(cell, food(2));
+
+    board.remove_item(cell, food(1));
+
+    assert_eq!(board.item(cell), food(2));
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn moved_item_leaves_its_cell_free() {
+    let mut board = SnakeBoard::new(SIZE);
+    let (from, to) = (UVec2::new(0, 2), UVec2::new(1, 2));
+    board.set_item(from, food(1));
+
+    board.move_item(from, to);
+
+    assert!(board.is_free(from));
+    assert_eq!(board.item(to), food(1));
+    assert_free_list(&board);
+  }
+
+  #[test]
+  fn random_free_cell_skips_taken_cells() {
+    let mut board = SnakeBoard::new(UVec2::new(2, 2));
+    board.add_snake(UVec2::new(0, 0));
+    board.set_item(UVec2::new(1, 0), food(1));
+    board.block(UVec2::new(0, 1));
+
+    for _ in 0..16 {
+      assert_eq!(board.random_free_cell(&[]), Some(UVec2::new(1, 1)));
+    }
+    assert_eq!(board.random_free_cell(&[UVec2::new(1, 1)]), None);
+
+    board.add_snake(UVec2::new(1, 1));
+    assert_eq!(board.random_free_cell(&[]), None);
+    assert_free_list(&board);
+  }
+}
@@ -0,0 +1,328 @@
+use std::collections::{VecDeque, vec_deque};
+
+use bevy::prelude::*;
+
+use super::{GridPosition, SnakeDirection, SnakeSkin};
+
+/// Cell taken by one segment and the direction the head moved in when it entered it.
+#[derive(Clone, Debug, Reflect)]
+pub(super) struct BodyCell {
+  pub position: GridPosition,
+  pub direction: SnakeDirection,
+}
+
+/// How a segment is drawn: a part of the skin, turned counterclockwise by quarter turns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct BodyPiece {
+  pub part: usize,
+  pub quarter_turns: u8,
+}
+
+impl BodyPiece {
+  fn new(part: usize, quarter_turns: u8) -> Self {
+    Self {
+      part,
+      quarter_turns,
+    }
+  }
+
+  /// The head and the tail are drawn facing left.
+  fn facing(part: usize, direction: SnakeDirection) -> Self {
+    let quarter_turns = match direction {
+      SnakeDirection::Left => 0,
+      SnakeDirection::Down => 1,
+      SnakeDirection::Right => 2,
+      SnakeDirection::Up => 3,
+    };
+
+    Self::new(part, quarter_turns)
+  }
+}
+
+/// The snake from head to tail. A step pushes the new head cell and pops the tail cell,
+/// so moving does not touch the rest of the body. Growing repeats the tail cell,
+/// and the new segments unfold behind the snake on the next steps.
+///
+/// The snake has no entities: the arena draws it on the snake tile layer. A step changes
+/// the pieces of only a few cells, which are remembered until the arena redraws them,
+/// see [`SnakeBody::take_changed_cells`].
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub(super) struct SnakeBody {
+  cells: VecDeque<BodyCell>,
+  changed_cells: Vec<GridPosition>,
+}
+
+impl SnakeBody {
+  pub fn clear(&mut self) {
+    // Клетки убранной змейки надо стереть
+    self.changed_cells.extend(
+      self
+        .cells
+        .drain(..)
+        .map(|cell| cell.position),
+    );
+  }
+
+  pub fn len(&self) -> usize {
+    self.cells.len()
+  }
+
+  pub fn head(&self) -> Option<&BodyCell> {
+    self.cells.front()
+  }
+
+  pub fn tail(&self) -> Option<&BodyCell> {
+    self.cells.back()
+  }
+
+  /// Cells from head to tail.
+  pub fn iter(&self) -> vec_deque::Iter<'_, BodyCell> {
+    self.cells.iter()
+  }
+
+  /// Adds a segment behind the tail on `cell`.
+  pub fn push_tail(&mut self, cell: BodyCell) {
+    // Бывший хвост становится сегментом тела
+    if let Some(tail) = self.cells.back() {
+      self
+        .changed_cells
+        .push(tail.position.clone());
+    }
+    self
+      .changed_cells
+      .push(cell.position.clone());
+    self.cells.push_back(cell);
+  }
+
+  /// Moves the head onto `cell` and returns the cell the tail has left. Only the new head,
+  /// the old head, the new tail and the left cell change their pieces.
+  pub fn step(&mut self, cell: BodyCell) -> Option<BodyCell> {
+    if let Some(head) = self.cells.front() {
+      self
+        .changed_cells
+        .push(head.position.clone());
+    }
+    self
+      .changed_cells
+      .push(cell.position.clone());
+    self.cells.push_front(cell);
+
+    let left = self.cells.pop_back()?;
+    self
+      .changed_cells
+      .push(left.position.clone());
+    if let Some(tail) = self.cells.back() {
+      self
+        .changed_cells
+        .push(tail.position.clone());
+    }
+
+    Some(left)
+  }
+
+  /// Cells whose piece has changed since the last call, possibly repeated.
+  pub fn take_changed_cells(&mut self) -> Vec<GridPosition> {
+    std::mem::take(&mut self.changed_cells)
+  }
+
+  /// Piece drawn on `position`, or `None` when no segment is there. The head and the tail
+  /// are found right away; only a cell the snake crosses itself on is searched for.
+  pub fn piece_at(&self, position: &GridPosition) -> Option<BodyPiece> {
+    // Голова рисуется поверх тела, через которое проходит
+    if let Some(index) = self
+      .cells
+      .iter()
+      .take(2)
+      .position(|cell| cell.position == *position)
+    {
+      return self.piece(index);
+    }
+
+    // Выросшие сегменты лежат в клетке хвоста
+    let tail_run = self
+      .cells
+      .iter()
+      .rev()
+      .take_while(|cell| cell.position == *position)
+      .count();
+    if tail_run > 0 {
+      return self.piece(self.cells.len() - tail_run);
+    }
+
+    let index = self
+      .cells
+      .iter()
+      .position(|cell| cell.position == *position)?;
+
+    self.piece(index)
+  }
+
+  /// Piece the `index`-th segment is drawn with. Grown segments that still wait on the tail
+  /// cell are covered by the tail, so the first of them is drawn as the tail already.
+  pub fn piece(&self, index: usize) -> Option<BodyPiece> {
+    use SnakeDirection::*;
+
+    let cell = self.cells.get(index)?;
+
+    let Some(ahead) = index
+      .checked_sub(1)
+      .and_then(|ahead| self.cells.get(ahead))
+    else {
+      return Some(BodyPiece::facing(SnakeSkin::HEAD, cell.direction));
+    };
+
+    let is_tail = self
+      .cells
+      .get(index + 1)
+      .is_none_or(|behind| behind.position == cell.position);
+    if is_tail {
+      return Some(BodyPiece::facing(SnakeSkin::TAIL, ahead.direction));
+    }
+
+    // Соседние клетки чередуют два вида прямого сегмента
+    let straight = if (cell.position.x + cell.position.y) % 2 == 1 {
+      SnakeSkin::BODY_START
+    } else {
+      SnakeSkin::BODY_END
+    };
+
+    let piece = match (cell.direction, ahead.direction) {
+      // Прямые сегменты
+      (Left | Right, Left | Right) => BodyPiece::new(straight, 0),
+      (Up | Down, Up | Down) => BodyPiece::new(straight, 1),
+
+      // Повороты
+      (Down, Left) | (Right, Up) => BodyPiece::new(SnakeSkin::TURN_BODY, 0),
+      (Up, Left) | (Right, Down) => BodyPiece::new(SnakeSkin::TURN_BODY, 1),
+      (Up, Right) | (Left, Down) => BodyPiece::new(SnakeSkin::TURN_BODY, 2),
+      (Down, Right) | (Left, Up) => BodyPiece::new(SnakeSkin::TURN_BODY, 3),
+    };
+
+    Some(piece)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Body from head to tail, every cell with the direction the head entered it in.
+  fn body(cells: &[(u32, u32, SnakeDirection)]) -> SnakeBody {
+    let mut body = SnakeBody::default();
+    for (x, y, direction) in cells {
+      body.push_tail(BodyCell {
+        position: GridPosition::new(*x, *y),
+        direction: *direction,
+      });
+    }
+
+    body
+  }
+
+  #[test]
+  fn head_and_tail_face_the_way_the_snake_moves() {
+    use SnakeDirection::*;
+
+    let body = body(&[(3, 1, Up), (3, 0, Right), (2, 0, Right)]);
+
+    assert_eq!(body.piece(0), Some(BodyPiece::new(SnakeSkin::HEAD, 3)));
+    assert_eq!(body.piece(2), Some(BodyPiece::new(SnakeSkin::TAIL, 2)));
+    assert_eq!(body.piece(3), None);
+  }
+
+  #[test]
+  fn turns_use_the_corner_piece() {
+    use SnakeDirection::*;
+
+    let body = body(&[(3, 1, Up), (3, 0, Right), (2, 0, Right)]);
+
+    assert_eq!(body.piece(1), Some(BodyPiece::new(SnakeSkin::TURN_BODY, 0)));
+  }
+
+  #[test]
+  fn straight_pieces_alternate_by_cell() {
+    use SnakeDirection::*;
+
+    let body = body(&[(0, 0, Left), (1, 0, Left), (2, 0, Left), (3, 0, Left)]);
+
+    assert_eq!(
+      body.piece(1),
+      Some(BodyPiece::new(SnakeSkin::BODY_START, 0))
+    );
+    assert_eq!(body.piece(2), Some(BodyPiece::new(SnakeSkin::BODY_END, 0)));
+  }
+
+  #[test]
+  fn step_changes_only_the_ends() {
+    use SnakeDirection::*;
+
+    let mut body = body(&[
+      (2, 0, Left),
+      (3, 0, Left),
+      (4, 0, Left),
+      (5, 0, Left),
+      (6, 0, Left),
+    ]);
+    body.take_changed_cells();
+
+    body.step(BodyCell {
+      position: GridPosition::new(1, 0),
+      direction: Left,
+    });
+
+    let mut changed = body.take_changed_cells();
+    changed.sort_by_key(|position| position.x);
+    assert_eq!(changed, [1, 2, 5, 6].map(|x| GridPosition::new(x, 0)));
+  }
+
+  #[test]
+  fn cleared_cells_are_redrawn() {
+    use SnakeDirection::*;
+
+    let mut body = body(&[(0, 0, Left), (1, 0, Left)]);
+    body.take_changed_cells();
+
+    body.clear();
+
+    assert_eq!(
+      body.take_changed_cells(),
+      [0, 1].map(|x| GridPosition::new(x, 0))
+    );
+    assert_eq!(body.piece_at(&GridPosition::new(0, 0)), None);
+  }
+
+  #[test]
+  fn piece_at_prefers_the_head() {
+    use SnakeDirection::*;
+
+    // Голова в призраке проходит через тело
+    let body = body(&[
+      (1, 1, Down),
+      (1, 2, Left),
+      (2, 2, Up),
+      (2, 1, Right),
+      (1, 1, Right),
+    ]);
+
+    assert_eq!(
+      body.piece_at(&GridPosition::new(1, 1)),
+      Some(BodyPiece::new(SnakeSkin::HEAD, 1))
+    );
+    assert_eq!(body.piece_at(&GridPosition::new(2, 2)), body.piece(2));
+  }
+
+  #[test]
+  fn grown_segments_are_drawn_as_the_tail() {
+    use SnakeDirection::*;
+
+    let body = body(&[(0, 0, Left), (1, 0, Left), (2, 0, Left), (2, 0, Left)]);
+
+    assert_eq!(body.piece(2), Some(BodyPiece::new(SnakeSkin::TAIL, 0)));
+    assert_eq!(body.piece(3), Some(BodyPiece::new(SnakeSkin::TAIL, 0)));
+    assert_eq!(
+      body.piece_at(&GridPosition::new(2, 0)),
+      Some(BodyPiece::new(SnakeSkin::TAIL, 0))
+    );
+  }
+}
@@ -0,0 +1,510 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::game::FontAssets;
+
+use super::{
+  BoardItem, Food, GridPosition, RequestStartGameEvent, SNAKE_SCOPE, SnakeBoard, SnakeBody,
+  SnakeGameAssets, SnakeGameState, StepClock,
+};
+
+const POWER_UP_SPAWN_SECONDS: f32 = 12.0;
+const POWER_UP_LIFETIME_SECONDS: f32 = 8.0;
+const SLOW_MO_SPEED_MULTIPLIER: f32 = 0.5;
+/// Food this many cells away from the head (Manhattan distance) is pulled by the magnet.
+const MAGNET_RADIUS: u32 = 3;
+
+pub(super) struct SnakeEffectsPlugin;
+
+impl Plugin for SnakeEffectsPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<EffectAtlas>();
+    app.insert_resource(PowerUpSpawnTimer(Timer::from_seconds(
+      POWER_UP_SPAWN_SECONDS,
+      TimerMode::Repeating,
+    )));
+
+    app
+      .add_observer(apply_effect_observer)
+      .add_observer(clear_effects_observer);
+
+    app
+      .add_systems(OnExit(SnakeGameState::NotStarted), setup_effects)
+      .add_systems(OnEnter(SnakeGameState::LevelSelect), clear_effects_system)
+      .add_systems(
+        FixedUpdate,
+        (
+          tick_effects_system,
+          power_up_lifetime_system,
+          power_up_spawning_system,
+        )
+          .run_if(in_state(SnakeGameState::Playing)),
+      )
+      .add_systems(Update, update_effect_hud_system);
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(super) enum SnakeEffectKind {
+  /// The snake moves at half speed.
+  SlowMo,
+  /// The snake can pass through itself.
+  Ghost,
+  /// Nearby food drifts towards the head.
+  Magnet,
+  /// Growth is worth more points. Stacks.
+  ScoreMultiplier,
+  /// Arrow keys are swapped with their opposites.
+  ReverseControls,
+}
+
+/// What happens when an effect is picked up while it is already active.
+enum EffectStacking {
+  /// The timer starts over.
+  Refresh,
+  /// The full duration is added to the remaining time.
+  Extend,
+  /// One more stack, up to `max`, and the timer starts over.
+  Stack { max: u32 },
+}
+
+impl SnakeEffectKind {
+  /// In the same order as the icons in `effects.png`.
+  const ALL: [Self; 5] = [
+    Self::SlowMo,
+    Self::Ghost,
+    Self::Magnet,
+    Self::ScoreMultiplier,
+    Self::ReverseControls,
+  ];
+
+  fn duration(self) -> Duration {
+    let seconds = match self {
+      Self::SlowMo => 6.0,
+      Self::Ghost => 5.0,
+      Self::Magnet => 8.0,
+      Self::ScoreMultiplier => 10.0,
+      Self::ReverseControls => 5.0,
+    };
+
+    Duration::from_secs_f32(seconds)
+  }
+
+  fn stacking(self) -> EffectStacking {
+    match self {
+      Self::SlowMo | Self::Magnet | Self::ReverseControls => EffectStacking::Refresh,
+      Self::Ghost => EffectStacking::Extend,
+      Self::ScoreMultiplier => EffectStacking::Stack { max: 3 },
+    }
+  }
+
+  fn is_negative(self) -> bool {
+    self == Self::ReverseControls
+  }
+
+  pub fn icon_index(self) -> usize {
+    Self::ALL
+      .iter()
+      .position(|kind| *kind == self)
+      .unwrap()
+  }
+}
+
+/// Status effect applied to the snake. Its entity despawns when the timer runs out,
+/// and every system reads effects on the fly, so nothing has to be reverted by hand.
+#[derive(Component)]
+pub(super) struct SnakeEffect {
+  kind: SnakeEffectKind,
+  timer: Timer,
+  stacks: u32,
+}
+
+impl SnakeEffect {
+  fn new(kind: SnakeEffectKind) -> Self {
+    Self {
+      kind,
+      timer: Timer::new(kind.duration(), TimerMode::Once),
+      stacks: 1,
+    }
+  }
+}
+
+#[derive(Event)]
+pub(super) struct ApplySnakeEffectEvent {
+  pub kind: SnakeEffectKind,
+}
+
+/// Pickup lying on the arena. The head collects it by moving onto it.
+#[derive(Component)]
+pub(super) struct PowerUp {
+  pub kind: SnakeEffectKind,
+  lifetime: Timer,
+}
+
+#[derive(Resource)]
+struct PowerUpSpawnTimer(Timer);
+
+#[derive(Resource, Default)]
+struct EffectAtlas {
+  image: Handle<Image>,
+  texture_atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+impl EffectAtlas {
+  fn texture_atlas(&self, kind: SnakeEffectKind) -> TextureAtlas {
+    TextureAtlas {
+      layout: self.texture_atlas_layout.clone(),
+      index: kind.icon_index(),
+    }
+  }
+}
+
+#[derive(Component)]
+struct EffectHudSlot(SnakeEffectKind);
+
+#[derive(Component)]
+struct EffectHudText(SnakeEffectKind);
+
+/// Read access to the effects currently applied to the snake.
+#[derive(SystemParam)]
+pub(super) struct ActiveEffects<'w, 's> {
+  effect_query: Query<'w, 's, &'static SnakeEffect>,
+}
+
+impl ActiveEffects<'_, '_> {
+  fn get(&self, kind: SnakeEffectKind) -> Option<&SnakeEffect> {
+    self
+      .effect_query
+      .iter()
+      .find(|effect| effect.kind == kind)
+  }
+
+  pub fn is_active(&self, kind: SnakeEffectKind) -> bool {
+    self.get(kind).is_some()
+  }
+
+  /// Factor the effects contribute to the snake's speed.
+  pub fn speed_multiplier(&self) -> f32 {
+    if self.is_active(SnakeEffectKind::SlowMo) {
+      SLOW_MO_SPEED_MULTIPLIER
+    } else {
+      1.0
+    }
+  }
+
+  pub fn score_multiplier(&self) -> u32 {
+    1 + self
+      .get(SnakeEffectKind::ScoreMultiplier)
+      .map_or(0, |effect| effect.stacks)
+  }
+}
+
+fn setup_effects(
+  mut commands: Commands,
+  mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+  mut effect_atlas: ResMut<EffectAtlas>,
+  snake_game_assets: Res<SnakeGameAssets>,
+  font_assets: Res<FontAssets>,
+) {
+  let layout = TextureAtlasLayout::from_grid(
+    UVec2::new(8, 8),
+    SnakeEffectKind::ALL.len() as u32,
+    1,
+    Some(UVec2::splat(1)),
+    None,
+  );
+  effect_atlas.image = snake_game_assets.effects.clone();
+  effect_atlas.texture_atlas_layout = texture_atlas_layouts.add(layout);
+
+  commands
+    .spawn((
+      Name::new("EffectHud"),
+      DespawnOnExit(SNAKE_SCOPE),
+      Node {
+        position_type: PositionType::Absolute,
+        top: px(12.),
+        right: px(12.),
+        column_gap: px(12.),
+        ..default()
+      },
+    ))
+    .with_children(|parent| {
+      for kind in SnakeEffectKind::ALL {
+        let text_color = if kind.is_negative() {
+          Color::srgb(1.0, 0.4, 0.4)
+        } else {
+          Color::WHITE
+        };
+
+        parent.spawn((
+          EffectHudSlot(kind),
+          Node {
+            display: Display::None,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+          },
+          children![
+            (
+              ImageNode::from_atlas_image(
+                effect_atlas.image.clone(),
+                effect_atlas.texture_atlas(kind),
+              ),
+              Node {
+                width: px(32.),
+                height: px(32.),
+                ..default()
+              },
+            ),
+            (
+              EffectHudText(kind),
+              Text::default(),
+              TextFont {
+                font: font_assets.regular.clone(),
+                font_size: 18.,
+                ..Default::default()
+              },
+              TextColor(text_color),
+            ),
+          ],
+        ));
+      }
+    });
+}
+
+fn apply_effect_observer(
+  event: On<ApplySnakeEffectEvent>,
+  mut commands: Commands,
+  mut effect_query: Query<&mut SnakeEffect>,
+) {
+  let kind = event.kind;
+
+  let Some(mut effect) = effect_query
+    .iter_mut()
+    .find(|effect| effect.kind == kind)
+  else {
+    commands.spawn((
+      Name::new("SnakeEffect"),
+      SnakeEffect::new(kind),
+      DespawnOnExit(SNAKE_SCOPE),
+    ));
+    return;
+  };
+
+  match kind.stacking() {
+    EffectStacking::Refresh => {}
+    EffectStacking::Extend => {
+      let remaining = effect.timer.remaining();
+      effect
+        .timer
+        .set_duration(remaining + kind.duration());
+    }
+    EffectStacking::Stack { max } => {
+      effect.stacks = (effect.stacks + 1).min(max);
+    }
+  }
+
+  effect.timer.reset();
+}
+
+fn tick_effects_system(
+  mut commands: Commands,
+  mut effect_query: Query<(Entity, &mut SnakeEffect)>,
+  time: Res<Time>,
+) {
+  for (entity, mut effect) in &mut effect_query {
+    if effect
+      .timer
+      .tick(time.delta())
+      .is_finished()
+    {
+      commands.entity(entity).despawn();
+    }
+  }
+}
+
+fn despawn_effects(
+  commands: &mut Commands,
+  effect_query: &Query<Entity, Or<(With<SnakeEffect>, With<PowerUp>)>>,
+) {
+  for entity in effect_query.iter() {
+    commands.entity(entity).despawn();
+  }
+}
+
+fn clear_effects_observer(
+  _: On<RequestStartGameEvent>,
+  mut commands: Commands,
+  mut spawn_timer: ResMut<PowerUpSpawnTimer>,
+  effect_query: Query<Entity, Or<(With<SnakeEffect>, With<PowerUp>)>>,
+) {
+  despawn_effects(&mut commands, &effect_query);
+  spawn_timer.0.reset();
+}
+
+fn clear_effects_system(
+  mut commands: Commands,
+  effect_query: Query<Entity, Or<(With<SnakeEffect>, With<PowerUp>)>>,
+) {
+  despawn_effects(&mut commands, &effect_query);
+}
+
+fn power_up_spawning_system(
+  mut commands: Commands,
+  mut spawn_timer: ResMut<PowerUpSpawnTimer>,
+  power_up_query: Query<(), With<PowerUp>>,
+  board: Res<SnakeBoard>,
+  time: Res<Time>,
+) {
+  if !spawn_timer
+    .0
+    .tick(time.delta())
+    .just_finished()
+    || !power_up_query.is_empty()
+  {
+    return;
+  }
+
+  let Some(cell) = board.random_free_cell(&[]) else {
+    return;
+  };
+
+  let kind = SnakeEffectKind::ALL[rand::random_range(0..SnakeEffectKind::ALL.len())];
+
+  // Рисует слой тайлов арены, видимость нужна для мигания
+  commands.spawn((
+    Name::new("PowerUp"),
+    PowerUp {
+      kind,
+      lifetime: Timer::from_seconds(POWER_UP_LIFETIME_SECONDS, TimerMode::Once),
+    },
+    Visibility::default(),
+    GridPosition::from(cell),
+    DespawnOnExit(SNAKE_SCOPE),
+  ));
+}
+
+fn power_up_lifetime_system(
+  mut commands: Commands,
+  mut power_up_query: Query<(Entity, &mut PowerUp, &mut Visibility)>,
+  time: Res<Time>,
+) {
+  for (entity, mut power_up, mut visibility) in &mut power_up_query {
+    if power_up
+      .lifetime
+      .tick(time.delta())
+      .is_finished()
+    {
+      commands.entity(entity).despawn();
+      continue;
+    }
+
+    // Последние две секунды мигает, предупреждая, что скоро пропадёт
+    let blinking = power_up.lifetime.remaining_secs() < 2.0;
+    *visibility = if blinking && (power_up.lifetime.elapsed_secs() * 8.0) as u32 % 2 == 0 {
+      Visibility::Hidden
+    } else {
+      Visibility::Inherited
+    };
+  }
+}
+
+pub(super) fn power_up_pickup_system(
+  mut commands: Commands,
+  power_up_query: Query<&PowerUp>,
+  body: Res<SnakeBody>,
+  board: Res<SnakeBoard>,
+) {
+  let Some(head) = body.head() else {
+    return;
+  };
+
+  let BoardItem::PowerUp(entity) = board.item(head.position.cell()) else {
+    return;
+  };
+
+  if let Ok(power_up) = power_up_query.get(entity) {
+    commands.trigger(ApplySnakeEffectEvent {
+      kind: power_up.kind,
+    });
+    commands.entity(entity).despawn();
+  }
+}
+
+pub(super) fn magnet_system(
+  mut food_query: Query<&mut GridPosition, With<Food>>,
+  mut board: ResMut<SnakeBoard>,
+  body: Res<SnakeBody>,
+  step_clock: Res<StepClock>,
+  effects: ActiveEffects,
+) {
+  if !step_clock.just_stepped() || !effects.is_active(SnakeEffectKind::Magnet) {
+    return;
+  }
+
+  let Some(head) = body.head() else {
+    return;
+  };
+  let head = &head.position;
+
+  for mut position in &mut food_query {
+    let offset = head - &*position;
+
+    if offset == IVec2::ZERO || offset.x.unsigned_abs() + offset.y.unsigned_abs() > MAGNET_RADIUS {
+      continue;
+    }
+
+    let step = if offset.x.abs() >= offset.y.abs() {
+      IVec2::new(offset.x.signum(), 0)
+    } else {
+      IVec2::new(0, offset.y.signum())
+    };
+
+    // Шаг всегда в сторону головы, поэтому за границы уровня еда не выйдет
+    let target = GridPosition::new(
+      position.x.saturating_add_signed(step.x),
+      position.y.saturating_add_signed(step.y),
+    );
+
+    // Голову еда может занять: тогда её съедят в этом же шаге
+    if !board.is_free(target.cell()) && target != *head {
+      continue;
+    }
+
+    board.move_item(position.cell(), target.cell());
+    *position = target;
+  }
+}
+
+fn update_effect_hud_system(
+  mut slot_query: Query<(&EffectHudSlot, &mut Node)>,
+  mut text_query: Query<(&EffectHudText, &mut Text)>,
+  effects: ActiveEffects,
+) {
+  for (slot, mut node) in &mut slot_query {
+    let display = if effects.is_active(slot.0) {
+      Display::Flex
+    } else {
+      Display::None
+    };
+
+    if node.display != display {
+      node.display = display;
+    }
+  }
+
+  for (hud_text, mut text) in &mut text_query {
+    let Some(effect) = effects.get(hud_text.0) else {
+      continue;
+    };
+
+    let seconds = effect.timer.remaining_secs().ceil();
+
+    text.0 = if effect.kind == SnakeEffectKind::ScoreMultiplier {
+      format!("x{} {seconds:.0}s", effects.score_multiplier())
+    } else {
+      format!("{seconds:.0}s")
+    };
+  }
+}
@@ -0,0 +1,291 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::audio::PlaySoundEvent;
+
+use super::{
+  CurrentLevel, Food, FoodEatenEvent, GridPosition, SNAKE_SCOPE, SnakeBody, SnakeCamera,
+  SnakeGameAssets, SnakeGameState, SnakeSkin, SnakeSoundAssets,
+};
+
+const DEATH_FLASH_SECONDS: f32 = 0.6;
+const DEATH_FLASH_INTERVAL_SECONDS: f32 = 0.1;
+const DEATH_FLASH_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+/// Segments burst one after another, starting from the head.
+const BURST_DELAY_PER_SEGMENT_SECONDS: f32 = 0.04;
+const BURST_SECONDS: f32 = 0.5;
+const BURST_SPEED: f32 = 60.0;
+const BURST_SPIN: f32 = 4.0 * PI;
+
+const SHAKE_SECONDS: f32 = 0.4;
+const SHAKE_INTENSITY: f32 = 3.0;
+
+const FOOD_PARTICLE_COUNT: usize = 8;
+const FOOD_PARTICLE_SECONDS: f32 = 0.35;
+const FOOD_PARTICLE_SPEED: f32 = 40.0;
+const FOOD_PARTICLE_SIZE: f32 = 2.0;
+
+pub(super) struct SnakeJuicePlugin;
+
+impl Plugin for SnakeJuicePlugin {
+  fn build(&self, app: &mut App) {
+    app.add_observer(food_pop_observer);
+
+    app
+      .add_systems(OnEnter(SnakeGameState::Dying), start_death_sequence)
+      .add_systems(
+        Update,
+        death_sequence_system.run_if(in_state(SnakeGameState::Dying)),
+      )
+      .add_systems(Update, (camera_shake_system, particle_system));
+  }
+}
+
+/// Progress of the death animation. The game switches to [`SnakeGameState::GameOver`]
+/// only once it is over, so no input is read while it plays.
+#[derive(Resource)]
+struct DeathSequence {
+  elapsed: f32,
+  duration: f32,
+}
+
+/// Sprite of one segment, spawned in its cell when the snake dies.
+#[derive(Component)]
+struct DeathBurst {
+  origin: Vec3,
+  start: f32,
+  velocity: Vec2,
+  spin: f32,
+}
+
+#[derive(Component)]
+struct CameraShake {
+  timer: Timer,
+  origin: Vec3,
+}
+
+#[derive(Component)]
+struct Particle {
+  velocity: Vec2,
+  timer: Timer,
+}
+
+fn random_direction() -> Vec2 {
+  Vec2::from_angle(rand::random_range(0.0..TAU))
+}
+
+fn start_death_sequence(
+  mut commands: Commands,
+  camera_single: Single<(Entity, &Transform), With<SnakeCamera>>,
+  body: Res<SnakeBody>,
+  snake_skin: Res<SnakeSkin>,
+  snake_game_assets: Res<SnakeGameAssets>,
+  current_level: Res<CurrentLevel>,
+  sound_assets: Res<SnakeSoundAssets>,
+) {
+  // Сегменты разлетаются по очереди от головы к хвосту
+  for (i, cell) in body.iter().enumerate() {
+    let Some(piece) = body.piece(i) else {
+      continue;
+    };
+    let origin = current_level
+      .bounds
+      .translation(&cell.position);
+
+    commands.spawn((
+      Name::new("SnakeDeathBurst"),
+      DeathBurst {
+        origin,
+        start: DEATH_FLASH_SECONDS + i as f32 * BURST_DELAY_PER_SEGMENT_SECONDS,
+        velocity: random_direction() * BURST_SPEED * rand::random_range(0.6..1.0),
+        spin: BURST_SPIN * rand::random_range(-1.0..1.0),
+      },
+      snake_skin.sprite(&snake_game_assets.snake_skin_sheet, piece.part),
+      Transform::from_translation(origin).with_rotation(Quat::from_rotation_z(
+        piece.quarter_turns as f32 * FRAC_PI_2,
+      )),
+      DespawnOnExit(SNAKE_SCOPE),
+    ));
+  }
+
+  commands.insert_resource(DeathSequence {
+    elapsed: 0.0,
+    duration: DEATH_FLASH_SECONDS
+      + body.len() as f32 * BURST_DELAY_PER_SEGMENT_SECONDS
+      + BURST_SECONDS,
+  });
+
+  let (camera_entity, camera_transform) = camera_single.into_inner();
+  commands
+    .entity(camera_entity)
+    .insert(CameraShake {
+      timer: Timer::from_seconds(SHAKE_SECONDS, TimerMode::Once),
+      origin: camera_transform.translation,
+    });
+
+  commands.trigger(PlaySoundEvent::sfx(sound_assets.hit.clone()));
+}
+
+fn death_sequence_system(
+  mut commands: Commands,
+  mut death_sequence: ResMut<DeathSequence>,
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  mut burst_query: Query<(Entity, &DeathBurst, &mut Transform, &mut Sprite)>,
+  time: Res<Time>,
+) {
+  death_sequence.elapsed += time.delta_secs();
+  let elapsed = death_sequence.elapsed;
+  let finished = elapsed >= death_sequence.duration;
+
+  for (entity, burst, mut transform, mut sprite) in &mut burst_query {
+    if finished {
+      commands.entity(entity).despawn();
+      continue;
+    }
+
+    if elapsed < burst.start {
+      let flash = (elapsed / DEATH_FLASH_INTERVAL_SECONDS) as u32 % 2 == 0;
+      sprite.color = if flash && elapsed < DEATH_FLASH_SECONDS {
+        DEATH_FLASH_COLOR
+      } else {
+        Color::WHITE
+      };
+      continue;
+    }
+
+    let t = ((elapsed - burst.start) / BURST_SECONDS).min(1.0);
+    let offset = burst.velocity * BURST_SECONDS * t;
+
+    transform.translation = burst.origin + offset.extend(1.0);
+    transform.rotate_z(burst.spin * time.delta_secs() * (1.0 - t));
+    sprite.color = DEATH_FLASH_COLOR.with_alpha(1.0 - t);
+  }
+
+  if finished {
+    commands.remove_resource::<DeathSequence>();
+    next_state.set(SnakeGameState::GameOver);
+  }
+}
+
+fn camera_shake_system(
+  mut commands: Commands,
+  mut camera_query: Query<(Entity, &mut CameraShake, &mut Transform)>,
+  time: Res<Time>,
+) {
+  for (entity, mut shake, mut transform) in &mut camera_query {
+    if shake
+      .timer
+      .tick(time.delta())
+      .is_finished()
+    {
+      transform.translation = shake.origin;
+      commands
+        .entity(entity)
+        .remove::<CameraShake>();
+      continue;
+    }
+
+    // Тряска затухает к концу
+    let strength = SHAKE_INTENSITY * (1.0 - shake.timer.fraction());
+    transform.translation = shake.origin + (random_direction() * strength).extend(0.0);
+  }
+}
+
+fn food_pop_observer(
+  event: On<FoodEatenEvent>,
+  mut commands: Commands,
+  food_query: Query<(&GridPosition, &Food)>,
+  current_level: Res<CurrentLevel>,
+) {
+  let Ok((food_position, food)) = food_query.get(event.food_entity) else {
+    return;
+  };
+
+  let color = match food {
+    Food::Green { .. } => Color::srgb(0.4, 0.9, 0.3),
+    Food::Red { .. } => Color::srgb(0.95, 0.3, 0.25),
+    Food::Blue { .. } => Color::srgb(0.3, 0.5, 1.0),
+  };
+
+  for i in 0..FOOD_PARTICLE_COUNT {
+    let angle = i as f32 / FOOD_PARTICLE_COUNT as f32 * TAU + rand::random_range(-0.3..0.3);
+
+    commands.spawn((
+      Name::new("FoodParticle"),
+      Particle {
+        velocity: Vec2::from_angle(angle) * FOOD_PARTICLE_SPEED * rand::random_range(0.7..1.0),
+        timer: Timer::from_seconds(FOOD_PARTICLE_SECONDS, TimerMode::Once),
+      },
+      Sprite::from_color(color, Vec2::splat(FOOD_PARTICLE_SIZE)),
+      Transform::from_translation(
+        current_level
+          .bounds
+          .translation(food_position)
+          .with_z(1.0),
+      ),
+      DespawnOnExit(SNAKE_SCOPE),
+    ));
+  }
+}
+
+fn particle_system(
+  mut commands: Commands,
+  mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+  time: Res<Time>,
+) {
+  for (entity, mut particle, mut transform, mut sprite) in &mut particle_query {
+    if particle
+      .timer
+      .tick(time.delta())
+      .is_finished()
+    {
+      commands.entity(entity).despawn();
+      continue;
+    }
+
+    transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+
+    let alpha = 1.0 - particle.timer.fraction();
+    sprite.color.set_alpha(alpha);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::state::app::StatesPlugin;
+
+  use super::*;
+
+  #[test]
+  fn death_sequence_ends_in_game_over() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin));
+    app.insert_state(SnakeGameState::Dying);
+    app.add_systems(
+      Update,
+      death_sequence_system.run_if(in_state(SnakeGameState::Dying)),
+    );
+    app.insert_resource(DeathSequence {
+      elapsed: 0.0,
+      duration: 0.0,
+    });
+
+    // Первый кадр просит GameOver, второй применяет переход
+    app.update();
+    app.update();
+
+    assert_eq!(
+      app
+        .world()
+        .resource::<State<SnakeGameState>>()
+        .get(),
+      &SnakeGameState::GameOver
+    );
+    assert!(
+      !app
+        .world()
+        .contains_resource::<DeathSequence>()
+    );
+  }
+}
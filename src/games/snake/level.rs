@@ -0,0 +1,810 @@
+use std::{
+  collections::{BTreeMap, HashSet},
+  time::Duration,
+};
+
+use bevy::{
+  asset::{AssetLoader, LoadContext, io::Reader},
+  prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+  game::FontAssets,
+  games::input_repeat::KeyRepeat,
+  loading::{AssetCollection, AssetCollectionAppExt},
+  profile::Profile,
+  state::GameState,
+};
+
+use super::{
+  GridPosition, RequestStartGameEvent, SNAKE_SCOPE, START_DIRECTION, START_SNAKE_LENGTH,
+  SnakeBoard, SnakeBody, SnakeGameState, SnakeScore, arena::TILE_SIZE,
+};
+
+/// Campaign levels in play order, loaded from `assets/games/snake/levels/<id>.level.ron`.
+/// Clearing a level unlocks the next one.
+const CAMPAIGN_LEVELS: [&str; 4] = ["open_field", "pillars", "portals", "labyrinth"];
+/// Size of the empty arena shown until a level is picked.
+const DEFAULT_ARENA_SIZE: UVec2 = UVec2::new(12, 12);
+
+pub(super) struct SnakeLevelPlugin;
+
+impl Plugin for SnakeLevelPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_asset::<SnakeLevelAsset>();
+    app.init_asset_loader::<SnakeLevelLoader>();
+    app.init_asset_collection::<SnakeLevelAssets>();
+
+    app.init_resource::<CurrentLevel>();
+    app.init_resource::<LevelSelection>();
+    app.init_resource::<LevelTimer>();
+
+    app
+      .add_observer(start_level_observer)
+      .add_observer(reset_level_timer_observer);
+
+    app
+      .add_systems(
+        Update,
+        (
+          level_select_input_system.run_if(not(in_state(GameState::Paused))),
+          update_level_select_ui_system,
+        )
+          .chain()
+          .run_if(in_state(SnakeGameState::LevelSelect)),
+      )
+      .add_systems(
+        FixedUpdate,
+        level_timer_system.run_if(in_state(SnakeGameState::Playing)),
+      )
+      .add_systems(Update, update_level_info_system)
+      .add_systems(OnEnter(SnakeGameState::LevelSelect), spawn_level_select_ui)
+      .add_systems(OnEnter(SnakeGameState::GameOver), submit_level_score_system)
+      .add_systems(
+        OnEnter(SnakeGameState::Win),
+        (submit_level_score_system, clear_level_system),
+      );
+  }
+}
+
+/// Arena of the current level, as large as the level itself. Movement wraps around its edges.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ArenaBounds {
+  pub size: UVec2,
+}
+
+impl ArenaBounds {
+  pub fn new(size: UVec2) -> Self {
+    Self { size }
+  }
+
+  pub fn max(&self) -> UVec2 {
+    self.size - UVec2::ONE
+  }
+
+  pub fn contains(&self, position: &GridPosition) -> bool {
+    position.x < self.size.x && position.y < self.size.y
+  }
+
+  fn cells(&self) -> impl Iterator<Item = GridPosition> {
+    let size = self.size;
+
+    (0..size.y).flat_map(move |y| (0..size.x).map(move |x| GridPosition::new(x, y)))
+  }
+
+  /// World position of the center of the cell. The arena tilemaps are centered on the origin,
+  /// so this is also the center of the tile drawn for the cell.
+  pub fn translation(&self, GridPosition { x, y }: &GridPosition) -> Vec3 {
+    let cell = Vec2::new(*x as f32, *y as f32);
+
+    ((cell + 0.5 - self.size.as_vec2() / 2.0) * TILE_SIZE).extend(0.0)
+  }
+}
+
+/// Snake level loaded from a `*.level.ron` file. The arena is sized to the level.
+#[derive(Asset, TypePath, Debug)]
+pub(super) struct SnakeLevelAsset {
+  name: String,
+  size: UVec2,
+  obstacles: Vec<UVec2>,
+  portals: Vec<(UVec2, UVec2)>,
+  start: UVec2,
+  target_length: u32,
+  time_limit: Option<f32>,
+}
+
+/// On-disk form of a level. `rows` draw it from top to bottom: `.` is an empty cell,
+/// `#` an obstacle, `S` the snake's start and a lowercase letter one end of a portal.
+/// Every portal letter must appear exactly twice.
+#[derive(Deserialize, Debug)]
+struct SnakeLevelFile {
+  name: String,
+  target_length: u32,
+  #[serde(default)]
+  time_limit: Option<f32>,
+  rows: Vec<String>,
+}
+
+#[derive(Resource)]
+struct SnakeLevelAssets {
+  levels: Vec<Handle<SnakeLevelAsset>>,
+}
+
+impl FromWorld for SnakeLevelAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      levels: CAMPAIGN_LEVELS
+        .iter()
+        .map(|id| asset_server.load(format!("games/snake/levels/{id}.level.ron")))
+        .collect(),
+    }
+  }
+}
+
+impl AssetCollection for SnakeLevelAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    self
+      .levels
+      .iter()
+      .map(|handle| handle.clone().untyped())
+      .collect()
+  }
+}
+
+/// Level the snake is currently played on. The default is an empty arena.
+#[derive(Resource)]
+pub(super) struct CurrentLevel {
+  pub index: usize,
+  pub name: String,
+  pub bounds: ArenaBounds,
+  pub obstacles: HashSet<GridPosition>,
+  pub portals: Vec<(GridPosition, GridPosition)>,
+  pub start: GridPosition,
+  pub target_length: u32,
+  pub time_limit: Option<f32>,
+}
+
+impl Default for CurrentLevel {
+  fn default() -> Self {
+    Self {
+      index: 0,
+      name: String::from("Free play"),
+      bounds: ArenaBounds::new(DEFAULT_ARENA_SIZE),
+      obstacles: HashSet::new(),
+      portals: Vec::new(),
+      start: (DEFAULT_ARENA_SIZE / 2).into(),
+      target_length: DEFAULT_ARENA_SIZE.element_product(),
+      time_limit: None,
+    }
+  }
+}
+
+impl CurrentLevel {
+  fn new(index: usize, asset: &SnakeLevelAsset) -> Self {
+    Self {
+      index,
+      name: asset.name.clone(),
+      bounds: ArenaBounds::new(asset.size),
+      obstacles: asset
+        .obstacles
+        .iter()
+        .map(|position| (*position).into())
+        .collect(),
+      portals: asset
+        .portals
+        .iter()
+        .map(|(a, b)| ((*a).into(), (*b).into()))
+        .collect(),
+      start: asset.start.into(),
+      target_length: asset.target_length,
+      time_limit: asset.time_limit,
+    }
+  }
+
+  /// Where the snake comes out after stepping onto `position`, if it is a portal.
+  pub fn portal_exit(&self, position: &GridPosition) -> Option<GridPosition> {
+    self.portals.iter().find_map(|(a, b)| {
+      if a == position {
+        Some(b.clone())
+      } else if b == position {
+        Some(a.clone())
+      } else {
+        None
+      }
+    })
+  }
+
+  /// Board of the arena's size with the obstacles and portals of this level blocked.
+  pub fn board(&self) -> SnakeBoard {
+    let mut board = SnakeBoard::new(self.bounds.size);
+
+    for position in self.bounds.cells() {
+      if self.obstacles.contains(&position) || self.portal_exit(&position).is_some() {
+        board.block(position.cell());
+      }
+    }
+
+    board
+  }
+
+  pub fn score_key(&self) -> String {
+    format!("Snake/{}", CAMPAIGN_LEVELS[self.index])
+  }
+
+  /// Index of the campaign level after this one, if there is any.
+  pub fn next_level(&self) -> Option<usize> {
+    Some(self.index + 1).filter(|index| *index < CAMPAIGN_LEVELS.len())
+  }
+}
+
+fn cleared_flag(id: &str) -> String {
+  format!("snake_level_cleared:{id}")
+}
+
+/// Number of campaign levels the player may pick: the first one and every level after a cleared one.
+fn unlocked_level_count(profile: &Profile) -> usize {
+  1 + CAMPAIGN_LEVELS
+    .iter()
+    .take(CAMPAIGN_LEVELS.len() - 1)
+    .take_while(|id| profile.has_flag(&cleared_flag(id)))
+    .count()
+}
+
+/// Builds [`CurrentLevel`] from the campaign level `index`, spawns it and starts a run.
+#[derive(Event)]
+pub(super) struct StartLevelEvent {
+  pub index: usize,
+}
+
+/// Holding Up or Down keeps scrolling through the levels.
+const LEVEL_SELECT_REPEAT: KeyRepeat =
+  KeyRepeat::new(Duration::from_millis(300), Duration::from_millis(100));
+
+#[derive(Resource)]
+struct LevelSelection {
+  selected: usize,
+  up: KeyRepeat,
+  down: KeyRepeat,
+}
+
+impl Default for LevelSelection {
+  fn default() -> Self {
+    Self {
+      selected: 0,
+      up: LEVEL_SELECT_REPEAT,
+      down: LEVEL_SELECT_REPEAT,
+    }
+  }
+}
+
+/// Counts down the level's time limit. Running out of time ends the run.
+#[derive(Resource, Default)]
+struct LevelTimer(Option<Timer>);
+
+/// UI belonging to the level being played, replaced when another level starts.
+#[derive(Component)]
+struct LevelEntity;
+
+#[derive(Component)]
+struct LevelInfoText;
+
+#[derive(Component)]
+struct LevelSelectRow(usize);
+
+#[derive(Default, TypePath)]
+struct SnakeLevelLoader;
+
+#[derive(Debug)]
+enum SnakeLevelLoaderError {
+  Io(std::io::Error),
+  Ron(ron::error::SpannedError),
+  Layout(String),
+}
+
+impl std::fmt::Display for SnakeLevelLoaderError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SnakeLevelLoaderError::Io(error) => write!(f, "Could not read snake level: {error}"),
+      SnakeLevelLoaderError::Ron(error) => write!(f, "Could not parse snake level: {error}"),
+      SnakeLevelLoaderError::Layout(error) => write!(f, "Invalid snake level: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for SnakeLevelLoaderError {}
+
+impl From<std::io::Error> for SnakeLevelLoaderError {
+  fn from(error: std::io::Error) -> Self {
+    SnakeLevelLoaderError::Io(error)
+  }
+}
+
+impl From<ron::error::SpannedError> for SnakeLevelLoaderError {
+  fn from(error: ron::error::SpannedError) -> Self {
+    SnakeLevelLoaderError::Ron(error)
+  }
+}
+
+impl AssetLoader for SnakeLevelLoader {
+  type Asset = SnakeLevelAsset;
+  type Settings = ();
+  type Error = SnakeLevelLoaderError;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    _load_context: &mut LoadContext<'_>,
+  ) -> Result<Self::Asset, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    parse_level(ron::de::from_bytes(&bytes)?).map_err(SnakeLevelLoaderError::Layout)
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["level.ron"]
+  }
+}
+
+fn parse_level(file: SnakeLevelFile) -> Result<SnakeLevelAsset, String> {
+  let height = file.rows.len() as u32;
+  let width = file
+    .rows
+    .first()
+    .map_or(0, |row| row.chars().count()) as u32;
+
+  if width == 0 || height == 0 {
+    return Err(String::from("level has no cells"));
+  }
+
+  let mut obstacles = Vec::new();
+  let mut portal_ends = BTreeMap::<char, Vec<UVec2>>::new();
+  let mut start = None;
+
+  for (row_index, row) in file.rows.iter().enumerate() {
+    if row.chars().count() as u32 != width {
+      return Err(format!("row {row_index} is not {width} cells wide"));
+    }
+
+    // Первая строка — верх арены, а y растёт вверх
+    let y = height - 1 - row_index as u32;
+
+    for (x, cell) in row.chars().enumerate() {
+      let position = UVec2::new(x as u32, y);
+
+      match cell {
+        '.' => {}
+        '#' => obstacles.push(position),
+        'S' => {
+          if start.replace(position).is_some() {
+            return Err(String::from("level has more than one start cell"));
+          }
+        }
+        'a'..='z' => portal_ends
+          .entry(cell)
+          .or_default()
+          .push(position),
+        _ => return Err(format!("unknown cell '{cell}' in row {row_index}")),
+      }
+    }
+  }
+
+  let mut portals = Vec::new();
+  for (letter, ends) in portal_ends {
+    let [a, b] = ends.as_slice() else {
+      return Err(format!(
+        "portal '{letter}' has {} ends, expected 2",
+        ends.len()
+      ));
+    };
+    portals.push((*a, *b));
+  }
+
+  let size = UVec2::new(width, height);
+  let start = start.unwrap_or(size / 2);
+
+  // Голова на старте, тело тянется назад, как его расставляет start_game
+  let back = START_DIRECTION.get_opposite().offset();
+  for i in 0..START_SNAKE_LENGTH {
+    let cell = start.as_ivec2() + back * i as i32;
+    let part = if i == 0 { "start cell" } else { "snake body" };
+
+    if cell.cmplt(IVec2::ZERO).any() || cell.cmpge(size.as_ivec2()).any() {
+      return Err(format!(
+        "{part} would be at column {}, row {}, outside the level",
+        cell.x,
+        height as i32 - 1 - cell.y
+      ));
+    }
+
+    let cell = cell.as_uvec2();
+    let (column, row) = (cell.x, height - 1 - cell.y);
+    if obstacles.contains(&cell) {
+      return Err(format!(
+        "{part} is on an obstacle at column {column}, row {row}"
+      ));
+    }
+    if portals
+      .iter()
+      .any(|(a, b)| *a == cell || *b == cell)
+    {
+      return Err(format!(
+        "{part} is on a portal at column {column}, row {row}"
+      ));
+    }
+  }
+
+  let free_cells = width * height - obstacles.len() as u32 - portals.len() as u32 * 2;
+  if file.target_length <= START_SNAKE_LENGTH || file.target_length > free_cells {
+    return Err(format!(
+      "target length {} must be between {} and {free_cells}",
+      file.target_length,
+      START_SNAKE_LENGTH + 1
+    ));
+  }
+
+  // Timer::from_seconds паникует на отрицательном или нечисловом времени
+  if let Some(time_limit) = file.time_limit
+    && !(time_limit.is_finite() && time_limit > 0.0)
+  {
+    return Err(format!(
+      "time limit {time_limit} must be a positive number of seconds"
+    ));
+  }
+
+  Ok(SnakeLevelAsset {
+    name: file.name,
+    size,
+    obstacles,
+    portals,
+    start,
+    target_length: file.target_length,
+    time_limit: file.time_limit,
+  })
+}
+
+/// Obstacles and portals are drawn by the arena tilemap, only the level HUD is spawned here.
+fn spawn_level_info(commands: &mut Commands, font_assets: &FontAssets) {
+  commands.spawn((
+    Name::new("LevelInfo"),
+    LevelEntity,
+    LevelInfoText,
+    DespawnOnExit(SNAKE_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 24.,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+  ));
+}
+
+fn spawn_level_select_ui(mut commands: Commands, font_assets: Res<FontAssets>) {
+  let text = |text: &str, font_size: f32| {
+    (
+      Text::new(text),
+      TextFont {
+        font: font_assets.regular.clone(),
+        font_size,
+        ..Default::default()
+      },
+      TextColor(Color::WHITE),
+    )
+  };
+
+  commands
+    .spawn((
+      Name::new("LevelSelectUi"),
+      DespawnOnExit(SnakeGameState::LevelSelect),
+      Node {
+        width: percent(100),
+        height: percent(100),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: px(8.),
+        ..default()
+      },
+      BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+    ))
+    .with_children(|parent| {
+      parent.spawn(text("Select level", 40.));
+
+      for index in 0..CAMPAIGN_LEVELS.len() {
+        parent.spawn((LevelSelectRow(index), text("", 28.)));
+      }
+
+      parent.spawn(text("Up/Down to choose, Enter to play", 20.));
+    });
+}
+
+fn update_level_select_ui_system(
+  mut row_query: Query<(&LevelSelectRow, &mut Text, &mut TextColor)>,
+  level_selection: Res<LevelSelection>,
+  level_assets: Res<SnakeLevelAssets>,
+  levels: Res<Assets<SnakeLevelAsset>>,
+  profile: Res<Profile>,
+) {
+  let unlocked = unlocked_level_count(&profile);
+
+  for (row, mut text, mut color) in &mut row_query {
+    let index = row.0;
+    let id = CAMPAIGN_LEVELS[index];
+    let marker = if index == level_selection.selected {
+      ">"
+    } else {
+      " "
+    };
+
+    text.0 = match levels.get(&level_assets.levels[index]) {
+      _ if index >= unlocked => format!("{marker} {}. Locked", index + 1),
+      None => format!("{marker} {}. {id} (failed to load)", index + 1),
+      Some(level) => {
+        let best = profile.best_score(&format!("Snake/{id}"));
+        let cleared = if profile.has_flag(&cleared_flag(id)) {
+          " - cleared"
+        } else {
+          ""
+        };
+        format!(
+          "{marker} {}. {}  best {best}{cleared}",
+          index + 1,
+          level.name
+        )
+      }
+    };
+
+    color.0 = if index >= unlocked {
+      Color::srgb(0.5, 0.5, 0.5)
+    } else if index == level_selection.selected {
+      Color::srgb(1.0, 0.85, 0.3)
+    } else {
+      Color::WHITE
+    };
+  }
+}
+
+fn level_select_input_system(
+  mut commands: Commands,
+  mut level_selection: ResMut<LevelSelection>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  profile: Res<Profile>,
+  time: Res<Time>,
+) {
+  let unlocked = unlocked_level_count(&profile);
+
+  let up = level_selection
+    .up
+    .update(keyboard_input.pressed(KeyCode::ArrowUp), time.delta());
+  let down = level_selection
+    .down
+    .update(keyboard_input.pressed(KeyCode::ArrowDown), time.delta());
+
+  level_selection.selected = (level_selection.selected + down as usize)
+    .saturating_sub(up as usize)
+    .min(unlocked - 1);
+
+  if keyboard_input.just_pressed(KeyCode::Enter) {
+    commands.trigger(StartLevelEvent {
+      index: level_selection.selected,
+    });
+  }
+}
+
+fn start_level_observer(
+  event: On<StartLevelEvent>,
+  mut commands: Commands,
+  mut level_selection: ResMut<LevelSelection>,
+  mut current_level: ResMut<CurrentLevel>,
+  mut board: ResMut<SnakeBoard>,
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  level_assets: Res<SnakeLevelAssets>,
+  levels: Res<Assets<SnakeLevelAsset>>,
+  font_assets: Res<FontAssets>,
+  level_entity_query: Query<Entity, With<LevelEntity>>,
+) {
+  let index = event.index;
+  let Some(level) = levels.get(&level_assets.levels[index]) else {
+    warn!("Snake level {} is not loaded", CAMPAIGN_LEVELS[index]);
+    return;
+  };
+
+  level_selection.selected = index;
+  *current_level = CurrentLevel::new(index, level);
+  *board = current_level.board();
+
+  for entity in level_entity_query.iter() {
+    commands.entity(entity).despawn();
+  }
+  spawn_level_info(&mut commands, &font_assets);
+
+  commands.trigger(RequestStartGameEvent);
+  next_state.set(SnakeGameState::WaitPlayer);
+}
+
+fn reset_level_timer_observer(
+  _: On<RequestStartGameEvent>,
+  mut level_timer: ResMut<LevelTimer>,
+  current_level: Res<CurrentLevel>,
+) {
+  level_timer.0 = current_level
+    .time_limit
+    .map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+}
+
+fn level_timer_system(
+  mut level_timer: ResMut<LevelTimer>,
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  time: Res<Time>,
+) {
+  let Some(timer) = &mut level_timer.0 else {
+    return;
+  };
+
+  if timer.tick(time.delta()).just_finished() {
+    next_state.set(SnakeGameState::Dying);
+  }
+}
+
+pub(super) fn snake_obstacle_collision_system(
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  body: Res<SnakeBody>,
+  current_level: Res<CurrentLevel>,
+) {
+  let Some(head) = body.head() else {
+    return;
+  };
+
+  if current_level
+    .obstacles
+    .contains(&head.position)
+  {
+    next_state.set(SnakeGameState::Dying);
+  }
+}
+
+fn update_level_info_system(
+  mut level_info_query: Query<&mut Text, With<LevelInfoText>>,
+  current_level: Res<CurrentLevel>,
+  level_timer: Res<LevelTimer>,
+  score: Res<SnakeScore>,
+  body: Res<SnakeBody>,
+) {
+  let snake_length = body.len();
+
+  for mut text in &mut level_info_query {
+    text.0 = format!(
+      "{}  {snake_length}/{}  score {}",
+      current_level.name, current_level.target_length, score.0
+    );
+
+    if let Some(timer) = &level_timer.0 {
+      text
+        .0
+        .push_str(&format!("  {:.0}s", timer.remaining_secs().ceil()));
+    }
+  }
+}
+
+fn submit_level_score_system(
+  mut profile: ResMut<Profile>,
+  current_level: Res<CurrentLevel>,
+  score: Res<SnakeScore>,
+) {
+  if profile.submit_score(&current_level.score_key(), score.0) {
+    info!(
+      "New record on snake level {}: {}",
+      current_level.name, score.0
+    );
+  }
+}
+
+fn clear_level_system(
+  mut profile: ResMut<Profile>,
+  mut level_selection: ResMut<LevelSelection>,
+  current_level: Res<CurrentLevel>,
+) {
+  profile
+    .flags
+    .insert(cleared_flag(CAMPAIGN_LEVELS[current_level.index]));
+
+  level_selection.selected = (current_level.index + 1).min(CAMPAIGN_LEVELS.len() - 1);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn file(rows: &[&str]) -> SnakeLevelFile {
+    SnakeLevelFile {
+      name: String::from("Test"),
+      target_length: 5,
+      time_limit: None,
+      rows: rows
+        .iter()
+        .map(|row| row.to_string())
+        .collect(),
+    }
+  }
+
+  fn parse(rows: &[&str]) -> Result<SnakeLevelAsset, String> {
+    parse_level(file(rows))
+  }
+
+  #[test]
+  fn snake_fits_behind_the_start() {
+    let level = parse(&["......", "..S...", "......"]).unwrap();
+
+    assert_eq!(level.start, UVec2::new(2, 1));
+  }
+
+  #[test]
+  fn start_defaults_to_the_middle() {
+    let level = parse(&["......", "......", "......"]).unwrap();
+
+    assert_eq!(level.start, UVec2::new(3, 1));
+  }
+
+  #[test]
+  fn start_on_an_obstacle_is_rejected() {
+    let error = parse(&["......", "...#..", "......"]).unwrap_err();
+
+    assert!(error.contains("start cell is on an obstacle"), "{error}");
+  }
+
+  #[test]
+  fn body_on_an_obstacle_is_rejected() {
+    let error = parse(&["......", "..S.#.", "......"]).unwrap_err();
+
+    assert!(error.contains("snake body is on an obstacle"), "{error}");
+  }
+
+  #[test]
+  fn body_on_a_portal_is_rejected() {
+    let error = parse(&["a.....", "..Sa..", "......"]).unwrap_err();
+
+    assert!(error.contains("snake body is on a portal"), "{error}");
+  }
+
+  #[test]
+  fn body_past_the_edge_is_rejected() {
+    let error = parse(&["......", "....S.", "......"]).unwrap_err();
+
+    assert!(error.contains("outside the level"), "{error}");
+  }
+
+  #[test]
+  fn time_limit_must_be_positive() {
+    for time_limit in [0.0, -10.0, f32::NAN, f32::INFINITY] {
+      let error = parse_level(SnakeLevelFile {
+        time_limit: Some(time_limit),
+        ..file(&["......", "......", "......"])
+      })
+      .unwrap_err();
+
+      assert!(error.contains("time limit"), "{error}");
+    }
+  }
+
+  #[test]
+  fn positive_time_limit_is_kept() {
+    let level = parse_level(SnakeLevelFile {
+      time_limit: Some(90.0),
+      ..file(&["......", "......", "......"])
+    })
+    .unwrap();
+
+    assert_eq!(level.time_limit, Some(90.0));
+  }
+}
@@ -1,38 +1,61 @@
-use std::{
-  collections::{HashMap, HashSet, VecDeque},
-  time::Duration,
-};
+use std::collections::VecDeque;
 
-use bevy::prelude::*;
+use bevy::{input::InputSystems, prelude::*, time::Stopwatch};
 
 use crate::{
+  audio::PlaySoundEvent,
   game::FontAssets,
-  games::{CurrentGameState, GameType},
+  games::{
+    CurrentGameState, GameExitMessage, GameResultMessage, GameType,
+    menu::{GameMenuBuilder, GameMenuPlugin, GameMenuSelectedEvent},
+    seconds_to_ticks,
+  },
+  loading::{AssetCollection, AssetCollectionAppExt},
+  profile::Profile,
+  settings::Settings,
+  state::{ContextScope, GameState},
 };
 
-const ARENA_WIDTH: u32 = 12;
-const ARENA_HEIGHT: u32 = 12;
-const ARENA_AREA: u32 = ARENA_WIDTH * ARENA_HEIGHT;
-const ARENA_CELL_SIZE: u32 = 6;
-const ARENA_CELL_GAP: u32 = 2;
-const ARENA_CELL_PADDING: u32 = 1;
-const ARENA_PIXEL_WIDTH: u32 =
-  ARENA_WIDTH * ARENA_CELL_SIZE + (ARENA_WIDTH - 1) * ARENA_CELL_GAP + ARENA_CELL_PADDING * 2;
-const ARENA_PIXEL_HEIGHT: u32 =
-  ARENA_HEIGHT * ARENA_CELL_SIZE + (ARENA_HEIGHT - 1) * ARENA_CELL_GAP + ARENA_CELL_PADDING * 2;
-
-const IMAGE_WIDTH: u32 = 252;
-const IMAGE_HEIGHT: u32 = 128;
+use achievements::SnakeAchievementsPlugin;
+use arena::{SnakeArenaPlugin, TILE_SIZE, arena_world_size};
+use board::SnakeBoardPlugin;
+use body::{BodyCell, SnakeBody};
+use effects::{
+  ActiveEffects, PowerUp, SnakeEffectKind, SnakeEffectsPlugin, magnet_system,
+  power_up_pickup_system,
+};
+use juice::SnakeJuicePlugin;
+use level::{
+  ArenaBounds, CurrentLevel, SnakeLevelPlugin, StartLevelEvent, snake_obstacle_collision_system,
+};
+use speed::{AddSpeedModifierEvent, SnakeSpeedPlugin, apply_snake_speed_system};
+
+mod achievements;
+mod arena;
+mod board;
+mod body;
+mod effects;
+mod juice;
+mod level;
+mod speed;
+
+/// Every entity of a snake session is despawned when the player leaves it.
+const SNAKE_SCOPE: ContextScope = ContextScope::Minigame(GameType::Snake);
+/// Empty space around the arena frame, in tiles.
+const CAMERA_MARGIN_TILES: f32 = 2.0;
+const GRASS_COLOR: Color = Color::srgb_u8(104, 159, 56);
 
 const STEP_TIME_SECONDS: f32 = 0.500;
+const FOOD_SOUND_PITCH_VARIATION: f32 = 0.08;
 
-const START_SNAKE_HEAD_POSITION: GridPosition = GridPosition {
-  x: ARENA_WIDTH / 2,
-  y: ARENA_HEIGHT / 2,
-};
 const START_SNAKE_LENGTH: u32 = 3;
+/// The snake starts heading this way, its body trailing behind the start cell.
+const START_DIRECTION: SnakeDirection = SnakeDirection::Left;
+
+pub use board::{BoardItem, SnakeBoard};
 
-const SNAKE_LENGTH_TO_CHANGE_FOOD: u32 = ARENA_AREA - 32;
+/// When fewer free cells than this are left, only plain green food spawns.
+const FREE_CELLS_TO_CHANGE_FOOD: u32 = 32;
 
 pub struct SnakeGamePlugin;
 
@@ -40,69 +63,83 @@ impl Plugin for SnakeGamePlugin {
   fn build(&self, app: &mut App) {
     app.init_state::<SnakeGameState>();
 
-    app.init_resource::<SnakeGameAssets>();
-    app.init_resource::<SnakeSoundAssets>();
+    app.add_plugins((
+      SnakeAchievementsPlugin,
+      SnakeArenaPlugin,
+      SnakeBoardPlugin,
+      SnakeLevelPlugin,
+      SnakeEffectsPlugin,
+      SnakeSpeedPlugin,
+      SnakeJuicePlugin,
+      GameMenuPlugin::<SnakeMenuAction>::default(),
+    ));
+
+    app.init_asset_collection::<SnakeGameAssets>();
+    app.init_asset_collection::<SnakeSoundAssets>();
     app.init_resource::<SnakeSkin>();
 
+    app.register_type::<SnakeBody>();
+
+    app.init_resource::<SnakeBody>();
     app.init_resource::<DirectionQueue>();
-    app.init_resource::<GameTimer>();
+    app.init_resource::<StepClock>();
+    app.init_resource::<SnakeScore>();
+    app.init_resource::<RunStopwatch>();
 
     app
       .add_systems(Update, setup.run_if(switched_to_game))
+      .add_systems(
+        Update,
+        fit_camera_to_arena_system.run_if(resource_changed::<CurrentLevel>),
+      )
       .add_systems(
         PreUpdate,
         (
           wait_for_input_system.run_if(in_state(SnakeGameState::WaitPlayer)),
-          wait_for_input_for_restart_system.run_if(in_state(SnakeGameState::GameOver)),
-        ),
+          input_accumulation_system.run_if(in_state(SnakeGameState::Playing)),
+        )
+          .after(InputSystems)
+          .run_if(not(in_state(GameState::Paused))),
       )
       .add_systems(
-        Update,
+        FixedUpdate,
         (
-          input_accumulation_system.run_if(in_state(SnakeGameState::Playing)),
-          snake_movement_system.run_if(in_state(SnakeGameState::Playing)),
-          update_snake_textures,
-          snake_self_collision_system.run_if(in_state(SnakeGameState::Playing)),
-          snake_food_collision_system.run_if(in_state(SnakeGameState::Playing)),
-          food_spawning_system.run_if(in_state(SnakeGameState::Playing)),
+          run_stopwatch_system,
+          apply_snake_speed_system,
+          snake_movement_system,
+          power_up_pickup_system,
+          magnet_system,
+          snake_self_collision_system,
+          snake_obstacle_collision_system,
+          snake_food_collision_system,
+          food_spawning_system,
         )
-          .chain(),
+          .chain()
+          .run_if(snake_running),
       )
       .add_observer(start_game)
       .add_observer(food_eaten_observer)
       .add_observer(grow_snake_observer)
-      .add_observer(snake_speed_multiplier_reset_observer)
-      .add_observer(snake_speed_multiplier_set_observer)
-      .add_observer(play_audio_once_observer)
+      .add_observer(add_score_observer)
+      .add_observer(snake_menu_observer)
       .add_observer(
         |event: On<SnakeGrowEvent>,
          mut commands: Commands,
-         snake_segment_query: Query<Entity, With<SnakeSegment>>,
-         food_query: Query<Entity, With<Food>>| {
-          let snake_length = snake_segment_query.iter().count() as u32 + event.amount;
-
-          if snake_length >= SNAKE_LENGTH_TO_CHANGE_FOOD {
+         food_query: Query<Entity, With<Food>>,
+         board: Res<SnakeBoard>| {
+          if event.length >= snake_length_to_change_food(&board) {
             destroy_all_food(&mut commands, food_query);
           }
         },
       )
-      .add_observer(
-        |event: On<SnakeGrowEvent>,
-         mut next_state: ResMut<NextState<SnakeGameState>>,
-         snake_segment_query: Query<Entity, With<SnakeSegment>>| {
-          let snake_length = snake_segment_query.iter().count() as u32 + event.amount;
-
-          if snake_length >= ARENA_AREA {
-            next_state.set(SnakeGameState::Win);
-          }
-        },
-      );
+      .add_observer(target_length_reached_observer);
 
     app
       .add_systems(OnEnter(SnakeGameState::GameOver), game_over_enter_observer)
-      .add_systems(OnExit(SnakeGameState::GameOver), game_over_exit_observer)
+      .add_systems(OnEnter(SnakeGameState::GameOver), submit_score_system)
+      .add_systems(OnEnter(SnakeGameState::Win), submit_score_system)
       .add_systems(OnEnter(SnakeGameState::Win), win_enter_observer)
-      .add_systems(OnExit(SnakeGameState::Win), win_exit_observer);
+      .add_systems(OnEnter(SnakeGameState::NotStarted), reset_game_system);
   }
 }
 
@@ -110,7 +147,32 @@ fn switched_to_game(config: Res<CurrentGameState>) -> bool {
   config.is_changed() && config.current_game == Some(GameType::Snake)
 }
 
-#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+/// The run is being played and nothing has ended it yet. When the app falls behind, several
+/// fixed ticks run in one frame before the state changes, so a pending change stops them too.
+fn snake_running(
+  state: Res<State<SnakeGameState>>,
+  next_state: Res<NextState<SnakeGameState>>,
+) -> bool {
+  *state.get() == SnakeGameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+fn target_length_reached_observer(
+  event: On<SnakeGrowEvent>,
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  current_level: Res<CurrentLevel>,
+) {
+  if event.length >= current_level.target_length {
+    next_state.set(SnakeGameState::Win);
+  }
+}
+
+fn snake_length_to_change_food(board: &SnakeBoard) -> u32 {
+  board
+    .open_cell_count()
+    .saturating_sub(FREE_CELLS_TO_CHANGE_FOOD)
+}
+
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Reflect)]
 struct GridPosition {
   x: u32,
   y: u32,
@@ -121,51 +183,33 @@ impl GridPosition {
     Self { x, y }
   }
 
-  fn opposite_to_direction(&self, direction: SnakeDirection) -> Self {
-    match direction {
-      SnakeDirection::Left => self.right(),
-      SnakeDirection::Right => self.left(),
-      SnakeDirection::Down => self.up(),
-      SnakeDirection::Up => self.down(),
-    }
+  fn cell(&self) -> UVec2 {
+    UVec2::new(self.x, self.y)
   }
 
-  fn left(&self) -> Self {
-    let (mut x, y) = (self.x, self.y);
-    if x == 0 {
-      x = ARENA_WIDTH - 1;
-    } else {
-      x -= 1;
-    }
-    Self::new(x, y)
-  }
-  fn right(&self) -> Self {
-    let (mut x, y) = (self.x, self.y);
-    if x == ARENA_WIDTH - 1 {
-      x = 0;
-    } else {
-      x += 1;
-    }
-    Self::new(x, y)
+  fn opposite_to_direction(&self, direction: SnakeDirection, bounds: &ArenaBounds) -> Self {
+    self.step(direction.get_opposite(), bounds)
   }
 
-  fn down(&self) -> Self {
-    let (x, mut y) = (self.x, self.y);
-    if y == 0 {
-      y = ARENA_HEIGHT - 1;
-    } else {
-      y -= 1;
+  /// Neighbouring cell in `direction`, wrapping around the edges of `bounds`.
+  fn step(&self, direction: SnakeDirection, bounds: &ArenaBounds) -> Self {
+    let max = bounds.max();
+    let (mut x, mut y) = (self.x, self.y);
+
+    match direction {
+      SnakeDirection::Left => x = if x == 0 { max.x } else { x - 1 },
+      SnakeDirection::Right => x = if x == max.x { 0 } else { x + 1 },
+      SnakeDirection::Down => y = if y == 0 { max.y } else { y - 1 },
+      SnakeDirection::Up => y = if y == max.y { 0 } else { y + 1 },
     }
+
     Self::new(x, y)
   }
-  fn up(&self) -> Self {
-    let (x, mut y) = (self.x, self.y);
-    if y == ARENA_HEIGHT - 1 {
-      y = 0;
-    } else {
-      y += 1;
-    }
-    Self::new(x, y)
+}
+
+impl From<UVec2> for GridPosition {
+  fn from(cell: UVec2) -> Self {
+    Self::new(cell.x, cell.y)
   }
 }
 
@@ -191,26 +235,7 @@ impl std::ops::Sub for &GridPosition {
   }
 }
 
-#[derive(Component)]
-struct SnakeHead {
-  direction: SnakeDirection,
-}
-
-#[derive(Component, Debug)]
-struct SnakeSegment {
-  follow_to: Option<Entity>,
-  direction: SnakeDirection,
-}
-
-impl Default for SnakeHead {
-  fn default() -> Self {
-    Self {
-      direction: SnakeDirection::Left,
-    }
-  }
-}
-
-#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Reflect)]
 enum SnakeDirection {
   #[default]
   Down,
@@ -228,6 +253,16 @@ impl SnakeDirection {
       SnakeDirection::Right => SnakeDirection::Left,
     }
   }
+
+  /// Step of one cell in this direction.
+  fn offset(&self) -> IVec2 {
+    match self {
+      SnakeDirection::Down => IVec2::NEG_Y,
+      SnakeDirection::Up => IVec2::Y,
+      SnakeDirection::Left => IVec2::NEG_X,
+      SnakeDirection::Right => IVec2::X,
+    }
+  }
 }
 
 #[derive(Component, Clone)]
@@ -249,33 +284,44 @@ struct FoodEatenEvent {
   food_entity: Entity,
 }
 
+/// Observers are not ordered, so the length the snake grows to is worked out
+/// before the body grows.
 #[derive(Event)]
 struct SnakeGrowEvent {
   amount: u32,
+  length: u32,
 }
 
-#[derive(Event)]
-struct RequestStartGameEvent;
-
-#[derive(Event)]
-struct SnakeSpeedMultiplierResetEvent;
-
-#[derive(Event)]
-struct SnakeSpeedMultiplierSetEvent {
-  multiplier: f32,
+impl SnakeGrowEvent {
+  fn new(body: &SnakeBody, amount: u32) -> Self {
+    Self {
+      amount,
+      length: body.len() as u32 + amount,
+    }
+  }
 }
 
 #[derive(Event)]
-struct PlayAudioOnceEvent {
-  sound_handle: Handle<AudioSource>,
+struct RequestStartGameEvent;
+
+/// Choices of the game over and win menus.
+#[derive(Clone, Copy)]
+enum SnakeMenuAction {
+  PlayAgain,
+  NextLevel,
+  LevelSelect,
+  BackToLobby,
 }
 
 #[derive(States, Debug, Clone, Hash, Eq, PartialEq, Default)]
 enum SnakeGameState {
   #[default]
   NotStarted,
+  LevelSelect,
   WaitPlayer,
   Playing,
+  /// Death animation before [`SnakeGameState::GameOver`].
+  Dying,
   GameOver,
   Win,
   ExitModal,
@@ -316,43 +362,72 @@ impl DirectionQueue {
   }
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct GameTimer(Timer);
+/// Points earned in the current run. Growing scores one point per segment,
+/// times the score multiplier effect.
+#[derive(Resource, Default)]
+struct SnakeScore(u32);
 
-impl GameTimer {
-  fn reset_duration(&mut self) {
-    self
-      .0
-      .set_duration(Duration::from_secs_f32(STEP_TIME_SECONDS));
+/// Time spent in [`SnakeGameState::Playing`] during the current run.
+#[derive(Resource, Default)]
+struct RunStopwatch(Stopwatch);
+
+/// Counts the fixed ticks between two steps of the snake. The speed is a whole number of ticks
+/// per step, so a run plays out the same at any frame rate.
+#[derive(Resource)]
+struct StepClock {
+  ticks_per_step: u32,
+  ticks: u32,
+  just_stepped: bool,
+}
+
+impl StepClock {
+  fn set_ticks_per_step(&mut self, ticks_per_step: u32) {
+    self.ticks_per_step = ticks_per_step.max(1);
+  }
+
+  /// Advances by one fixed tick. Returns `true` when the snake has to step.
+  fn tick(&mut self) -> bool {
+    self.ticks += 1;
+    self.just_stepped = self.ticks >= self.ticks_per_step;
+
+    if self.just_stepped {
+      self.ticks = 0;
+    }
+
+    self.just_stepped
+  }
+
+  /// Whether the last fixed tick was a step.
+  fn just_stepped(&self) -> bool {
+    self.just_stepped
   }
 
-  fn set_duration_multiplier(&mut self, multiplier: f32) {
-    self
-      .0
-      .set_duration(Duration::from_secs_f32(
-        self.0.duration().as_secs_f32() * 1.0 / multiplier,
-      ));
+  fn reset(&mut self) {
+    self.ticks = 0;
+    self.just_stepped = false;
   }
 }
 
-impl Default for GameTimer {
+impl Default for StepClock {
   fn default() -> Self {
-    GameTimer(Timer::from_seconds(STEP_TIME_SECONDS, TimerMode::Repeating))
+    Self {
+      ticks_per_step: seconds_to_ticks(STEP_TIME_SECONDS),
+      ticks: 0,
+      just_stepped: false,
+    }
   }
 }
 
 #[derive(Component)]
-struct GameOverUi;
-
-#[derive(Component)]
-struct WinUi;
+struct SnakeCamera;
 
-#[derive(Component)]
-struct MenuUi;
+/// Names of the skins in `snake_skins.png`. Every skin is one row of the atlas.
+pub const SNAKE_SKIN_NAMES: [&str; 3] = ["Classic", "Azure", "Ember"];
 
 #[derive(Resource, Default)]
 struct SnakeSkin {
   texture_atlas_layout: Handle<TextureAtlasLayout>,
+  row: usize,
 }
 
 impl SnakeSkin {
@@ -361,511 +436,310 @@ impl SnakeSkin {
   const BODY_END: usize = 2;
   const TURN_BODY: usize = 3;
   const TAIL: usize = 4;
+
+  const PARTS: usize = 5;
+  /// Width of `snake_skins.png` in tiles.
+  const SHEET_COLUMNS: usize = 14;
+
+  fn sprite(&self, sheet: &Handle<Image>, part: usize) -> Sprite {
+    Sprite::from_atlas_image(
+      sheet.clone(),
+      TextureAtlas {
+        layout: self.texture_atlas_layout.clone(),
+        index: self.row * Self::PARTS + part,
+      },
+    )
+  }
+
+  /// Tile of `part` for the snake tile layer. The tilemap counts the columns of the whole
+  /// `snake_skins.png`, not just the parts of a skin.
+  fn tile_index(&self, part: usize) -> u32 {
+    (self.row * Self::SHEET_COLUMNS + part) as u32
+  }
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 struct SnakeGameAssets {
   snake_skin_sheet: Handle<Image>,
+  food: Handle<Image>,
   game_over: Handle<Image>,
-  background: Handle<Image>,
+  arena_tiles: Handle<Image>,
+  effects: Handle<Image>,
 }
 
-#[derive(Resource, Default)]
+impl FromWorld for SnakeGameAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      snake_skin_sheet: asset_server.load("games/snake/snake_skins.png"),
+      food: asset_server.load("games/snake/food.png"),
+      game_over: asset_server.load("games/snake/game_over.png"),
+      arena_tiles: asset_server.load("games/snake/arena_tiles.png"),
+      effects: asset_server.load("games/snake/effects.png"),
+    }
+  }
+}
+
+impl AssetCollection for SnakeGameAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![
+      self.snake_skin_sheet.clone().untyped(),
+      self.food.clone().untyped(),
+      self.game_over.clone().untyped(),
+      self.arena_tiles.clone().untyped(),
+      self.effects.clone().untyped(),
+    ]
+  }
+}
+
+#[derive(Resource)]
 struct SnakeSoundAssets {
   eat_green: Handle<AudioSource>,
   eat_red: Handle<AudioSource>,
   eat_blue: Handle<AudioSource>,
+  hit: Handle<AudioSource>,
+}
+
+impl FromWorld for SnakeSoundAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      eat_green: asset_server.load("games/snake/sounds/green_food_pickup.wav"),
+      eat_red: asset_server.load("games/snake/sounds/red_food_pickup.wav"),
+      eat_blue: asset_server.load("games/snake/sounds/blue_food_pickup.wav"),
+      hit: asset_server.load("games/snake/sounds/Hit.bfxr"),
+    }
+  }
+}
+
+impl AssetCollection for SnakeSoundAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![
+      self.eat_green.clone().untyped(),
+      self.eat_red.clone().untyped(),
+      self.eat_blue.clone().untyped(),
+      self.hit.clone().untyped(),
+    ]
+  }
 }
 
 fn setup(
   mut commands: Commands,
   mut next_state: ResMut<NextState<SnakeGameState>>,
-  mut snake_game_assets: ResMut<SnakeGameAssets>,
-  mut sound_assets: ResMut<SnakeSoundAssets>,
   mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
   mut snake_skin: ResMut<SnakeSkin>,
-  asset_server: Res<AssetServer>,
+  settings: Res<Settings>,
+  current_level: Res<CurrentLevel>,
 ) {
-  snake_game_assets.snake_skin_sheet = asset_server.load("games/snake/snake_skins.png");
-  snake_game_assets.game_over = asset_server.load("games/snake/game_over.png");
-  snake_game_assets.background = asset_server.load("games/snake/background.png");
+  let snake_skins_layout = TextureAtlasLayout::from_grid(
+    UVec2::new(8, 8),
+    SnakeSkin::PARTS as u32,
+    SNAKE_SKIN_NAMES.len() as u32,
+    Some(UVec2::splat(1)),
+    None,
+  );
+  snake_skin.texture_atlas_layout = texture_atlas_layouts.add(snake_skins_layout);
+  snake_skin.row = settings
+    .snake_skin
+    .min(SNAKE_SKIN_NAMES.len() - 1);
 
-  sound_assets.eat_green = asset_server.load("games/snake/sounds/green_food_pickup.wav");
-  sound_assets.eat_red = asset_server.load("games/snake/sounds/red_food_pickup.wav");
-  sound_assets.eat_blue = asset_server.load("games/snake/sounds/blue_food_pickup.wav");
+  spawn_camera(&mut commands, &arena_projection(&current_level.bounds));
 
-  let snake_skins_layout =
-    TextureAtlasLayout::from_grid(UVec2::new(8, 8), 5, 1, Some(UVec2::splat(1)), None);
-  snake_skin.texture_atlas_layout = texture_atlas_layouts.add(snake_skins_layout);
+  next_state.set(SnakeGameState::LevelSelect);
+}
 
-  let (width, height) = spawn_background(&mut commands, snake_game_assets.background.clone());
+/// Арена с рамкой целиком помещается в кадр при любом соотношении сторон окна
+fn arena_projection(bounds: &ArenaBounds) -> Projection {
+  let view_size = arena_world_size(bounds) + CAMERA_MARGIN_TILES * 2.0 * TILE_SIZE;
 
   let mut projection = OrthographicProjection::default_2d();
-  projection.scaling_mode = bevy::camera::ScalingMode::Fixed {
-    width: width as f32,
-    height: height as f32,
+  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
+    min_width: view_size.x,
+    min_height: view_size.y,
   };
 
-  spawn_camera(&mut commands, &projection);
-
-  commands.trigger(RequestStartGameEvent);
+  Projection::Orthographic(projection)
+}
 
-  next_state.set(SnakeGameState::WaitPlayer);
+/// Levels differ in size, so the camera is refitted to every new one.
+fn fit_camera_to_arena_system(
+  mut camera_single: Single<&mut Projection, With<SnakeCamera>>,
+  current_level: Res<CurrentLevel>,
+) {
+  **camera_single = arena_projection(&current_level.bounds);
 }
 
 fn start_game(
   _: On<RequestStartGameEvent>,
-  mut commands: Commands,
   mut direction_queue: ResMut<DirectionQueue>,
+  mut score: ResMut<SnakeScore>,
+  mut run_stopwatch: ResMut<RunStopwatch>,
+  mut body: ResMut<SnakeBody>,
+  mut board: ResMut<SnakeBoard>,
+  current_level: Res<CurrentLevel>,
 ) {
+  score.0 = 0;
+  run_stopwatch.0.reset();
+
   let snake_head_direction = direction_queue
     .pop()
-    .unwrap_or(SnakeDirection::Left);
-
-  let snake_head_entity = spawn_snake_head_segment(
-    &mut commands,
-    &START_SNAKE_HEAD_POSITION,
-    snake_head_direction,
-  );
+    .unwrap_or(START_DIRECTION);
 
   assert!(START_SNAKE_LENGTH > 0);
 
-  let mut last_segment = (
-    snake_head_entity,
-    START_SNAKE_HEAD_POSITION.opposite_to_direction(snake_head_direction),
-  );
-  for _ in 1..START_SNAKE_LENGTH {
-    let snake_segment_entity = spawn_snake_body_segment(
-      &mut commands,
-      (last_segment.0, &last_segment.1),
-      snake_head_direction,
-    );
+  // Прошлую змейку убрали с доски раньше: новый уровень приходит уже с новой доской
+  body.clear();
 
-    last_segment = (
-      snake_segment_entity,
-      last_segment
-        .1
-        .opposite_to_direction(snake_head_direction),
-    );
+  let mut position = current_level.start.clone();
+  for _ in 0..START_SNAKE_LENGTH {
+    board.add_snake(position.cell());
+    body.push_tail(BodyCell {
+      position: position.clone(),
+      direction: snake_head_direction,
+    });
+
+    position = position.opposite_to_direction(snake_head_direction, &current_level.bounds);
   }
 }
 
-fn spawn_camera(commands: &mut Commands, projection: &OrthographicProjection) {
+fn spawn_camera(commands: &mut Commands, projection: &Projection) {
   commands.spawn((
+    Name::new("SnakeCamera"),
+    SnakeCamera,
+    DespawnOnExit(SNAKE_SCOPE),
     Camera2d,
     Camera {
       order: 1,
+      clear_color: ClearColorConfig::Custom(GRASS_COLOR),
       ..Default::default()
     },
-    Projection::Orthographic(projection.clone()),
+    projection.clone(),
     Transform::from_xyz(0.0, 0.0, 0.0),
     GlobalTransform::default(),
   ));
 }
 
-fn spawn_snake_head_segment(
-  commands: &mut Commands,
-  position: &GridPosition,
-  direction: SnakeDirection,
-) -> Entity {
-  commands
-    .spawn((
-      Name::new("SnakeHead"),
-      SnakeHead { direction },
-      SnakeSegment {
-        follow_to: None,
-        direction,
-      },
-      GridPosition::clone(position),
-      Transform {
-        translation: transform_cell_to_translation(&position),
-        ..Default::default()
-      },
-      Sprite::from_color(
-        Color::srgb(1.0, 1.0, 0.0),
-        Vec2::new(ARENA_CELL_SIZE as f32 / 1.5, ARENA_CELL_SIZE as f32 / 1.5),
-      ),
-    ))
-    .id()
-}
-
-fn spawn_snake_body_segment(
-  commands: &mut Commands,
-  follow_to: (Entity, &GridPosition),
-  direction: SnakeDirection,
-) -> Entity {
-  let (follow_to_entity, follow_to_position) = follow_to;
-
-  commands
-    .spawn((
-      Name::new("SnakeBody"),
-      SnakeSegment {
-        follow_to: Some(follow_to_entity),
-        direction,
-      },
-      GridPosition::clone(follow_to_position),
-      Transform {
-        translation: transform_cell_to_translation(&follow_to_position),
-        ..Default::default()
-      },
-      Sprite::from_color(
-        Color::srgb(1.0, 1.0, 1.0),
-        Vec2::new(ARENA_CELL_SIZE as f32 / 1.5, ARENA_CELL_SIZE as f32 / 1.5),
-      ),
-    ))
-    .id()
-}
-
 fn grow_snake_observer(
   grow_event: On<SnakeGrowEvent>,
-  mut commands: Commands,
-  snake_segment_query: Query<(Entity, &GridPosition, &SnakeSegment)>,
+  mut body: ResMut<SnakeBody>,
+  mut board: ResMut<SnakeBoard>,
 ) {
-  let segments = snake_segment_query
-    .iter()
-    .map(|(entity, _, segment)| (entity, segment))
-    .collect::<Vec<_>>();
-
-  let mut follow_to_entity = find_snake_tail_segment(&segments);
-
-  let follow_to_position = snake_segment_query
-    .get(follow_to_entity)
-    .unwrap()
-    .1;
-
-  let follow_to_direction = snake_segment_query
-    .get(follow_to_entity)
-    .unwrap()
-    .2
-    .direction;
+  let Some(tail) = body.tail().cloned() else {
+    return;
+  };
 
   for _ in 0..grow_event.amount {
-    follow_to_entity = commands
-      .spawn((
-        Name::new("SnakeBody"),
-        SnakeSegment {
-          follow_to: Some(follow_to_entity),
-          direction: follow_to_direction,
-        },
-        GridPosition::clone(follow_to_position),
-        Transform {
-          translation: transform_cell_to_translation(&follow_to_position),
-          ..Default::default()
-        },
-        Sprite::from_color(
-          Color::srgb(1.0, 1.0, 1.0),
-          Vec2::new(ARENA_CELL_SIZE as f32 / 1.5, ARENA_CELL_SIZE as f32 / 1.5),
-        ),
-      ))
-      .id();
+    board.add_snake(tail.position.cell());
+    body.push_tail(tail.clone());
   }
 }
 
-fn destroy_all_food(commands: &mut Commands, food_query: Query<Entity, With<Food>>) {
-  for entity in food_query.iter() {
-    commands.entity(entity).try_despawn();
+/// Takes the snake off the board.
+fn clear_snake(body: &mut SnakeBody, board: &mut SnakeBoard) {
+  for cell in body.iter() {
+    board.remove_snake(cell.position.cell());
   }
+  body.clear();
 }
 
-fn snake_speed_multiplier_reset_observer(
-  _: On<SnakeSpeedMultiplierResetEvent>,
-  mut game_timer: ResMut<GameTimer>,
+fn add_score_observer(
+  grow_event: On<SnakeGrowEvent>,
+  mut score: ResMut<SnakeScore>,
+  effects: ActiveEffects,
 ) {
-  game_timer.reset_duration();
+  score.0 += grow_event.amount * effects.score_multiplier();
 }
 
-fn snake_speed_multiplier_set_observer(
-  speed_multiplier_event: On<SnakeSpeedMultiplierSetEvent>,
-  mut game_timer: ResMut<GameTimer>,
-) {
-  game_timer.set_duration_multiplier(speed_multiplier_event.multiplier);
+fn destroy_all_food(commands: &mut Commands, food_query: Query<Entity, With<Food>>) {
+  for entity in food_query.iter() {
+    commands.entity(entity).try_despawn();
+  }
 }
 
 fn food_eaten_observer(
   eaten_food: On<FoodEatenEvent>,
   mut commands: Commands,
-  food_query: Query<(Entity, &GridPosition, &Food)>,
+  food_query: Query<&Food>,
+  body: Res<SnakeBody>,
   sound_assets: Res<SnakeSoundAssets>,
 ) {
-  let eaten_food = food_query
-    .get(eaten_food.food_entity)
-    .unwrap();
+  // Еду мог уже убрать магнит или перезапуск уровня
+  let Ok(food) = food_query.get(eaten_food.food_entity) else {
+    return;
+  };
 
-  match *eaten_food.2 {
+  match *food {
     Food::Green { growth_amount } => {
-      commands.trigger(SnakeGrowEvent {
-        amount: growth_amount,
-      });
-      commands.trigger(PlayAudioOnceEvent {
-        sound_handle: sound_assets.eat_green.clone(),
-      });
+      commands.trigger(SnakeGrowEvent::new(&body, growth_amount));
+      commands.trigger(
+        PlaySoundEvent::sfx(sound_assets.eat_green.clone())
+          .with_pitch_variation(FOOD_SOUND_PITCH_VARIATION),
+      );
     }
     Food::Red {
       growth_amount,
       speed_multiplier,
     } => {
-      commands.trigger(SnakeGrowEvent {
-        amount: growth_amount,
-      });
-      commands.trigger(SnakeSpeedMultiplierSetEvent {
+      commands.trigger(SnakeGrowEvent::new(&body, growth_amount));
+      commands.trigger(AddSpeedModifierEvent {
         multiplier: speed_multiplier,
       });
-      commands.trigger(PlayAudioOnceEvent {
-        sound_handle: sound_assets.eat_red.clone(),
-      });
+      commands.trigger(
+        PlaySoundEvent::sfx(sound_assets.eat_red.clone())
+          .with_pitch_variation(FOOD_SOUND_PITCH_VARIATION),
+      );
     }
     Food::Blue { speed_multiplier } => {
-      commands.trigger(SnakeSpeedMultiplierSetEvent {
+      commands.trigger(AddSpeedModifierEvent {
         multiplier: speed_multiplier,
       });
-      commands.trigger(PlayAudioOnceEvent {
-        sound_handle: sound_assets.eat_blue.clone(),
-      });
+      commands.trigger(
+        PlaySoundEvent::sfx(sound_assets.eat_blue.clone())
+          .with_pitch_variation(FOOD_SOUND_PITCH_VARIATION),
+      );
     }
   }
 
   commands
-    .entity(eaten_food.0)
+    .entity(eaten_food.food_entity)
     .try_despawn();
 }
 
-fn play_audio_once_observer(event: On<PlayAudioOnceEvent>, mut commands: Commands) {
-  commands.spawn((
-    Name::new("AudioSource"),
-    AudioPlayer::new(event.sound_handle.clone()),
-  ));
-}
-
-fn spawn_background(commands: &mut Commands, background_image: Handle<Image>) -> (usize, usize) {
-  commands.spawn((
-    Name::new("Background"),
-    Transform {
-      translation: Vec3::new(0.0, 0.0, -1.0),
-      ..Default::default()
-    },
-    Sprite::from_image(background_image),
-  ));
-
-  // TODO: сделать загрузку из спрайта
-  return (IMAGE_WIDTH as usize, IMAGE_HEIGHT as usize);
-}
-
+/// Food has no sprite: the arena draws it on the food tile layer from [`SnakeBoard`].
 fn spawn_food(commands: &mut Commands, food: Food, position: GridPosition) -> Entity {
-  // let image = asset_server.load("games/snake/food.png");
-
-  let color = match &food {
-    Food::Green { .. } => Color::srgb(0.0, 1.0, 0.0),
-    Food::Red { .. } => Color::srgb(1.0, 0.0, 0.0),
-    Food::Blue { .. } => Color::srgb(0.0, 0.0, 1.0),
-  };
-
   commands
     .spawn((
       Name::new("Food"),
       food,
-      position.clone(),
-      Sprite::from_color(
-        color,
-        Vec2::new(ARENA_CELL_SIZE as f32 / 1.5, ARENA_CELL_SIZE as f32 / 1.5),
-      ),
-      Transform {
-        translation: transform_cell_to_translation(&position),
-        ..Default::default()
-      },
+      position,
+      DespawnOnExit(SNAKE_SCOPE),
     ))
     .id()
 }
 
-fn update_snake_textures(
-  snake_head_segment: Single<
-    (
-      Entity,
-      &mut Sprite,
-      &mut Transform,
-      &GridPosition,
-      &SnakeHead,
-    ),
-    With<SnakeSegment>,
-  >,
-  mut snake_body_segments: Query<
-    (
-      Entity,
-      &mut Sprite,
-      &mut Transform,
-      &GridPosition,
-      &SnakeSegment,
-    ),
-    Without<SnakeHead>,
-  >,
-  snake_game_assets: Res<SnakeGameAssets>,
-  snake_skin: Res<SnakeSkin>,
-) {
-  use std::f32::consts::{FRAC_PI_2, PI};
-
-  fn set_sprite(
-    sprite: &mut Sprite,
-    atlas: &Handle<Image>,
-    layout: &Handle<TextureAtlasLayout>,
-    index: usize,
-  ) {
-    *sprite = Sprite::from_atlas_image(
-      atlas.clone(),
-      TextureAtlas {
-        layout: layout.clone(),
-        index,
-      },
-    );
-  }
-
-  fn set_rotation(transform: &mut Transform, rotation: f32) {
-    *transform = Transform {
-      translation: transform.translation,
-      rotation: Quat::from_rotation_z(rotation),
-      ..Default::default()
-    };
-  }
-
-  fn direction_to_rotation(dir: SnakeDirection) -> f32 {
-    match dir {
-      SnakeDirection::Left => 0.0,
-      SnakeDirection::Right => PI,
-      SnakeDirection::Down => FRAC_PI_2,
-      SnakeDirection::Up => -FRAC_PI_2,
-    }
-  }
-
-  let get_body_sprite_data =
-    |prev_dir: SnakeDirection, curr_dir: SnakeDirection, is_start: bool| -> (usize, f32) {
-      use SnakeDirection::*;
-
-      let body_skin = if is_start {
-        SnakeSkin::BODY_START
-      } else {
-        SnakeSkin::BODY_END
-      };
-
-      let result = match (prev_dir, curr_dir) {
-        // Прямые сегменты
-        (Up, Up) => (body_skin, FRAC_PI_2),
-        (Down, Down) => (body_skin, FRAC_PI_2),
-        (Left, Left) => (body_skin, 0.0),
-        (Right, Right) => (body_skin, 0.0),
-
-        // Повороты
-        (Down, Left) => (SnakeSkin::TURN_BODY, 0.0),
-        (Down, Right) => (SnakeSkin::TURN_BODY, -FRAC_PI_2),
-        (Up, Left) => (SnakeSkin::TURN_BODY, FRAC_PI_2),
-        (Up, Right) => (SnakeSkin::TURN_BODY, PI),
-        (Left, Up) => (SnakeSkin::TURN_BODY, -FRAC_PI_2),
-        (Left, Down) => (SnakeSkin::TURN_BODY, PI),
-        (Right, Up) => (SnakeSkin::TURN_BODY, 0.0),
-        (Right, Down) => (SnakeSkin::TURN_BODY, FRAC_PI_2),
-
-        _ => (body_skin, 0.0), // заглушка
-      };
-
-      // if result.0 == SnakeSkin::TURN_BODY {
-      //   *is_start = true;
-      // } else {
-      //   *is_start = !*is_start;
-      // }
-
-      result
-    };
-
-  // --- Обновляем голову ---
-  let (head_entity, mut head_sprite, mut head_transform, head_pos, head_segment) =
-    snake_head_segment.into_inner();
-
-  set_sprite(
-    &mut head_sprite,
-    &snake_game_assets.snake_skin_sheet,
-    &snake_skin.texture_atlas_layout,
-    SnakeSkin::HEAD,
-  );
-  set_rotation(
-    &mut head_transform,
-    direction_to_rotation(head_segment.direction),
-  );
-
-  // --- Создаем список сегментов с их соседями ---
-  let mut segments: Vec<(Entity, GridPosition, SnakeDirection, Option<Entity>)> =
-    vec![(head_entity, head_pos.clone(), head_segment.direction, None)];
-  segments.extend(
-    snake_body_segments
-      .iter()
-      .map(|(entity, _sprite, _transform, pos, seg)| {
-        (entity, pos.clone(), seg.direction, seg.follow_to)
-      }),
-  );
-
-  let mut segments_with_neighbors = Vec::with_capacity(segments.len());
-  for i in 0..segments.len() {
-    let (entity, position, direction, follow_to) = &segments[i];
-    let followed_by = segments.get(i + 1).map(|s| s.0);
-    segments_with_neighbors.push((
-      *entity,
-      position.clone(),
-      direction,
-      *follow_to,
-      followed_by,
-    ));
-  }
-
-  // --- Обновляем тело ---
-  let mut is_body_start = (head_pos.x + head_pos.y) % 2 == 0;
-  for i in 1..segments_with_neighbors.len() - 1 {
-    let (_, mut sprite, mut transform) = {
-      let seg = snake_body_segments
-        .get_mut(segments_with_neighbors[i].0)
-        .unwrap();
-      (segments_with_neighbors[i].0, seg.1, seg.2)
-    };
-
-    // Определяем тип сегмента
-    let (sprite_index, sprite_rotation) = get_body_sprite_data(
-      *segments_with_neighbors[i].2,
-      *segments_with_neighbors[i - 1].2,
-      is_body_start,
-    );
-
-    is_body_start = !is_body_start;
-
-    set_sprite(
-      &mut sprite,
-      &snake_game_assets.snake_skin_sheet,
-      &snake_skin.texture_atlas_layout,
-      sprite_index,
-    );
-    set_rotation(&mut transform, sprite_rotation);
-  }
-
-  // --- Обновляем хвост ---
-  let tail = segments_with_neighbors
-    .last_mut()
-    .unwrap();
-  let mut tail_seg = snake_body_segments
-    .get_mut(tail.0)
-    .unwrap();
-
-  set_sprite(
-    &mut tail_seg.1,
-    &snake_game_assets.snake_skin_sheet,
-    &snake_skin.texture_atlas_layout,
-    SnakeSkin::TAIL,
-  );
-  let last_body = &segments_with_neighbors[segments_with_neighbors.len() - 2];
-  set_rotation(&mut tail_seg.2, direction_to_rotation(*last_body.2));
-}
-
 fn input_accumulation_system(
   mut direction_queue: ResMut<DirectionQueue>,
   keyboard_input: Res<ButtonInput<KeyCode>>,
-  snake_head: Single<&SnakeHead>,
+  body: Res<SnakeBody>,
+  effects: ActiveEffects,
 ) {
+  use SnakeDirection::*;
+
+  let Some(head) = body.head() else {
+    return;
+  };
+
+  let (left, right, up, down) = if effects.is_active(SnakeEffectKind::ReverseControls) {
+    (Right, Left, Down, Up)
+  } else {
+    (Left, Right, Up, Down)
+  };
+
   let last_direction = direction_queue
     .peek()
-    .unwrap_or(&snake_head.direction);
+    .unwrap_or(&head.direction);
 
   let possible_direction: Vec<SnakeDirection> = vec![
     SnakeDirection::Left,
@@ -879,110 +753,63 @@ fn input_accumulation_system(
   })
   .collect();
 
-  if possible_direction.contains(&SnakeDirection::Left)
-    && keyboard_input.just_pressed(KeyCode::ArrowLeft)
-  {
-    direction_queue.push(SnakeDirection::Left);
-  } else if possible_direction.contains(&SnakeDirection::Right)
-    && keyboard_input.just_pressed(KeyCode::ArrowRight)
-  {
-    direction_queue.push(SnakeDirection::Right);
-  } else if possible_direction.contains(&SnakeDirection::Up)
-    && keyboard_input.just_pressed(KeyCode::ArrowUp)
+  if possible_direction.contains(&left) && keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+    direction_queue.push(left);
+  } else if possible_direction.contains(&right) && keyboard_input.just_pressed(KeyCode::ArrowRight)
   {
-    direction_queue.push(SnakeDirection::Up);
-  } else if possible_direction.contains(&SnakeDirection::Down)
-    && keyboard_input.just_pressed(KeyCode::ArrowDown)
-  {
-    direction_queue.push(SnakeDirection::Down);
+    direction_queue.push(right);
+  } else if possible_direction.contains(&up) && keyboard_input.just_pressed(KeyCode::ArrowUp) {
+    direction_queue.push(up);
+  } else if possible_direction.contains(&down) && keyboard_input.just_pressed(KeyCode::ArrowDown) {
+    direction_queue.push(down);
   }
 }
 
 fn snake_movement_system(
-  mut game_timer: ResMut<GameTimer>,
+  mut step_clock: ResMut<StepClock>,
   mut direction_queue: ResMut<DirectionQueue>,
-  mut snake_segment_query: Query<
-    (Entity, &mut Transform, &mut GridPosition, &mut SnakeSegment),
-    Without<SnakeHead>,
-  >,
-  snake_head_single: Single<(Entity, &mut Transform, &mut GridPosition, &mut SnakeHead)>,
-  time: Res<Time>,
+  mut body: ResMut<SnakeBody>,
+  mut board: ResMut<SnakeBoard>,
+  current_level: Res<CurrentLevel>,
 ) {
-  game_timer.tick(time.delta());
-
-  if !game_timer.is_finished() {
+  if !step_clock.tick() {
     return;
   }
 
-  let (snake_head_entity, mut snake_head_transform, mut snake_head_position, mut snake_head) =
-    snake_head_single.into_inner();
-
-  let mut before_move_segment_positions = snake_segment_query
-    .iter()
-    .map(|segment| (segment.0, segment.2.clone(), segment.3.direction))
-    .collect::<Vec<(Entity, GridPosition, SnakeDirection)>>();
-
-  before_move_segment_positions.insert(
-    0,
-    (
-      snake_head_entity,
-      snake_head_position.clone(),
-      snake_head.direction,
-    ),
-  );
+  let Some(head) = body.head() else {
+    return;
+  };
 
   let direction = direction_queue
     .pop()
-    .unwrap_or(snake_head.direction);
+    .unwrap_or(head.direction);
 
-  match direction {
-    SnakeDirection::Left => {
-      snake_head.direction = SnakeDirection::Left;
-      *snake_head_position = snake_head_position.left();
-    }
-    SnakeDirection::Right => {
-      snake_head.direction = SnakeDirection::Right;
-      *snake_head_position = snake_head_position.right();
-    }
-    SnakeDirection::Down => {
-      snake_head.direction = SnakeDirection::Down;
-      *snake_head_position = snake_head_position.down();
-    }
-    SnakeDirection::Up => {
-      snake_head.direction = SnakeDirection::Up;
-      *snake_head_position = snake_head_position.up();
-    }
-  }
-
-  snake_head_transform.translation = transform_cell_to_translation(&snake_head_position);
-
-  for (_, mut segment_transform, mut segment_position, mut segment) in
-    snake_segment_query.iter_mut()
-  {
-    if let Some(follow_to_segment_entity) = segment.follow_to {
-      let (_, follow_to_segment_position, follow_to_segment_direction) =
-        before_move_segment_positions
-          .iter()
-          .find(|(entity, _, _)| entity == &follow_to_segment_entity)
-          .unwrap();
+  let mut position = head
+    .position
+    .step(direction, &current_level.bounds);
 
-      *segment_position = GridPosition::clone(follow_to_segment_position);
-      segment.direction = *follow_to_segment_direction;
+  if let Some(exit) = current_level.portal_exit(&position) {
+    position = exit;
+  }
 
-      segment_transform.translation = transform_cell_to_translation(&segment_position);
-    }
+  board.add_snake(position.cell());
+  if let Some(tail) = body.step(BodyCell {
+    position,
+    direction,
+  }) {
+    board.remove_snake(tail.position.cell());
   }
 }
 
 fn food_spawning_system(
   mut commands: Commands,
-  snake_segment_query: Query<&SnakeSegment>,
-  snake_segment_position_query: Query<&GridPosition, With<SnakeSegment>>,
-  food_query: Query<(Entity, &GridPosition, &Food)>,
+  food_query: Query<&Food>,
+  body: Res<SnakeBody>,
+  board: Res<SnakeBoard>,
 ) {
-  let snake_length = snake_segment_query.iter().count() as u32;
+  let snake_length = body.len() as u32;
 
-  let available_foods = if snake_length < SNAKE_LENGTH_TO_CHANGE_FOOD {
+  let available_foods = if snake_length < snake_length_to_change_food(&board) {
     vec![
       Food::Green { growth_amount: 1 },
       Food::Red {
@@ -997,176 +824,200 @@ fn food_spawning_system(
     vec![Food::Green { growth_amount: 1 }]
   };
 
-  let mut except: Vec<GridPosition> = snake_segment_position_query
-    .iter()
-    .map(|p| p.clone())
-    .chain(
-      food_query
-        .iter()
-        .map(|(_, p, _)| p.clone()),
-    )
-    .collect();
-
-  fn has_food_type(food_query: &Query<(Entity, &GridPosition, &Food)>, target: &Food) -> bool {
+  fn has_food_type(food_query: &Query<&Food>, target: &Food) -> bool {
     food_query
       .iter()
-      .any(|(_, _, food)| std::mem::discriminant(food) == std::mem::discriminant(target))
+      .any(|food| std::mem::discriminant(food) == std::mem::discriminant(target))
   }
 
-  let spawn_if_missing = |food: &Food, except: &mut Vec<GridPosition>, commands: &mut Commands| {
-    if !has_food_type(&food_query, food) {
-      let position = get_random_position_except(except);
-      except.push(position.clone());
-      spawn_food(commands, food.clone(), position);
+  // Доска узнает о новой еде только после применения команд
+  let mut spawned = Vec::new();
+
+  for food in available_foods.iter() {
+    if has_food_type(&food_query, food) {
+      continue;
     }
-  };
 
-  available_foods.iter().for_each(|food| {
-    spawn_if_missing(food, &mut except, &mut commands);
-  });
+    let Some(cell) = board.random_free_cell(&spawned) else {
+      warn!("No free cell left for food");
+      return;
+    };
+
+    spawned.push(cell);
+    spawn_food(&mut commands, food.clone(), cell.into());
+  }
 }
 
 fn snake_self_collision_system(
   mut next_state: ResMut<NextState<SnakeGameState>>,
-  head_single: Single<(Entity, &GridPosition), With<SnakeHead>>,
-  segment_query: Query<(Entity, &GridPosition), With<SnakeSegment>>,
+  body: Res<SnakeBody>,
+  board: Res<SnakeBoard>,
+  effects: ActiveEffects,
 ) {
-  let (head_entity, head_position) = head_single.into_inner();
+  let Some(head) = body.head() else {
+    return;
+  };
 
-  for (segment_entity, segment_position) in segment_query.iter() {
-    if head_entity == segment_entity {
-      continue;
-    }
+  if effects.is_active(SnakeEffectKind::Ghost) {
+    return;
+  }
 
-    if *head_position == *segment_position {
-      next_state.set(SnakeGameState::GameOver);
-      return;
-    }
+  // Кроме головы в клетке есть ещё кто-то из сегментов
+  if board.snake_count(head.position.cell()) > 1 {
+    next_state.set(SnakeGameState::Dying);
   }
 }
 
 fn snake_food_collision_system(
   mut commands: Commands,
-  segment_query: Query<&GridPosition, With<SnakeSegment>>,
-  food_query: Query<(Entity, &GridPosition, &Food)>,
+  body: Res<SnakeBody>,
+  board: Res<SnakeBoard>,
 ) {
-  let mut foods = HashMap::new();
+  let Some(head) = body.head() else {
+    return;
+  };
 
-  for (entity, position, food) in food_query.iter() {
-    foods.insert(position, (entity, food));
+  if let BoardItem::Food(food_entity) = board.item(head.position.cell()) {
+    commands.trigger(FoodEatenEvent { food_entity });
   }
+}
 
-  for position in segment_query.iter() {
-    if foods.contains_key(&position) {
-      commands.trigger(FoodEatenEvent {
-        food_entity: foods.get(&position).unwrap().0,
-      });
-      return;
-    }
-  }
+fn submit_score_system(
+  mut game_result_messages: MessageWriter<GameResultMessage>,
+  score: Res<SnakeScore>,
+) {
+  game_result_messages.write(GameResultMessage {
+    game: GameType::Snake,
+    score: score.0,
+  });
 }
 
 fn game_over_enter_observer(
   mut commands: Commands,
   font_assets: Res<FontAssets>,
   snake_game_assets: Res<SnakeGameAssets>,
+  score: Res<SnakeScore>,
+  run_stopwatch: Res<RunStopwatch>,
+  body: Res<SnakeBody>,
 ) {
-  let create_game_over_ui = |game_over_image: Handle<Image>| {
-    let image_node = (
-      Node {
-        height: px(300.),
-        ..default()
-      },
-      children![
-        (ImageNode {
-          image: game_over_image,
-          ..default()
-        })
-      ],
+  GameMenuBuilder::new()
+    .image(snake_game_assets.game_over.clone())
+    .line(run_stats(body.len(), &score, &run_stopwatch))
+    .option("Play again", SnakeMenuAction::PlayAgain)
+    .option("Level select", SnakeMenuAction::LevelSelect)
+    .option("Back to lobby", SnakeMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(SnakeGameState::GameOver),
     );
+}
 
-    let text_node = (
-      Text {
-        0: String::from("Press any key to restart..."),
-        ..Default::default()
-      },
-      TextFont {
-        font: font_assets.regular.clone(),
-        font_size: 32.,
-        ..Default::default()
-      },
-      TextColor(Color::WHITE),
-    );
+fn win_enter_observer(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  score: Res<SnakeScore>,
+  run_stopwatch: Res<RunStopwatch>,
+  current_level: Res<CurrentLevel>,
+  profile: Res<Profile>,
+  body: Res<SnakeBody>,
+) {
+  // Рекорд мог быть уже записан в этом же кадре, поэтому берём максимум
+  let best = profile
+    .best_score(&current_level.score_key())
+    .max(score.0);
+
+  let mut menu = GameMenuBuilder::new()
+    .title("Congratulations! You won!")
+    .line(format!("{} cleared", current_level.name))
+    .line(run_stats(body.len(), &score, &run_stopwatch))
+    .line(format!("Best {best}"));
+
+  if current_level.next_level().is_some() {
+    menu = menu.option("Next level", SnakeMenuAction::NextLevel);
+  }
 
-    (
-      Node {
-        width: percent(100),
-        height: percent(100),
-        flex_direction: FlexDirection::Column,
-        align_items: AlignItems::Center,
-        justify_content: JustifyContent::Center,
-        ..default()
-      },
-      children![image_node, text_node],
-    )
-  };
+  menu
+    .option("Play again", SnakeMenuAction::PlayAgain)
+    .option("Level select", SnakeMenuAction::LevelSelect)
+    .option("Back to lobby", SnakeMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(SnakeGameState::Win),
+    );
+}
 
-  commands.spawn((
-    create_game_over_ui(snake_game_assets.game_over.clone()),
-    GameOverUi,
-  ));
+fn run_stats(snake_length: usize, score: &SnakeScore, run_stopwatch: &RunStopwatch) -> String {
+  format!(
+    "Length {snake_length}  score {}  time {:.1}s",
+    score.0,
+    run_stopwatch.0.elapsed_secs()
+  )
 }
 
-fn game_over_exit_observer(
+fn snake_menu_observer(
+  event: On<GameMenuSelectedEvent<SnakeMenuAction>>,
   mut commands: Commands,
-  game_over_ui_query: Query<Entity, With<GameOverUi>>,
+  mut next_state: ResMut<NextState<SnakeGameState>>,
+  mut game_exit_messages: MessageWriter<GameExitMessage>,
+  mut body: ResMut<SnakeBody>,
+  mut board: ResMut<SnakeBoard>,
+  food_query: Query<Entity, With<Food>>,
+  current_level: Res<CurrentLevel>,
 ) {
-  for entity in game_over_ui_query.iter() {
-    commands.entity(entity).despawn();
+  despawn_run(&mut commands, &mut body, &mut board, &food_query);
+
+  match event.action {
+    SnakeMenuAction::PlayAgain => {
+      commands.trigger(RequestStartGameEvent);
+      next_state.set(SnakeGameState::WaitPlayer);
+    }
+    SnakeMenuAction::NextLevel => {
+      if let Some(index) = current_level.next_level() {
+        commands.trigger(StartLevelEvent { index });
+      }
+    }
+    SnakeMenuAction::LevelSelect => next_state.set(SnakeGameState::LevelSelect),
+    SnakeMenuAction::BackToLobby => {
+      next_state.set(SnakeGameState::NotStarted);
+      game_exit_messages.write(GameExitMessage);
+    }
   }
 }
 
-fn win_enter_observer(mut commands: Commands, font_assets: Res<FontAssets>) {
-  let create_ui = || {
-    let text_node = (
-      Text {
-        0: String::from("Congratulations! You won!"),
-        ..Default::default()
-      },
-      TextFont {
-        font: font_assets.regular.clone(),
-        font_size: 32.,
-        ..Default::default()
-      },
-      TextColor(Color::WHITE),
-    );
+fn despawn_run(
+  commands: &mut Commands,
+  body: &mut SnakeBody,
+  board: &mut SnakeBoard,
+  food_query: &Query<Entity, With<Food>>,
+) {
+  clear_snake(body, board);
 
-    (
-      Node {
-        width: percent(100),
-        height: percent(100),
-        flex_direction: FlexDirection::Column,
-        align_items: AlignItems::Center,
-        justify_content: JustifyContent::Center,
-        ..default()
-      },
-      children![text_node],
-    )
-  };
+  for entity in food_query.iter() {
+    commands.entity(entity).despawn();
+  }
+}
 
-  commands.spawn((create_ui(), WinUi));
+/// Forgets the last run once the player is back in the lobby. The entities themselves are
+/// despawned with the minigame's [`ContextScope`].
+fn reset_game_system(
+  mut direction_queue: ResMut<DirectionQueue>,
+  mut body: ResMut<SnakeBody>,
+  mut board: ResMut<SnakeBoard>,
+) {
+  clear_snake(&mut body, &mut board);
+  direction_queue.inner.clear();
 }
 
-fn win_exit_observer(mut commands: Commands, win_ui_query: Query<Entity, With<GameOverUi>>) {
-  for entity in win_ui_query.iter() {
-    commands.entity(entity).despawn();
-  }
+fn run_stopwatch_system(mut run_stopwatch: ResMut<RunStopwatch>, time: Res<Time>) {
+  run_stopwatch.0.tick(time.delta());
 }
 
 fn wait_for_input_system(
   mut direction_queue: ResMut<DirectionQueue>,
   mut next_state: ResMut<NextState<SnakeGameState>>,
-  mut game_timer: ResMut<GameTimer>,
+  mut step_clock: ResMut<StepClock>,
   keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
   const INPUTS: [KeyCode; 4] = [
@@ -1191,111 +1042,194 @@ fn wait_for_input_system(
   direction_queue.push(head_direction);
 
   if keyboard_input.any_just_pressed(INPUTS) {
-    game_timer.reset_duration();
-    game_timer.reset();
+    step_clock.reset();
     next_state.set(SnakeGameState::Playing);
   }
 }
 
-fn wait_for_input_for_restart_system(
-  mut commands: Commands,
-  mut direction_queue: ResMut<DirectionQueue>,
-  mut next_state: ResMut<NextState<SnakeGameState>>,
-  mut game_timer: ResMut<GameTimer>,
-  mut snake_segment_query: Query<Entity, With<SnakeSegment>>,
-  mut food_query: Query<Entity, With<Food>>,
-  keyboard_input: Res<ButtonInput<KeyCode>>,
-) {
-  const INPUTS: [KeyCode; 4] = [
-    KeyCode::ArrowLeft,
-    KeyCode::ArrowRight,
-    KeyCode::ArrowUp,
-    KeyCode::ArrowDown,
-  ];
+#[cfg(test)]
+mod tests {
+  use bevy::state::app::StatesPlugin;
 
-  let mut head_direction = SnakeDirection::Left;
+  use super::*;
 
-  if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
-    head_direction = SnakeDirection::Left;
-  } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
-    head_direction = SnakeDirection::Right;
-  } else if keyboard_input.just_pressed(KeyCode::ArrowUp) {
-    head_direction = SnakeDirection::Up;
-  } else if keyboard_input.just_pressed(KeyCode::ArrowDown) {
-    head_direction = SnakeDirection::Down;
-  }
+  /// Number of [`RequestStartGameEvent`]s triggered.
+  #[derive(Resource, Default)]
+  struct StartRequests(u32);
 
-  direction_queue.push(head_direction);
+  fn app(state: SnakeGameState) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin));
+    app.insert_state(state);
 
-  if keyboard_input.any_just_pressed(INPUTS) {
-    snake_segment_query
-      .iter_mut()
-      .for_each(|food| {
-        commands.entity(food).despawn();
-      });
+    app.add_message::<GameResultMessage>();
+    app.add_message::<GameExitMessage>();
 
-    food_query.iter_mut().for_each(|food| {
-      commands.entity(food).despawn();
-    });
+    app.init_resource::<SnakeBody>();
+    app.init_resource::<SnakeScore>();
+    app.init_resource::<CurrentLevel>();
+    app.init_resource::<SnakeBoard>();
+    app.init_resource::<StartRequests>();
 
-    commands.trigger(RequestStartGameEvent);
+    app.add_observer(
+      |_: On<RequestStartGameEvent>, mut requests: ResMut<StartRequests>| {
+        requests.0 += 1;
+      },
+    );
 
-    game_timer.reset_duration();
-    game_timer.reset();
-    next_state.set(SnakeGameState::Playing);
+    app
   }
-}
 
-fn transform_cell_to_translation(GridPosition { x, y }: &GridPosition) -> Vec3 {
-  const CENTER_X: f32 = -(ARENA_PIXEL_WIDTH as f32) / 2.0;
-  const CENTER_Y: f32 = -(ARENA_PIXEL_HEIGHT as f32) / 2.0;
+  fn state(app: &App) -> &SnakeGameState {
+    app
+      .world()
+      .resource::<State<SnakeGameState>>()
+      .get()
+  }
 
-  let pixel_x =
-    CENTER_X + (x * (ARENA_CELL_SIZE + ARENA_CELL_GAP)) as f32 + ARENA_CELL_PADDING as f32;
-  let pixel_y =
-    CENTER_Y + (y * (ARENA_CELL_SIZE + ARENA_CELL_GAP)) as f32 + ARENA_CELL_PADDING as f32;
+  fn grow_body(app: &mut App, length: u32) {
+    let mut body = app
+      .world_mut()
+      .resource_mut::<SnakeBody>();
+    for x in 0..length {
+      body.push_tail(BodyCell {
+        position: GridPosition::new(x, 0),
+        direction: START_DIRECTION,
+      });
+    }
+  }
 
-  Vec3::new(
-    pixel_x + (ARENA_CELL_SIZE as f32) / 2.0,
-    pixel_y + (ARENA_CELL_SIZE as f32) / 2.0,
-    0.0,
-  )
-}
+  fn trigger_grow(app: &mut App, amount: u32) {
+    let event = SnakeGrowEvent::new(app.world().resource::<SnakeBody>(), amount);
+    app.world_mut().trigger(event);
+    app.update();
+  }
+
+  fn select(app: &mut App, action: SnakeMenuAction) {
+    app
+      .world_mut()
+      .trigger(GameMenuSelectedEvent { action });
+    app.update();
+  }
 
-fn get_random_position_except(except: &Vec<GridPosition>) -> GridPosition {
-  let except = except.iter().collect::<HashSet<_>>();
+  #[test]
+  fn reaching_target_length_wins() {
+    let mut app = app(SnakeGameState::Playing);
+    app.add_observer(target_length_reached_observer);
+    app
+      .world_mut()
+      .resource_mut::<CurrentLevel>()
+      .target_length = 4;
+    grow_body(&mut app, 3);
 
-  let mut i = rand::random_range(0..ARENA_AREA as usize - except.len());
+    trigger_grow(&mut app, 1);
 
-  for y in 0..ARENA_HEIGHT {
-    for x in 0..ARENA_WIDTH {
-      if except.contains(&GridPosition { x, y }) {
-        continue;
-      }
-      if i == 0 {
-        return GridPosition { x, y };
-      }
-      i -= 1;
-    }
+    assert_eq!(state(&app), &SnakeGameState::Win);
   }
 
-  panic!("Failed to find a random position");
-}
+  #[test]
+  fn growing_short_of_target_length_keeps_playing() {
+    let mut app = app(SnakeGameState::Playing);
+    app.add_observer(target_length_reached_observer);
+    app
+      .world_mut()
+      .resource_mut::<CurrentLevel>()
+      .target_length = 10;
+    grow_body(&mut app, 3);
 
-fn find_snake_tail_segment(segments: &[(Entity, &SnakeSegment)]) -> Entity {
-  let mut referenced = HashSet::<Entity>::new();
+    trigger_grow(&mut app, 1);
 
-  for (_, segment) in segments.iter() {
-    if let Some(follow_to) = segment.follow_to {
-      referenced.insert(follow_to);
+    assert_eq!(state(&app), &SnakeGameState::Playing);
+  }
+
+  #[test]
+  fn win_and_game_over_submit_the_score() {
+    for end_state in [SnakeGameState::Win, SnakeGameState::GameOver] {
+      let mut app = app(SnakeGameState::Playing);
+      app.add_systems(OnEnter(end_state.clone()), submit_score_system);
+      app
+        .world_mut()
+        .resource_mut::<SnakeScore>()
+        .0 = 42;
+
+      app
+        .world_mut()
+        .resource_mut::<NextState<SnakeGameState>>()
+        .set(end_state.clone());
+      app.update();
+
+      assert_eq!(state(&app), &end_state);
+      let scores: Vec<u32> = app
+        .world()
+        .resource::<Messages<GameResultMessage>>()
+        .iter_current_update_messages()
+        .map(|message| message.score)
+        .collect();
+      assert_eq!(scores, [42]);
+    }
+  }
+
+  #[test]
+  fn play_again_restarts_the_run() {
+    for end_state in [SnakeGameState::Win, SnakeGameState::GameOver] {
+      let mut app = app(end_state);
+      app.add_observer(snake_menu_observer);
+      grow_body(&mut app, 3);
+
+      select(&mut app, SnakeMenuAction::PlayAgain);
+
+      assert_eq!(state(&app), &SnakeGameState::WaitPlayer);
+      assert_eq!(
+        app
+          .world()
+          .resource::<StartRequests>()
+          .0,
+        1
+      );
+      // Змейка прошлого забега убрана до старта нового
+      assert_eq!(
+        app
+          .world()
+          .resource::<SnakeBody>()
+          .len(),
+        0
+      );
     }
   }
 
-  for (entity, _) in segments.iter() {
-    if !referenced.contains(entity) {
-      return *entity;
+  #[test]
+  fn back_to_lobby_exits_the_game() {
+    for end_state in [SnakeGameState::Win, SnakeGameState::GameOver] {
+      let mut app = app(end_state);
+      app.add_observer(snake_menu_observer);
+
+      select(&mut app, SnakeMenuAction::BackToLobby);
+
+      assert_eq!(state(&app), &SnakeGameState::NotStarted);
+      assert_eq!(
+        app
+          .world()
+          .resource::<Messages<GameExitMessage>>()
+          .len(),
+        1
+      );
+      assert_eq!(
+        app
+          .world()
+          .resource::<StartRequests>()
+          .0,
+        0
+      );
     }
   }
 
-  panic!("No tail segment found");
+  #[test]
+  fn level_select_leaves_the_end_screen() {
+    let mut app = app(SnakeGameState::Win);
+    app.add_observer(snake_menu_observer);
+
+    select(&mut app, SnakeMenuAction::LevelSelect);
+
+    assert_eq!(state(&app), &SnakeGameState::LevelSelect);
+  }
 }
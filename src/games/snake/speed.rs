@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+
+use crate::{game::FontAssets, settings::Settings};
+
+use super::{
+  ActiveEffects, RequestStartGameEvent, SNAKE_SCOPE, START_SNAKE_LENGTH, SnakeBody, SnakeGameState,
+  StepClock, seconds_to_ticks,
+};
+
+/// Food multipliers are multiplied into a running factor that is kept in this range, so food
+/// eaten after reaching a bound still moves the speed back.
+const MIN_FOOD_MULTIPLIER: f32 = 0.5;
+const MAX_FOOD_MULTIPLIER: f32 = 2.0;
+/// Speed gained for every segment grown past the start length.
+const DIFFICULTY_PER_SEGMENT: f32 = 0.02;
+const MAX_DIFFICULTY: f32 = 1.6;
+const MIN_STEPS_PER_SECOND: f32 = 1.0;
+const MAX_STEPS_PER_SECOND: f32 = 10.0;
+
+pub(super) struct SnakeSpeedPlugin;
+
+impl Plugin for SnakeSpeedPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<SpeedModifiers>();
+
+    app
+      .add_observer(add_speed_modifier_observer)
+      .add_observer(reset_speed_modifiers_observer);
+
+    app
+      .add_systems(OnExit(SnakeGameState::NotStarted), spawn_speed_debug_text)
+      .add_systems(
+        Update,
+        (toggle_speed_debug_system, update_speed_debug_system).chain(),
+      );
+  }
+}
+
+/// Speed multiplier picked up during the run, e.g. from red or blue food.
+#[derive(Event)]
+pub(super) struct AddSpeedModifierEvent {
+  pub multiplier: f32,
+}
+
+/// Speed picked up from food in the current run.
+#[derive(Resource)]
+pub(super) struct SpeedModifiers {
+  food: f32,
+  eaten: u32,
+}
+
+impl Default for SpeedModifiers {
+  fn default() -> Self {
+    Self {
+      food: 1.0,
+      eaten: 0,
+    }
+  }
+}
+
+impl SpeedModifiers {
+  fn add(&mut self, multiplier: f32) {
+    self.food = (self.food * multiplier).clamp(MIN_FOOD_MULTIPLIER, MAX_FOOD_MULTIPLIER);
+    self.eaten += 1;
+  }
+}
+
+/// How the current step rate is put together. Every factor is recomputed from the game state,
+/// so eating food in any order gives the same speed as long as it stays within the bounds.
+#[derive(Debug)]
+struct SnakeSpeed {
+  base: f32,
+  difficulty: f32,
+  food: f32,
+  effects: f32,
+  steps_per_second: f32,
+}
+
+impl SnakeSpeed {
+  fn step_seconds(&self) -> f32 {
+    1.0 / self.steps_per_second
+  }
+
+  /// The step duration rounded to fixed ticks. This is what the snake actually moves at.
+  fn ticks_per_step(&self) -> u32 {
+    seconds_to_ticks(self.step_seconds())
+  }
+}
+
+/// Rises linearly with the snake's length until it reaches [`MAX_DIFFICULTY`].
+fn difficulty(snake_length: u32) -> f32 {
+  let grown = snake_length.saturating_sub(START_SNAKE_LENGTH) as f32;
+
+  (1.0 + grown * DIFFICULTY_PER_SEGMENT).min(MAX_DIFFICULTY)
+}
+
+fn snake_speed(
+  settings: &Settings,
+  modifiers: &SpeedModifiers,
+  effects: f32,
+  snake_length: u32,
+) -> SnakeSpeed {
+  let base = 1.0 / settings.snake_step_seconds;
+  let difficulty = difficulty(snake_length);
+  let food = modifiers.food;
+
+  SnakeSpeed {
+    base,
+    difficulty,
+    food,
+    effects,
+    steps_per_second: (base * difficulty * food * effects)
+      .clamp(MIN_STEPS_PER_SECOND, MAX_STEPS_PER_SECOND),
+  }
+}
+
+/// Sets the ticks per step from the current [`SnakeSpeed`]. Runs right before the snake moves.
+pub(super) fn apply_snake_speed_system(
+  mut step_clock: ResMut<StepClock>,
+  modifiers: Res<SpeedModifiers>,
+  settings: Res<Settings>,
+  effects: ActiveEffects,
+  body: Res<SnakeBody>,
+) {
+  let speed = snake_speed(
+    &settings,
+    &modifiers,
+    effects.speed_multiplier(),
+    body.len() as u32,
+  );
+
+  step_clock.set_ticks_per_step(speed.ticks_per_step());
+}
+
+fn add_speed_modifier_observer(
+  event: On<AddSpeedModifierEvent>,
+  mut modifiers: ResMut<SpeedModifiers>,
+) {
+  modifiers.add(event.multiplier);
+}
+
+fn reset_speed_modifiers_observer(
+  _: On<RequestStartGameEvent>,
+  mut modifiers: ResMut<SpeedModifiers>,
+) {
+  *modifiers = SpeedModifiers::default();
+}
+
+#[derive(Component)]
+struct SpeedDebugText;
+
+fn spawn_speed_debug_text(mut commands: Commands, font_assets: Res<FontAssets>) {
+  commands.spawn((
+    Name::new("SpeedDebugText"),
+    SpeedDebugText,
+    DespawnOnExit(SNAKE_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Visibility::Hidden,
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 16.,
+      ..Default::default()
+    },
+    TextColor(Color::srgb(0.6, 1.0, 0.6)),
+  ));
+}
+
+fn toggle_speed_debug_system(
+  mut debug_text_query: Query<&mut Visibility, With<SpeedDebugText>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::F3) {
+    return;
+  }
+
+  for mut visibility in &mut debug_text_query {
+    *visibility = match *visibility {
+      Visibility::Hidden => Visibility::Inherited,
+      _ => Visibility::Hidden,
+    };
+  }
+}
+
+fn update_speed_debug_system(
+  mut debug_text_query: Query<(&mut Text, &Visibility), With<SpeedDebugText>>,
+  modifiers: Res<SpeedModifiers>,
+  settings: Res<Settings>,
+  effects: ActiveEffects,
+  body: Res<SnakeBody>,
+) {
+  let speed = snake_speed(
+    &settings,
+    &modifiers,
+    effects.speed_multiplier(),
+    body.len() as u32,
+  );
+
+  for (mut text, visibility) in &mut debug_text_query {
+    if *visibility == Visibility::Hidden {
+      continue;
+    }
+
+    text.0 = format!(
+      "speed {:.2} steps/s ({} ticks/step) = base {:.2} x difficulty {:.2} x food {:.2} ({} eaten) x effects {:.2}\n\
+       clamped to {MIN_STEPS_PER_SECOND}..{MAX_STEPS_PER_SECOND}",
+      speed.steps_per_second,
+      speed.ticks_per_step(),
+      speed.base,
+      speed.difficulty,
+      speed.food,
+      modifiers.eaten,
+      speed.effects,
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RED: f32 = 1.25;
+  const BLUE: f32 = 0.85;
+
+  fn modifiers(multipliers: &[f32]) -> SpeedModifiers {
+    let mut modifiers = SpeedModifiers::default();
+    for multiplier in multipliers {
+      modifiers.add(*multiplier);
+    }
+
+    modifiers
+  }
+
+  fn steps_per_second(modifiers: &SpeedModifiers, effects: f32, snake_length: u32) -> f32 {
+    snake_speed(&Settings::default(), modifiers, effects, snake_length).steps_per_second
+  }
+
+  #[test]
+  fn difficulty_starts_at_one() {
+    assert_eq!(difficulty(0), 1.0);
+    assert_eq!(difficulty(START_SNAKE_LENGTH), 1.0);
+    assert!(difficulty(START_SNAKE_LENGTH + 1) > 1.0);
+  }
+
+  #[test]
+  fn difficulty_is_capped() {
+    assert_eq!(difficulty(1000), MAX_DIFFICULTY);
+    assert_eq!(difficulty(u32::MAX), MAX_DIFFICULTY);
+  }
+
+  #[test]
+  fn food_multiplier_is_clamped() {
+    assert_eq!(modifiers(&[RED; 20]).food, MAX_FOOD_MULTIPLIER);
+    assert_eq!(modifiers(&[BLUE; 20]).food, MIN_FOOD_MULTIPLIER);
+  }
+
+  #[test]
+  fn food_after_a_bound_still_changes_the_speed() {
+    let mut food = [RED; 21];
+    food[20] = BLUE;
+
+    assert_eq!(modifiers(&food).food, MAX_FOOD_MULTIPLIER * BLUE);
+
+    let mut food = [BLUE; 21];
+    food[20] = RED;
+
+    assert_eq!(modifiers(&food).food, MIN_FOOD_MULTIPLIER * RED);
+  }
+
+  #[test]
+  fn food_order_does_not_matter_within_the_bounds() {
+    let a = steps_per_second(&modifiers(&[RED, BLUE, RED]), 1.0, 5);
+    let b = steps_per_second(&modifiers(&[BLUE, RED, RED]), 1.0, 5);
+
+    assert!((a - b).abs() < 1e-5, "{a} != {b}");
+  }
+
+  #[test]
+  fn speed_is_clamped() {
+    let modifiers = SpeedModifiers::default();
+
+    assert_eq!(
+      steps_per_second(&modifiers, 100.0, 1000),
+      MAX_STEPS_PER_SECOND
+    );
+    assert_eq!(
+      steps_per_second(&modifiers, 0.01, START_SNAKE_LENGTH),
+      MIN_STEPS_PER_SECOND
+    );
+  }
+}
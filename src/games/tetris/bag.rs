@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+
+use super::piece::Tetromino;
+
+/// Pieces shown in the next queue.
+pub(super) const PREVIEW_LEN: usize = 5;
+
+/// 7-bag randomizer: the pieces are dealt in shuffled bags of all seven, so the same piece
+/// never comes more than twice in a row and no piece is missing for long.
+#[derive(Default)]
+pub(super) struct PieceBag {
+  queue: VecDeque<Tetromino>,
+}
+
+impl PieceBag {
+  pub fn next(&mut self) -> Tetromino {
+    self.fill();
+    let piece = self
+      .queue
+      .pop_front()
+      .expect("the bag has just been filled");
+    self.fill();
+
+    piece
+  }
+
+  /// The next [`PREVIEW_LEN`] pieces, in the order they are dealt.
+  pub fn preview(&self) -> impl Iterator<Item = Tetromino> + '_ {
+    self
+      .queue
+      .iter()
+      .take(PREVIEW_LEN)
+      .copied()
+  }
+
+  fn fill(&mut self) {
+    while self.queue.len() < PREVIEW_LEN {
+      let mut bag = Tetromino::ALL;
+      bag.shuffle(&mut rand::rng());
+      self.queue.extend(bag);
+    }
+  }
+}
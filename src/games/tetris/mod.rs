@@ -0,0 +1,484 @@
+use std::time::Duration;
+
+use bevy::{input::InputSystems, prelude::*};
+
+use crate::{
+  game::FontAssets,
+  games::{
+    CurrentGameState, GameExitMessage, GameResultMessage, GameType,
+    input_repeat::KeyRepeat,
+    menu::{GameMenuBuilder, GameMenuPlugin, GameMenuSelectedEvent},
+    seconds_to_ticks,
+  },
+  loading::{AssetCollection, AssetCollectionAppExt},
+  state::{ContextScope, GameState},
+};
+
+use bag::PieceBag;
+use piece::{Piece, Spin, Tetromino};
+use playfield::{PLAYFIELD_WIDTH, Playfield};
+use render::{TetrisRenderPlugin, view_size};
+
+mod bag;
+mod piece;
+mod playfield;
+mod render;
+
+/// Every entity of a tetris session is despawned when the player leaves it.
+const TETRIS_SCOPE: ContextScope = ContextScope::Minigame(GameType::Tetris);
+
+const BACKGROUND_COLOR: Color = Color::srgb(0.08, 0.08, 0.12);
+
+/// Delayed auto shift and auto repeat rate of Left and Right.
+const SHIFT_REPEAT: KeyRepeat =
+  KeyRepeat::new(Duration::from_millis(170), Duration::from_millis(50));
+/// Soft drop falls this many times faster than gravity, but at least [`MIN_SOFT_DROP_GRAVITY`].
+const SOFT_DROP_FACTOR: f32 = 20.0;
+const MIN_SOFT_DROP_GRAVITY: f32 = 0.25;
+/// Time a piece rests on the stack before it locks.
+const LOCK_DELAY_SECONDS: f32 = 0.5;
+/// Moving or rotating a resting piece restarts the lock delay at most this many times.
+const MAX_LOCK_RESETS: u32 = 15;
+const LINES_PER_LEVEL: u32 = 10;
+/// Beyond this level gravity stops getting faster.
+const MAX_GRAVITY_LEVEL: u32 = 20;
+
+pub struct TetrisGamePlugin;
+
+impl Plugin for TetrisGamePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<TetrisGameState>();
+
+    app.add_plugins((
+      TetrisRenderPlugin,
+      GameMenuPlugin::<TetrisMenuAction>::default(),
+    ));
+
+    app.init_asset_collection::<TetrisGameAssets>();
+
+    app.init_resource::<TetrisRun>();
+    app.init_resource::<TetrisInput>();
+
+    app
+      .add_systems(Update, setup.run_if(switched_to_game))
+      .add_systems(
+        PreUpdate,
+        tetris_input_system
+          .after(InputSystems)
+          .run_if(in_state(TetrisGameState::Playing).and(not(in_state(GameState::Paused)))),
+      )
+      .add_systems(FixedUpdate, tetris_step_system.run_if(tetris_running))
+      .add_systems(OnEnter(TetrisGameState::Playing), start_run_system)
+      .add_systems(
+        OnEnter(TetrisGameState::GameOver),
+        (submit_score_system, game_over_enter_system),
+      )
+      .add_systems(OnEnter(TetrisGameState::NotStarted), reset_run_system)
+      .add_observer(tetris_menu_observer);
+  }
+}
+
+#[derive(States, Debug, Clone, Hash, Eq, PartialEq, Default)]
+enum TetrisGameState {
+  #[default]
+  NotStarted,
+  Playing,
+  GameOver,
+}
+
+/// Choices of the game over menu.
+#[derive(Clone, Copy)]
+enum TetrisMenuAction {
+  PlayAgain,
+  BackToLobby,
+}
+
+#[derive(Resource)]
+struct TetrisGameAssets {
+  blocks: Handle<Image>,
+}
+
+impl FromWorld for TetrisGameAssets {
+  fn from_world(world: &mut World) -> Self {
+    let asset_server = world.resource::<AssetServer>();
+
+    Self {
+      blocks: asset_server.load("games/tetris/blocks.png"),
+    }
+  }
+}
+
+impl AssetCollection for TetrisGameAssets {
+  fn handles(&self) -> Vec<UntypedHandle> {
+    vec![self.blocks.clone().untyped()]
+  }
+}
+
+#[derive(Component)]
+struct TetrisCamera;
+
+/// One game from the first piece to the game over.
+#[derive(Resource, Default)]
+struct TetrisRun {
+  playfield: Playfield,
+  bag: PieceBag,
+  piece: Option<Piece>,
+  hold: Option<Tetromino>,
+  /// Hold can be used once per piece.
+  hold_used: bool,
+  /// Part of a row the piece has fallen since it last moved down.
+  fall_progress: f32,
+  /// Fixed ticks the piece has been resting on the stack.
+  lock_ticks: u32,
+  lock_resets: u32,
+  score: u32,
+  lines: u32,
+}
+
+impl TetrisRun {
+  fn level(&self) -> u32 {
+    self.lines / LINES_PER_LEVEL + 1
+  }
+
+  /// Rows fallen per fixed tick. Every level the pieces fall faster, as in the guideline:
+  /// `(0.8 - (level - 1) * 0.007) ^ (level - 1)` seconds per row.
+  fn gravity(&self) -> f32 {
+    let level = (self.level().min(MAX_GRAVITY_LEVEL) - 1) as f32;
+    let seconds_per_row = (0.8 - level * 0.007).powf(level);
+
+    1.0 / seconds_to_ticks(seconds_per_row) as f32
+  }
+
+  /// Deals the next piece, or `kind` when it comes from the hold. Returns `false` if it does
+  /// not fit, which ends the game.
+  fn spawn_piece(&mut self, kind: Option<Tetromino>) -> bool {
+    let kind = kind.unwrap_or_else(|| self.bag.next());
+    let piece = Piece::new(kind, Playfield::spawn_position(kind));
+
+    self.piece = Some(piece);
+    self.fall_progress = 0.0;
+    self.lock_ticks = 0;
+    self.lock_resets = 0;
+
+    self.playfield.fits(&piece)
+  }
+
+  fn is_grounded(&self, piece: &Piece) -> bool {
+    !self
+      .playfield
+      .fits(&piece.moved(IVec2::NEG_Y))
+  }
+
+  /// Moves the piece if `piece` fits. A resting piece that moves gets its lock delay back.
+  fn try_place(&mut self, piece: Piece) -> bool {
+    if !self.playfield.fits(&piece) {
+      return false;
+    }
+
+    self.piece = Some(piece);
+
+    if self.lock_ticks > 0 && self.lock_resets < MAX_LOCK_RESETS {
+      self.lock_ticks = 0;
+      self.lock_resets += 1;
+    }
+
+    true
+  }
+
+  fn shift(&mut self, columns: i32) {
+    for _ in 0..columns.unsigned_abs() {
+      let Some(piece) = self.piece else {
+        return;
+      };
+
+      if !self.try_place(piece.moved(IVec2::X * columns.signum())) {
+        return;
+      }
+    }
+  }
+
+  fn rotate(&mut self, spin: Spin) {
+    let Some(piece) = self.piece else {
+      return;
+    };
+
+    for candidate in piece.rotations(spin) {
+      if self.try_place(candidate) {
+        return;
+      }
+    }
+  }
+
+  /// Swaps the piece with the held one. Returns `false` if the swapped in piece does not fit.
+  fn hold(&mut self) -> bool {
+    let Some(piece) = self.piece else {
+      return true;
+    };
+
+    if self.hold_used {
+      return true;
+    }
+
+    let held = self.hold.replace(piece.kind);
+    self.hold_used = true;
+
+    self.spawn_piece(held)
+  }
+
+  /// Drops the piece to the stack and locks it at once. Returns `false` on game over.
+  fn hard_drop(&mut self) -> bool {
+    let Some(piece) = self.piece else {
+      return true;
+    };
+
+    let dropped = self.playfield.drop_position(&piece);
+    self.score += 2 * (piece.position.y - dropped.position.y) as u32;
+    self.piece = Some(dropped);
+
+    self.lock()
+  }
+
+  /// Applies one fixed tick of gravity and the lock delay. Returns `false` on game over.
+  fn fall(&mut self, soft_drop: bool) -> bool {
+    let Some(mut piece) = self.piece else {
+      return true;
+    };
+
+    let gravity = self.gravity();
+    self.fall_progress += if soft_drop {
+      (gravity * SOFT_DROP_FACTOR).max(MIN_SOFT_DROP_GRAVITY)
+    } else {
+      gravity
+    };
+
+    while self.fall_progress >= 1.0 && !self.is_grounded(&piece) {
+      self.fall_progress -= 1.0;
+      piece = piece.moved(IVec2::NEG_Y);
+      self.piece = Some(piece);
+
+      if soft_drop {
+        self.score += 1;
+      }
+    }
+
+    if !self.is_grounded(&piece) {
+      self.lock_ticks = 0;
+      return true;
+    }
+
+    // На стопке падать некуда, копить падение незачем
+    self.fall_progress = 0.0;
+    self.lock_ticks += 1;
+
+    if self.lock_ticks < seconds_to_ticks(LOCK_DELAY_SECONDS) {
+      return true;
+    }
+
+    self.lock()
+  }
+
+  /// Locks the piece, clears the full lines and deals the next piece. Returns `false` on
+  /// game over.
+  fn lock(&mut self) -> bool {
+    let Some(piece) = self.piece.take() else {
+      return true;
+    };
+
+    if !self.playfield.lock(&piece) {
+      return false;
+    }
+
+    let lines = self.playfield.clear_lines();
+    self.score += line_clear_points(lines) * self.level();
+    self.lines += lines;
+    self.hold_used = false;
+
+    self.spawn_piece(None)
+  }
+}
+
+/// Points for clearing `lines` at once, before the level multiplier.
+fn line_clear_points(lines: u32) -> u32 {
+  match lines {
+    0 => 0,
+    1 => 100,
+    2 => 300,
+    3 => 500,
+    _ => 800,
+  }
+}
+
+/// Input gathered since the last fixed tick.
+#[derive(Resource)]
+struct TetrisInput {
+  left: KeyRepeat,
+  right: KeyRepeat,
+  shift: i32,
+  spins: Vec<Spin>,
+  hold: bool,
+  hard_drop: bool,
+  soft_drop: bool,
+}
+
+impl Default for TetrisInput {
+  fn default() -> Self {
+    Self {
+      left: SHIFT_REPEAT,
+      right: SHIFT_REPEAT,
+      shift: 0,
+      spins: Vec::new(),
+      hold: false,
+      hard_drop: false,
+      soft_drop: false,
+    }
+  }
+}
+
+fn switched_to_game(config: Res<CurrentGameState>) -> bool {
+  config.is_changed() && config.current_game == Some(GameType::Tetris)
+}
+
+/// A pending state change stops the fixed ticks too, as in the snake.
+fn tetris_running(
+  state: Res<State<TetrisGameState>>,
+  next_state: Res<NextState<TetrisGameState>>,
+) -> bool {
+  *state.get() == TetrisGameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+fn setup(mut commands: Commands, mut next_state: ResMut<NextState<TetrisGameState>>) {
+  let view_size = view_size();
+
+  let mut projection = OrthographicProjection::default_2d();
+  projection.scaling_mode = bevy::camera::ScalingMode::AutoMin {
+    min_width: view_size.x,
+    min_height: view_size.y,
+  };
+
+  commands.spawn((
+    Name::new("TetrisCamera"),
+    TetrisCamera,
+    DespawnOnExit(TETRIS_SCOPE),
+    Camera2d,
+    Camera {
+      order: 1,
+      clear_color: ClearColorConfig::Custom(BACKGROUND_COLOR),
+      ..Default::default()
+    },
+    Projection::Orthographic(projection),
+  ));
+
+  next_state.set(TetrisGameState::Playing);
+}
+
+fn start_run_system(mut run: ResMut<TetrisRun>, mut input: ResMut<TetrisInput>) {
+  *run = TetrisRun::default();
+  *input = TetrisInput::default();
+
+  run.spawn_piece(None);
+}
+
+fn reset_run_system(mut run: ResMut<TetrisRun>) {
+  *run = TetrisRun::default();
+}
+
+fn tetris_input_system(
+  mut input: ResMut<TetrisInput>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  time: Res<Time>,
+) {
+  let left = input
+    .left
+    .update(keyboard_input.pressed(KeyCode::ArrowLeft), time.delta());
+  let right = input
+    .right
+    .update(keyboard_input.pressed(KeyCode::ArrowRight), time.delta());
+  // Больше ширины поля сдвигать бессмысленно, а u32::MAX не влезает в i32
+  let clamp = |count: u32| count.min(PLAYFIELD_WIDTH) as i32;
+  input.shift += clamp(right) - clamp(left);
+
+  if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyX]) {
+    input.spins.push(Spin::Clockwise);
+  }
+  if keyboard_input.any_just_pressed([KeyCode::KeyZ, KeyCode::ControlLeft]) {
+    input.spins.push(Spin::CounterClockwise);
+  }
+
+  input.hold |= keyboard_input.any_just_pressed([KeyCode::KeyC, KeyCode::ShiftLeft]);
+  input.hard_drop |= keyboard_input.just_pressed(KeyCode::Space);
+  input.soft_drop = keyboard_input.pressed(KeyCode::ArrowDown);
+}
+
+fn tetris_step_system(
+  mut run: ResMut<TetrisRun>,
+  mut input: ResMut<TetrisInput>,
+  mut next_state: ResMut<NextState<TetrisGameState>>,
+) {
+  let mut alive = true;
+
+  if std::mem::take(&mut input.hold) {
+    alive &= run.hold();
+  }
+
+  for spin in std::mem::take(&mut input.spins) {
+    run.rotate(spin);
+  }
+
+  run.shift(std::mem::take(&mut input.shift));
+
+  alive &= if std::mem::take(&mut input.hard_drop) {
+    run.hard_drop()
+  } else {
+    run.fall(input.soft_drop)
+  };
+
+  if !alive {
+    next_state.set(TetrisGameState::GameOver);
+  }
+}
+
+fn submit_score_system(
+  mut game_result_messages: MessageWriter<GameResultMessage>,
+  run: Res<TetrisRun>,
+) {
+  game_result_messages.write(GameResultMessage {
+    game: GameType::Tetris,
+    score: run.score,
+  });
+}
+
+fn game_over_enter_system(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  run: Res<TetrisRun>,
+) {
+  GameMenuBuilder::new()
+    .title("Game over")
+    .line(format!(
+      "Score {}  lines {}  level {}",
+      run.score,
+      run.lines,
+      run.level()
+    ))
+    .option("Play again", TetrisMenuAction::PlayAgain)
+    .option("Back to lobby", TetrisMenuAction::BackToLobby)
+    .spawn(
+      &mut commands,
+      &font_assets,
+      DespawnOnExit(TetrisGameState::GameOver),
+    );
+}
+
+fn tetris_menu_observer(
+  event: On<GameMenuSelectedEvent<TetrisMenuAction>>,
+  mut next_state: ResMut<NextState<TetrisGameState>>,
+  mut game_exit_messages: MessageWriter<GameExitMessage>,
+) {
+  match event.action {
+    TetrisMenuAction::PlayAgain => next_state.set(TetrisGameState::Playing),
+    TetrisMenuAction::BackToLobby => {
+      next_state.set(TetrisGameState::NotStarted);
+      game_exit_messages.write(GameExitMessage);
+    }
+  }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Tetromino {
+  I,
+  O,
+  T,
+  S,
+  Z,
+  J,
+  L,
+}
+
+impl Tetromino {
+  pub const ALL: [Tetromino; 7] = [
+    Tetromino::I,
+    Tetromino::O,
+    Tetromino::T,
+    Tetromino::S,
+    Tetromino::Z,
+    Tetromino::J,
+    Tetromino::L,
+  ];
+
+  pub fn color(self) -> Color {
+    match self {
+      Tetromino::I => Color::srgb(0.3, 0.85, 0.95),
+      Tetromino::O => Color::srgb(0.95, 0.85, 0.25),
+      Tetromino::T => Color::srgb(0.7, 0.35, 0.9),
+      Tetromino::S => Color::srgb(0.4, 0.85, 0.35),
+      Tetromino::Z => Color::srgb(0.95, 0.3, 0.3),
+      Tetromino::J => Color::srgb(0.3, 0.45, 0.95),
+      Tetromino::L => Color::srgb(0.95, 0.6, 0.2),
+    }
+  }
+
+  /// Side of the square box the piece rotates in.
+  fn box_size(self) -> i32 {
+    match self {
+      Tetromino::I | Tetromino::O => 4,
+      _ => 3,
+    }
+  }
+
+  /// Cells in the spawn orientation, inside the rotation box, y up.
+  fn spawn_cells(self) -> [IVec2; 4] {
+    let cells = match self {
+      Tetromino::I => [(0, 2), (1, 2), (2, 2), (3, 2)],
+      Tetromino::O => [(1, 1), (2, 1), (1, 2), (2, 2)],
+      Tetromino::T => [(1, 2), (0, 1), (1, 1), (2, 1)],
+      Tetromino::S => [(1, 2), (2, 2), (0, 1), (1, 1)],
+      Tetromino::Z => [(0, 2), (1, 2), (1, 1), (2, 1)],
+      Tetromino::J => [(0, 2), (0, 1), (1, 1), (2, 1)],
+      Tetromino::L => [(2, 2), (0, 1), (1, 1), (2, 1)],
+    };
+
+    cells.map(|(x, y)| IVec2::new(x, y))
+  }
+
+  /// SRS offsets of every rotation state. A kick is the difference between the offsets of
+  /// the two states, see [`Piece::rotations`].
+  fn offsets(self) -> &'static [[(i32, i32); 5]; 4] {
+    const JLSTZ: [[(i32, i32); 5]; 4] = [
+      [(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
+      [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+      [(0, 0), (0, 0), (0, 0), (0, 0), (0, 0)],
+      [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    ];
+    const I: [[(i32, i32); 5]; 4] = [
+      [(0, 0), (-1, 0), (2, 0), (-1, 0), (2, 0)],
+      [(-1, 0), (0, 0), (0, 0), (0, 1), (0, -2)],
+      [(-1, 1), (1, 1), (-2, 1), (1, 0), (-2, 0)],
+      [(0, 1), (0, 1), (0, 1), (0, -1), (0, 2)],
+    ];
+    const O: [[(i32, i32); 5]; 4] = [[(0, 0); 5]; 4];
+
+    match self {
+      Tetromino::I => &I,
+      Tetromino::O => &O,
+      _ => &JLSTZ,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Spin {
+  Clockwise,
+  CounterClockwise,
+}
+
+/// Piece in the playfield. `position` is the bottom-left corner of its rotation box and
+/// `rotation` is the SRS state: 0 at spawn, then R, 2 and L clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Piece {
+  pub kind: Tetromino,
+  pub rotation: u8,
+  pub position: IVec2,
+}
+
+impl Piece {
+  pub fn new(kind: Tetromino, position: IVec2) -> Self {
+    Self {
+      kind,
+      rotation: 0,
+      position,
+    }
+  }
+
+  /// Cells inside the rotation box, y up.
+  pub fn local_cells(&self) -> [IVec2; 4] {
+    let size = self.kind.box_size();
+
+    self.kind.spawn_cells().map(|mut cell| {
+      for _ in 0..self.rotation {
+        cell = IVec2::new(cell.y, size - 1 - cell.x);
+      }
+      cell
+    })
+  }
+
+  pub fn cells(&self) -> [IVec2; 4] {
+    self
+      .local_cells()
+      .map(|cell| self.position + cell)
+  }
+
+  pub fn moved(&self, offset: IVec2) -> Self {
+    Self {
+      position: self.position + offset,
+      ..*self
+    }
+  }
+
+  /// Candidates for rotating the piece, in the order SRS tests them: the plain rotation first,
+  /// then the wall kicks.
+  pub fn rotations(&self, spin: Spin) -> impl Iterator<Item = Piece> {
+    let from = self.rotation;
+    let to = match spin {
+      Spin::Clockwise => (from + 1) % 4,
+      Spin::CounterClockwise => (from + 3) % 4,
+    };
+    let offsets = self.kind.offsets();
+    let kick = move |test: usize| {
+      IVec2::from(offsets[from as usize][test]) - IVec2::from(offsets[to as usize][test])
+    };
+    // Первый тест смещений поправляет центр вращения, а он уже учтён поворотом в рамке
+    let center = kick(0);
+    let rotated = Self {
+      rotation: to,
+      ..*self
+    };
+
+    (0..5).map(move |test| rotated.moved(kick(test) - center))
+  }
+}
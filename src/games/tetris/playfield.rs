@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use super::piece::{Piece, Tetromino};
+
+pub(super) const PLAYFIELD_WIDTH: u32 = 10;
+pub(super) const VISIBLE_HEIGHT: u32 = 20;
+/// Rows above the visible field, where the pieces spawn.
+const HIDDEN_ROWS: u32 = 2;
+const PLAYFIELD_HEIGHT: u32 = VISIBLE_HEIGHT + HIDDEN_ROWS;
+
+/// Locked blocks of the well, row 0 at the bottom.
+pub(super) struct Playfield {
+  rows: Vec<[Option<Tetromino>; PLAYFIELD_WIDTH as usize]>,
+}
+
+impl Default for Playfield {
+  fn default() -> Self {
+    Self {
+      rows: vec![[None; PLAYFIELD_WIDTH as usize]; PLAYFIELD_HEIGHT as usize],
+    }
+  }
+}
+
+impl Playfield {
+  /// Bottom-left corner of the rotation box of a new piece, so that it appears just above
+  /// the visible field.
+  pub fn spawn_position(kind: Tetromino) -> IVec2 {
+    let box_bottom = match kind {
+      Tetromino::I => VISIBLE_HEIGHT as i32 - 2,
+      _ => VISIBLE_HEIGHT as i32 - 1,
+    };
+
+    IVec2::new(3, box_bottom)
+  }
+
+  pub fn block(&self, cell: UVec2) -> Option<Tetromino> {
+    self
+      .rows
+      .get(cell.y as usize)
+      .and_then(|row| {
+        row
+          .get(cell.x as usize)
+          .copied()
+          .flatten()
+      })
+  }
+
+  pub fn is_free(&self, cell: IVec2) -> bool {
+    if cell.x < 0 || cell.y < 0 || cell.x >= PLAYFIELD_WIDTH as i32 {
+      return false;
+    }
+
+    // Над скрытыми строками поле открыто
+    cell.y >= PLAYFIELD_HEIGHT as i32 || self.block(cell.as_uvec2()).is_none()
+  }
+
+  pub fn fits(&self, piece: &Piece) -> bool {
+    piece
+      .cells()
+      .into_iter()
+      .all(|cell| self.is_free(cell))
+  }
+
+  /// The piece dropped as far down as it goes.
+  pub fn drop_position(&self, piece: &Piece) -> Piece {
+    let mut dropped = *piece;
+    while self.fits(&dropped.moved(IVec2::NEG_Y)) {
+      dropped = dropped.moved(IVec2::NEG_Y);
+    }
+
+    dropped
+  }
+
+  /// Writes the piece into the field. Returns `false` if it is entirely above the visible
+  /// field, which ends the game.
+  pub fn lock(&mut self, piece: &Piece) -> bool {
+    let mut visible = false;
+
+    for cell in piece.cells() {
+      visible |= cell.y < VISIBLE_HEIGHT as i32;
+
+      if let Some(row) = self.rows.get_mut(cell.y as usize) {
+        row[cell.x as usize] = Some(piece.kind);
+      }
+    }
+
+    visible
+  }
+
+  /// Removes the full rows and returns how many there were.
+  pub fn clear_lines(&mut self) -> u32 {
+    let before = self.rows.len();
+    self
+      .rows
+      .retain(|row| row.iter().any(Option::is_none));
+    let cleared = before - self.rows.len();
+
+    self
+      .rows
+      .resize(before, [None; PLAYFIELD_WIDTH as usize]);
+
+    cleared as u32
+  }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::{
+  game::FontAssets,
+  games::{
+    GameType,
+    grid::{GridLayer, spawn_grid_layer},
+  },
+  profile::Profile,
+};
+
+use super::{
+  TETRIS_SCOPE, TetrisGameAssets, TetrisGameState, TetrisRun,
+  bag::PREVIEW_LEN,
+  piece::{Piece, Tetromino},
+  playfield::{PLAYFIELD_WIDTH, VISIBLE_HEIGHT},
+};
+
+/// Side of one cell in world units.
+const TILE_SIZE: f32 = 8.0;
+/// Empty space around the well and the side panels, in tiles.
+const MARGIN_TILES: f32 = 1.0;
+/// Side panels hold a piece preview of this size, in tiles.
+const PREVIEW_SIZE: UVec2 = UVec2::new(4, 2);
+/// Rows between two pieces of the next queue.
+const PREVIEW_GAP: u32 = 1;
+/// The previews of held pieces are dimmed until hold can be used again.
+const USED_HOLD_ALPHA: f32 = 0.4;
+
+/// Tiles of `blocks.png`.
+mod block_tile {
+  pub const FLOOR: u32 = 0;
+  pub const WALL: u32 = 1;
+  /// White block, tinted with the piece color.
+  pub const BLOCK: u32 = 2;
+  /// Outline where the piece would land.
+  pub const GHOST: u32 = 3;
+}
+
+pub(super) struct TetrisRenderPlugin;
+
+impl Plugin for TetrisRenderPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(OnExit(TetrisGameState::NotStarted), spawn_layers)
+      .add_systems(
+        Update,
+        (
+          draw_playfield_system,
+          draw_hold_system,
+          draw_next_system,
+          update_run_info_system,
+        )
+          .run_if(resource_changed::<TetrisRun>),
+      );
+  }
+}
+
+#[derive(Component)]
+struct PlayfieldLayer;
+
+#[derive(Component)]
+struct HoldLayer;
+
+#[derive(Component)]
+struct NextLayer;
+
+#[derive(Component)]
+struct RunInfoText;
+
+fn next_layer_size() -> UVec2 {
+  UVec2::new(
+    PREVIEW_SIZE.x,
+    PREVIEW_LEN as u32 * (PREVIEW_SIZE.y + PREVIEW_GAP) - PREVIEW_GAP,
+  )
+}
+
+/// Horizontal distance from the center of the well to the center of a side panel, in tiles.
+fn panel_offset() -> f32 {
+  // Половина колодца, стенка, отступ и половина панели
+  PLAYFIELD_WIDTH as f32 / 2.0 + 1.0 + MARGIN_TILES + PREVIEW_SIZE.x as f32 / 2.0
+}
+
+/// Size of the world area the camera has to show: the well, the side panels and a margin.
+pub(super) fn view_size() -> Vec2 {
+  Vec2::new(
+    (panel_offset() + PREVIEW_SIZE.x as f32 / 2.0 + MARGIN_TILES) * 2.0,
+    VISIBLE_HEIGHT as f32 + 1.0 + MARGIN_TILES * 2.0,
+  ) * TILE_SIZE
+}
+
+fn tile(index: u32, color: Color) -> (TileTextureIndex, TileColor, TileVisible) {
+  (TileTextureIndex(index), TileColor(color), TileVisible(true))
+}
+
+fn hidden(_: TilePos) -> (TileTextureIndex, TileColor, TileVisible) {
+  (
+    TileTextureIndex(0),
+    TileColor::default(),
+    TileVisible(false),
+  )
+}
+
+fn spawn_layers(
+  mut commands: Commands,
+  tetris_game_assets: Res<TetrisGameAssets>,
+  font_assets: Res<FontAssets>,
+) {
+  let top = VISIBLE_HEIGHT as f32 / 2.0;
+  let texture = || tetris_game_assets.blocks.clone();
+  let layer = |name, size, center: Vec2, z| GridLayer {
+    name,
+    size,
+    texture: texture(),
+    tile_size: TILE_SIZE,
+    translation: (center * TILE_SIZE).extend(z),
+    scope: TETRIS_SCOPE,
+  };
+
+  // Колодец: пол под видимыми строками и стенки по бокам и снизу
+  let well_size = UVec2::new(PLAYFIELD_WIDTH + 2, VISIBLE_HEIGHT + 1);
+  spawn_grid_layer(
+    &mut commands,
+    layer("TetrisWell", well_size, Vec2::new(0.0, -0.5), -1.0),
+    |position| {
+      let is_wall = position.x == 0 || position.x == well_size.x - 1 || position.y == 0;
+      let index = if is_wall {
+        block_tile::WALL
+      } else {
+        block_tile::FLOOR
+      };
+      tile(index, Color::WHITE)
+    },
+  );
+
+  let playfield = spawn_grid_layer(
+    &mut commands,
+    layer(
+      "TetrisPlayfield",
+      UVec2::new(PLAYFIELD_WIDTH, VISIBLE_HEIGHT),
+      Vec2::ZERO,
+      0.0,
+    ),
+    hidden,
+  );
+  commands
+    .entity(playfield)
+    .insert(PlayfieldLayer);
+
+  let hold_center = Vec2::new(-panel_offset(), top - PREVIEW_SIZE.y as f32 / 2.0);
+  let next_center = Vec2::new(panel_offset(), top - next_layer_size().y as f32 / 2.0);
+  let floor = |_| tile(block_tile::FLOOR, Color::WHITE);
+
+  spawn_grid_layer(
+    &mut commands,
+    layer("TetrisHoldPanel", PREVIEW_SIZE, hold_center, -1.0),
+    floor,
+  );
+  let hold = spawn_grid_layer(
+    &mut commands,
+    layer("TetrisHold", PREVIEW_SIZE, hold_center, 0.0),
+    hidden,
+  );
+  commands.entity(hold).insert(HoldLayer);
+
+  spawn_grid_layer(
+    &mut commands,
+    layer("TetrisNextPanel", next_layer_size(), next_center, -1.0),
+    floor,
+  );
+  let next = spawn_grid_layer(
+    &mut commands,
+    layer("TetrisNext", next_layer_size(), next_center, 0.0),
+    hidden,
+  );
+  commands.entity(next).insert(NextLayer);
+
+  commands.spawn((
+    Name::new("TetrisRunInfo"),
+    RunInfoText,
+    DespawnOnExit(TETRIS_SCOPE),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(12.),
+      left: px(12.),
+      ..default()
+    },
+    Text::default(),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: 24.,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+  ));
+}
+
+fn paint(
+  tile_query: &mut Query<(&mut TileTextureIndex, &mut TileColor, &mut TileVisible)>,
+  storage: &TileStorage,
+  position: UVec2,
+  block: Option<(u32, Color)>,
+) {
+  let Some(tile_entity) = storage.get(&TilePos {
+    x: position.x,
+    y: position.y,
+  }) else {
+    return;
+  };
+  let Ok((mut texture_index, mut color, mut visible)) = tile_query.get_mut(tile_entity) else {
+    return;
+  };
+
+  visible.0 = block.is_some();
+  if let Some((index, block_color)) = block {
+    texture_index.0 = index;
+    color.0 = block_color;
+  }
+}
+
+/// Cells of a piece preview in its spawn orientation, inside a [`PREVIEW_SIZE`] box.
+fn preview_cells(kind: Tetromino) -> impl Iterator<Item = UVec2> {
+  // В начальном положении фигуры занимают две верхние строки рамки вращения
+  let bottom = match kind {
+    Tetromino::I => 2,
+    _ => 1,
+  };
+
+  Piece::new(kind, IVec2::new(0, -bottom))
+    .cells()
+    .into_iter()
+    .map(|cell| cell.as_uvec2())
+}
+
+fn draw_playfield_system(
+  mut tile_query: Query<(&mut TileTextureIndex, &mut TileColor, &mut TileVisible)>,
+  playfield_single: Single<&TileStorage, With<PlayfieldLayer>>,
+  run: Res<TetrisRun>,
+) {
+  let piece_cells = run.piece.map(|piece| piece.cells());
+  let ghost_cells = run.piece.map(|piece| {
+    run
+      .playfield
+      .drop_position(&piece)
+      .cells()
+  });
+
+  for x in 0..PLAYFIELD_WIDTH {
+    for y in 0..VISIBLE_HEIGHT {
+      let cell = UVec2::new(x, y);
+      let contains =
+        |cells: Option<[IVec2; 4]>| cells.is_some_and(|cells| cells.contains(&cell.as_ivec2()));
+
+      let block = match (run.playfield.block(cell), run.piece) {
+        (Some(kind), _) => Some((block_tile::BLOCK, kind.color())),
+        (None, Some(piece)) if contains(piece_cells) => {
+          Some((block_tile::BLOCK, piece.kind.color()))
+        }
+        (None, Some(piece)) if contains(ghost_cells) => {
+          Some((block_tile::GHOST, piece.kind.color()))
+        }
+        _ => None,
+      };
+
+      paint(&mut tile_query, &playfield_single, cell, block);
+    }
+  }
+}
+
+fn draw_preview(
+  tile_query: &mut Query<(&mut TileTextureIndex, &mut TileColor, &mut TileVisible)>,
+  storage: &TileStorage,
+  bottom: u32,
+  kind: Option<Tetromino>,
+  color: impl Fn(Tetromino) -> Color,
+) {
+  for x in 0..PREVIEW_SIZE.x {
+    for y in 0..PREVIEW_SIZE.y {
+      paint(tile_query, storage, UVec2::new(x, bottom + y), None);
+    }
+  }
+
+  let Some(kind) = kind else {
+    return;
+  };
+
+  for cell in preview_cells(kind) {
+    paint(
+      tile_query,
+      storage,
+      UVec2::new(cell.x, bottom + cell.y),
+      Some((block_tile::BLOCK, color(kind))),
+    );
+  }
+}
+
+fn draw_hold_system(
+  mut tile_query: Query<(&mut TileTextureIndex, &mut TileColor, &mut TileVisible)>,
+  hold_single: Single<&TileStorage, With<HoldLayer>>,
+  run: Res<TetrisRun>,
+) {
+  draw_preview(&mut tile_query, &hold_single, 0, run.hold, |kind| {
+    if run.hold_used {
+      kind.color().with_alpha(USED_HOLD_ALPHA)
+    } else {
+      kind.color()
+    }
+  });
+}
+
+/// The first piece of the queue is drawn at the top.
+fn draw_next_system(
+  mut tile_query: Query<(&mut TileTextureIndex, &mut TileColor, &mut TileVisible)>,
+  next_single: Single<&TileStorage, With<NextLayer>>,
+  run: Res<TetrisRun>,
+) {
+  let mut preview = run.bag.preview();
+
+  for slot in 0..PREVIEW_LEN as u32 {
+    let bottom = (PREVIEW_LEN as u32 - 1 - slot) * (PREVIEW_SIZE.y + PREVIEW_GAP);
+    draw_preview(
+      &mut tile_query,
+      &next_single,
+      bottom,
+      preview.next(),
+      Tetromino::color,
+    );
+  }
+}
+
+fn update_run_info_system(
+  mut run_info_query: Query<&mut Text, With<RunInfoText>>,
+  run: Res<TetrisRun>,
+  profile: Res<Profile>,
+) {
+  for mut text in &mut run_info_query {
+    text.0 = format!(
+      "Score {}\nLines {}\nLevel {}\nBest {}",
+      run.score,
+      run.lines,
+      run.level(),
+      profile
+        .best_score(&GameType::Tetris.to_string())
+        .max(run.score)
+    );
+  }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::{game::FontAssets, profile::Profile, state::ContextScope};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+  fn build(&self, app: &mut App) {
+    // Счётчик есть только в лобби, в мини-играх его нет
+    app.add_systems(OnEnter(ContextScope::Lobby), spawn_token_counter);
+
+    app.add_systems(
+      Update,
+      update_token_counter_system.run_if(resource_changed::<Profile>),
+    );
+  }
+}
+
+#[derive(Component)]
+struct TokenCounterText;
+
+fn token_counter_text(profile: &Profile) -> String {
+  format!("Tokens: {}", profile.tokens)
+}
+
+fn spawn_token_counter(
+  mut commands: Commands,
+  font_assets: Res<FontAssets>,
+  profile: Res<Profile>,
+) {
+  commands.spawn((
+    Name::new("TokenCounter"),
+    DespawnOnExit(ContextScope::Lobby),
+    Node {
+      position_type: PositionType::Absolute,
+      top: px(16.),
+      right: px(16.),
+      padding: UiRect::axes(px(12.), px(6.)),
+      ..default()
+    },
+    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+    children![(
+      TokenCounterText,
+      Text::new(token_counter_text(&profile)),
+      TextFont {
+        font: font_assets.regular.clone(),
+        font_size: 28.,
+        ..Default::default()
+      },
+      TextColor(Color::srgb(1.0, 0.85, 0.3)),
+    )],
+  ));
+}
+
+fn update_token_counter_system(
+  profile: Res<Profile>,
+  mut text_query: Query<&mut Text, With<TokenCounterText>>,
+) {
+  for mut text in &mut text_query {
+    text.0 = token_counter_text(&profile);
+  }
+}
@@ -1,6 +1,18 @@
+pub mod achievements;
+pub mod animation;
+pub mod audio;
+pub mod bfxr;
+pub mod camera;
 pub mod components;
+pub mod dialogue;
 pub mod game;
 pub mod games;
+pub mod hud;
+pub mod loading;
+pub mod npc;
 pub mod player;
+pub mod profile;
+pub mod scope;
+pub mod settings;
 pub mod state;
 pub mod tilemap;
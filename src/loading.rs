@@ -0,0 +1,245 @@
+use std::any::type_name;
+
+use bevy::{asset::RecursiveDependencyLoadState, prelude::*};
+
+use crate::state::{AppState, ContextScope};
+
+/// Waits in [`AppState::Loading`] until every registered [`AssetCollection`] is loaded,
+/// then switches to [`AppState::Lobby`].
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<AppState>();
+    app.init_resource::<AssetCollections>();
+
+    app.add_systems(
+      OnEnter(AppState::Loading),
+      (collect_pending_assets, spawn_loading_ui),
+    );
+    app.add_systems(
+      Update,
+      (
+        track_loading_progress_system,
+        continue_despite_errors_system,
+      )
+        .chain()
+        .run_if(in_state(AppState::Loading)),
+    );
+    app.add_systems(OnExit(AppState::Loading), despawn_loading_ui);
+  }
+}
+
+/// Resource holding handles that must be loaded before the game leaves the loading screen.
+/// Handles are created in [`FromWorld`], so the loads start as soon as the collection is registered.
+pub trait AssetCollection: Resource + FromWorld {
+  fn handles(&self) -> Vec<UntypedHandle>;
+}
+
+pub trait AssetCollectionAppExt {
+  fn init_asset_collection<C: AssetCollection>(&mut self) -> &mut Self;
+}
+
+impl AssetCollectionAppExt for App {
+  fn init_asset_collection<C: AssetCollection>(&mut self) -> &mut Self {
+    self.init_resource::<C>();
+    self
+      .world_mut()
+      .get_resource_or_init::<AssetCollections>()
+      .collectors
+      .push(AssetCollector {
+        name: type_name::<C>(),
+        handles: |world| world.resource::<C>().handles(),
+      });
+    self
+  }
+}
+
+struct AssetCollector {
+  name: &'static str,
+  handles: fn(&World) -> Vec<UntypedHandle>,
+}
+
+#[derive(Resource, Default)]
+struct AssetCollections {
+  collectors: Vec<AssetCollector>,
+}
+
+struct PendingAsset {
+  collection: &'static str,
+  handle: UntypedHandle,
+}
+
+#[derive(Resource, Default)]
+struct PendingAssets {
+  assets: Vec<PendingAsset>,
+  failed: usize,
+}
+
+#[derive(Component)]
+struct LoadingUi;
+
+#[derive(Component)]
+struct LoadingProgressBar;
+
+#[derive(Component)]
+struct LoadingStatusText;
+
+#[derive(Component)]
+struct LoadingErrorsText;
+
+fn collect_pending_assets(world: &mut World) {
+  let collections = world.resource::<AssetCollections>();
+
+  let assets = collections
+    .collectors
+    .iter()
+    .flat_map(|collector| {
+      (collector.handles)(world)
+        .into_iter()
+        .map(|handle| PendingAsset {
+          collection: collector.name,
+          handle,
+        })
+    })
+    .collect();
+
+  world.insert_resource(PendingAssets { assets, failed: 0 });
+}
+
+fn spawn_loading_ui(mut commands: Commands) {
+  // Шрифт игры сам ещё грузится, поэтому здесь встроенный шрифт Bevy
+  commands.spawn((
+    Name::new("LoadingCamera"),
+    LoadingUi,
+    DespawnOnExit(ContextScope::None),
+    Camera2d,
+  ));
+
+  commands.spawn((
+    Name::new("LoadingUi"),
+    LoadingUi,
+    Node {
+      width: percent(100),
+      height: percent(100),
+      flex_direction: FlexDirection::Column,
+      align_items: AlignItems::Center,
+      justify_content: JustifyContent::Center,
+      row_gap: px(16.),
+      ..default()
+    },
+    children![
+      (
+        LoadingStatusText,
+        Text::new("Loading..."),
+        TextFont::from_font_size(28.),
+        TextColor(Color::WHITE),
+      ),
+      (
+        Node {
+          width: px(400.),
+          height: px(24.),
+          border: UiRect::all(px(2.)),
+          ..default()
+        },
+        BorderColor::all(Color::WHITE),
+        children![(
+          LoadingProgressBar,
+          Node {
+            width: percent(0),
+            height: percent(100),
+            ..default()
+          },
+          BackgroundColor(Color::srgb(1.0, 0.85, 0.3)),
+        )],
+      ),
+      (
+        LoadingErrorsText,
+        Text::default(),
+        TextFont::from_font_size(18.),
+        TextColor(Color::srgb(1.0, 0.3, 0.3)),
+      ),
+    ],
+  ));
+}
+
+fn track_loading_progress_system(
+  mut pending_assets: ResMut<PendingAssets>,
+  mut next_state: ResMut<NextState<AppState>>,
+  mut progress_bar_query: Query<&mut Node, With<LoadingProgressBar>>,
+  mut status_text_query: Query<&mut Text, (With<LoadingStatusText>, Without<LoadingErrorsText>)>,
+  mut errors_text_query: Query<&mut Text, (With<LoadingErrorsText>, Without<LoadingStatusText>)>,
+  asset_server: Res<AssetServer>,
+) {
+  let total = pending_assets.assets.len();
+  let mut loaded = 0;
+  let mut errors = Vec::new();
+
+  for asset in &pending_assets.assets {
+    match asset_server.get_recursive_dependency_load_state(asset.handle.id()) {
+      Some(RecursiveDependencyLoadState::Loaded) => loaded += 1,
+      Some(RecursiveDependencyLoadState::Failed(error)) => {
+        let path = asset_server
+          .get_path(asset.handle.id())
+          .map(|path| path.to_string())
+          .unwrap_or_else(|| "<unknown>".to_string());
+        errors.push(format!("{} ({}): {}", path, asset.collection, error));
+      }
+      _ => {}
+    }
+  }
+
+  if errors.len() > pending_assets.failed {
+    for error in &errors[pending_assets.failed..] {
+      error!("Failed to load asset {}", error);
+    }
+    pending_assets.failed = errors.len();
+  }
+
+  let progress = if total == 0 {
+    1.0
+  } else {
+    (loaded + errors.len()) as f32 / total as f32
+  };
+
+  for mut node in &mut progress_bar_query {
+    node.width = percent(progress * 100.0);
+  }
+
+  for mut text in &mut status_text_query {
+    text.0 = format!("Loading... {loaded}/{total}");
+  }
+
+  if !errors.is_empty() {
+    for mut text in &mut errors_text_query {
+      text.0 = format!(
+        "Some assets failed to load:\n{}\n\nPress Enter to continue anyway",
+        errors.join("\n")
+      );
+    }
+    return;
+  }
+
+  if loaded == total {
+    info!("Loaded {total} assets");
+    next_state.set(AppState::Lobby);
+  }
+}
+
+fn continue_despite_errors_system(
+  pending_assets: Res<PendingAssets>,
+  mut next_state: ResMut<NextState<AppState>>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+  if pending_assets.failed > 0 && keyboard_input.just_pressed(KeyCode::Enter) {
+    next_state.set(AppState::Lobby);
+  }
+}
+
+fn despawn_loading_ui(mut commands: Commands, loading_ui_query: Query<Entity, With<LoadingUi>>) {
+  for entity in loading_ui_query.iter() {
+    commands.entity(entity).despawn();
+  }
+
+  commands.remove_resource::<PendingAssets>();
+}
@@ -0,0 +1,376 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+  animation::{FacingDirection, MovementState, WalkAnimation, WalkCycleAtlas},
+  dialogue::InDialogue,
+  game::FontAssets,
+};
+
+const NPC_ARRIVE_DISTANCE: f32 = 1.0;
+const SPEECH_BUBBLE_OFFSET: f32 = 32.0;
+const SPEECH_BUBBLE_FONT_SIZE: f32 = 8.0;
+
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+  fn build(&self, app: &mut App) {
+    app.register_type::<Npc>();
+    app.register_type::<NpcWander>();
+    app.register_type::<NpcPatrol>();
+    app.register_type::<NpcWaypoint>();
+    app.register_type::<NpcPlayingCabinet>();
+    app.register_type::<NpcChatter>();
+
+    app.add_observer(on_add_npc);
+    app.add_observer(on_add_npc_playing_cabinet);
+    app.add_observer(on_add_npc_chatter);
+    app.add_observer(show_speech_bubble_observer);
+
+    app.add_systems(
+      Update,
+      ((npc_wander_system, npc_patrol_system), npc_movement_system).chain(),
+    );
+
+    app.add_systems(Update, (npc_chatter_system, speech_bubble_lifetime_system));
+  }
+}
+
+/// Lobby patron placed in Tiled. Behaviour is picked by adding one of
+/// [`NpcWander`], [`NpcPatrol`] or [`NpcPlayingCabinet`] next to it.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct Npc {
+  pub name: String,
+  pub speed: f32,
+  pub sprite_sheet: String,
+}
+
+impl Default for Npc {
+  fn default() -> Self {
+    Self {
+      name: String::from("Patron"),
+      speed: 40.0,
+      sprite_sheet: String::from("player/sheet.png"),
+    }
+  }
+}
+
+/// Walks to random points inside a `width` x `height` rectangle centered on the spawn point.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct NpcWander {
+  pub width: f32,
+  pub height: f32,
+  pub min_pause: f32,
+  pub max_pause: f32,
+}
+
+impl Default for NpcWander {
+  fn default() -> Self {
+    Self {
+      width: 64.0,
+      height: 32.0,
+      min_pause: 1.0,
+      max_pause: 3.0,
+    }
+  }
+}
+
+/// Walks between all [`NpcWaypoint`]s of the same `route`, ordered by [`NpcWaypoint::order`].
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct NpcPatrol {
+  pub route: String,
+  pub pause: f32,
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct NpcWaypoint {
+  pub route: String,
+  pub order: u32,
+}
+
+/// Stands still facing a cabinet.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct NpcPlayingCabinet;
+
+/// Periodically shows one of the `;`-separated `lines` in a speech bubble.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct NpcChatter {
+  pub lines: String,
+  pub interval: f32,
+}
+
+impl Default for NpcChatter {
+  fn default() -> Self {
+    Self {
+      lines: String::new(),
+      interval: 8.0,
+    }
+  }
+}
+
+#[derive(Component, Default)]
+struct NpcBrain {
+  home: Option<Vec2>,
+  target: Option<Vec2>,
+  waypoint_index: usize,
+  pause: Timer,
+}
+
+#[derive(Component, Deref, DerefMut)]
+struct ChatterTimer(Timer);
+
+#[derive(Component)]
+pub struct SpeechBubble {
+  speaker: Entity,
+  timer: Timer,
+}
+
+#[derive(Event)]
+pub struct ShowSpeechBubbleEvent {
+  pub speaker: Entity,
+  pub text: String,
+  pub seconds: f32,
+}
+
+fn on_add_npc(
+  add_npc: On<Add, Npc>,
+  mut commands: Commands,
+  query: Query<&Npc>,
+  walk_cycle_atlas: Res<WalkCycleAtlas>,
+  asset_server: Res<AssetServer>,
+) {
+  let entity = add_npc.event().entity;
+
+  let Ok(npc) = query.get(entity) else {
+    return;
+  };
+
+  info!("New Npc [{:?} {:?}]", npc.name, entity);
+
+  let texture_sheet = asset_server.load(npc.sprite_sheet.clone());
+
+  commands.entity(entity).insert((
+    NpcBrain::default(),
+    WalkAnimation::default(),
+    walk_cycle_atlas.sprite(texture_sheet),
+    Anchor::BOTTOM_CENTER,
+  ));
+}
+
+fn on_add_npc_playing_cabinet(add: On<Add, NpcPlayingCabinet>, mut commands: Commands) {
+  commands
+    .entity(add.event().entity)
+    .insert((FacingDirection::Up, MovementState::Idle));
+}
+
+fn on_add_npc_chatter(add: On<Add, NpcChatter>, mut commands: Commands, query: Query<&NpcChatter>) {
+  let entity = add.event().entity;
+
+  let Ok(chatter) = query.get(entity) else {
+    return;
+  };
+
+  let mut timer = Timer::from_seconds(chatter.interval.max(1.0), TimerMode::Repeating);
+  // Разносим реплики разных NPC по времени
+  timer.set_elapsed(Duration::from_secs_f32(rand::random_range(
+    0.0..timer.duration().as_secs_f32(),
+  )));
+
+  commands
+    .entity(entity)
+    .insert(ChatterTimer(timer));
+}
+
+fn npc_wander_system(mut query: Query<(&mut NpcBrain, &NpcWander, &GlobalTransform)>) {
+  for (mut brain, wander, global_transform) in &mut query {
+    let home = *brain.home.get_or_insert(
+      global_transform
+        .translation()
+        .truncate(),
+    );
+
+    if brain.target.is_some() || !brain.pause.is_finished() {
+      continue;
+    }
+
+    let half_size = Vec2::new(wander.width, wander.height) / 2.0;
+    let offset = Vec2::new(
+      rand::random_range(-half_size.x..=half_size.x),
+      rand::random_range(-half_size.y..=half_size.y),
+    );
+    brain.target = Some(home + offset);
+
+    let pause = rand::random_range(wander.min_pause..=wander.max_pause.max(wander.min_pause));
+    brain.pause = Timer::from_seconds(pause, TimerMode::Once);
+  }
+}
+
+fn npc_patrol_system(
+  mut query: Query<(&mut NpcBrain, &NpcPatrol)>,
+  waypoint_query: Query<(&NpcWaypoint, &GlobalTransform)>,
+) {
+  for (mut brain, patrol) in &mut query {
+    if brain.target.is_some() || !brain.pause.is_finished() {
+      continue;
+    }
+
+    let mut route = waypoint_query
+      .iter()
+      .filter(|(waypoint, _)| waypoint.route == patrol.route)
+      .map(|(waypoint, transform)| (waypoint.order, transform.translation().truncate()))
+      .collect::<Vec<_>>();
+
+    if route.is_empty() {
+      continue;
+    }
+
+    route.sort_by_key(|(order, _)| *order);
+
+    brain.waypoint_index %= route.len();
+    brain.target = Some(route[brain.waypoint_index].1);
+    brain.waypoint_index += 1;
+    brain.pause = Timer::from_seconds(patrol.pause, TimerMode::Once);
+  }
+}
+
+fn npc_movement_system(
+  mut query: Query<
+    (
+      &mut Transform,
+      &GlobalTransform,
+      &mut FacingDirection,
+      &mut MovementState,
+      &mut NpcBrain,
+      &Npc,
+    ),
+    Without<InDialogue>,
+  >,
+  time: Res<Time>,
+) {
+  for (mut transform, global_transform, mut direction, mut state, mut brain, npc) in &mut query {
+    let Some(target) = brain.target else {
+      brain.pause.tick(time.delta());
+      *state = MovementState::Idle;
+      continue;
+    };
+
+    let to_target = target
+      - global_transform
+        .translation()
+        .truncate();
+    let distance = to_target.length();
+
+    if distance <= NPC_ARRIVE_DISTANCE {
+      brain.target = None;
+      *state = MovementState::Idle;
+      continue;
+    }
+
+    *state = MovementState::Walking;
+
+    let move_delta = to_target / distance * (npc.speed * time.delta_secs()).min(distance);
+
+    if let Some(new_direction) = FacingDirection::from_delta(dominant_axis(to_target)) {
+      *direction = new_direction;
+    }
+
+    transform.translation += move_delta.extend(0.0);
+  }
+}
+
+/// Keeps only the longer axis, so NPCs walking mostly sideways use the side sprites.
+fn dominant_axis(delta: Vec2) -> Vec2 {
+  if delta.x.abs() > delta.y.abs() {
+    Vec2::new(delta.x, 0.0)
+  } else {
+    Vec2::new(0.0, delta.y)
+  }
+}
+
+fn npc_chatter_system(
+  mut commands: Commands,
+  mut query: Query<(Entity, &NpcChatter, &mut ChatterTimer)>,
+  time: Res<Time>,
+) {
+  for (entity, chatter, mut timer) in &mut query {
+    timer.tick(time.delta());
+
+    if !timer.just_finished() {
+      continue;
+    }
+
+    let lines = chatter
+      .lines
+      .split(';')
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+      continue;
+    }
+
+    let line = lines[rand::random_range(0..lines.len())];
+
+    commands.trigger(ShowSpeechBubbleEvent {
+      speaker: entity,
+      text: line.to_string(),
+      seconds: 3.0,
+    });
+  }
+}
+
+fn show_speech_bubble_observer(
+  event: On<ShowSpeechBubbleEvent>,
+  mut commands: Commands,
+  bubble_query: Query<(Entity, &SpeechBubble)>,
+  font_assets: Res<FontAssets>,
+) {
+  for (bubble_entity, bubble) in &bubble_query {
+    if bubble.speaker == event.speaker {
+      commands.entity(bubble_entity).despawn();
+    }
+  }
+
+  let Ok(mut speaker) = commands.get_entity(event.speaker) else {
+    return;
+  };
+
+  speaker.with_child((
+    Name::new("SpeechBubble"),
+    SpeechBubble {
+      speaker: event.speaker,
+      timer: Timer::from_seconds(event.seconds, TimerMode::Once),
+    },
+    Text2d::new(event.text.clone()),
+    TextFont {
+      font: font_assets.regular.clone(),
+      font_size: SPEECH_BUBBLE_FONT_SIZE,
+      ..Default::default()
+    },
+    TextColor(Color::WHITE),
+    TextBackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+    Transform::from_xyz(0.0, SPEECH_BUBBLE_OFFSET, 10.0),
+  ));
+}
+
+fn speech_bubble_lifetime_system(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut SpeechBubble)>,
+  time: Res<Time>,
+) {
+  for (entity, mut bubble) in &mut query {
+    bubble.timer.tick(time.delta());
+
+    if bubble.timer.is_finished() {
+      commands.entity(entity).despawn();
+    }
+  }
+}
@@ -0,0 +1,133 @@
+use std::{
+  collections::{HashMap, HashSet},
+  env, fs,
+  path::PathBuf,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const PROFILE_FILE_NAME: &str = "profile.ron";
+const STARTING_TOKENS: u32 = 5;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(Profile::load());
+
+    app.add_systems(
+      Last,
+      save_profile_system.run_if(resource_changed::<Profile>.and(not(resource_added::<Profile>))),
+    );
+  }
+}
+
+/// Player progress that survives restarts. Written to `profile.ron` next to the executable's
+/// working directory whenever it changes.
+#[derive(Resource, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Profile {
+  pub flags: HashSet<String>,
+  pub best_scores: HashMap<String, u32>,
+  pub tokens: u32,
+  pub achievement_progress: HashMap<String, u32>,
+}
+
+impl Default for Profile {
+  fn default() -> Self {
+    Self {
+      flags: HashSet::new(),
+      best_scores: HashMap::new(),
+      tokens: STARTING_TOKENS,
+      achievement_progress: HashMap::new(),
+    }
+  }
+}
+
+impl Profile {
+  fn path() -> PathBuf {
+    env::current_dir()
+      .unwrap()
+      .join(PROFILE_FILE_NAME)
+  }
+
+  fn load() -> Self {
+    let path = Self::path();
+
+    let Ok(contents) = fs::read_to_string(&path) else {
+      info!("No profile found at {:?}, starting a new one", path);
+      return Self::default();
+    };
+
+    match ron::from_str(&contents) {
+      Ok(profile) => profile,
+      Err(error) => {
+        error!("Failed to parse profile {:?}: {}", path, error);
+        Self::default()
+      }
+    }
+  }
+
+  fn save(&self) {
+    let path = Self::path();
+
+    let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+      Ok(contents) => contents,
+      Err(error) => {
+        error!("Failed to serialize profile: {}", error);
+        return;
+      }
+    };
+
+    if let Err(error) = fs::write(&path, contents) {
+      error!("Failed to write profile {:?}: {}", path, error);
+    }
+  }
+
+  pub fn has_flag(&self, flag: &str) -> bool {
+    self.flags.contains(flag)
+  }
+
+  pub fn best_score(&self, game: &str) -> u32 {
+    self
+      .best_scores
+      .get(game)
+      .copied()
+      .unwrap_or(0)
+  }
+
+  pub fn achievement_progress(&self, id: &str) -> u32 {
+    self
+      .achievement_progress
+      .get(id)
+      .copied()
+      .unwrap_or(0)
+  }
+
+  /// Returns `false` and leaves the balance untouched if there are not enough tokens.
+  pub fn spend_tokens(&mut self, amount: u32) -> bool {
+    let Some(tokens) = self.tokens.checked_sub(amount) else {
+      return false;
+    };
+
+    self.tokens = tokens;
+    true
+  }
+
+  /// Returns `true` if `score` is a new record.
+  pub fn submit_score(&mut self, game: &str, score: u32) -> bool {
+    if score <= self.best_score(game) {
+      return false;
+    }
+
+    self
+      .best_scores
+      .insert(game.to_string(), score);
+    true
+  }
+}
+
+fn save_profile_system(profile: Res<Profile>) {
+  profile.save();
+}
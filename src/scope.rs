@@ -0,0 +1,81 @@
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+
+use crate::state::ContextScope;
+
+/// Owns the [`ContextScope`] state. Every entity spawned by the lobby or a minigame session
+/// is tagged with `DespawnOnExit(scope)`, so leaving the context removes all of it.
+/// Screens inside a session (game over, level select, ...) are modals: their entities are
+/// tagged with `DespawnOnExit` of the minigame's own state instead.
+///
+/// Debug builds check after every context change that nothing spawned in the old context
+/// is still alive, see [`report_leaked_entities_system`].
+pub struct ScopePlugin;
+
+impl Plugin for ScopePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_state::<ContextScope>();
+
+    if cfg!(debug_assertions) {
+      app.init_resource::<ScopeSnapshot>();
+      app.add_systems(Last, report_leaked_entities_system);
+    }
+  }
+}
+
+/// Entity that is deliberately not owned by any context, e.g. music that crossfades between
+/// the lobby and a minigame, or a toast that removes itself. The leak check skips it.
+#[derive(Component, Default)]
+pub struct Unscoped;
+
+/// Entities that were alive right after the last context change.
+#[derive(Resource, Default)]
+struct ScopeSnapshot {
+  entities: EntityHashSet,
+}
+
+/// Runs after the scoped entities of the exited context have been despawned. Anything spawned
+/// since the previous context change that is still alive and not owned by the new context
+/// has leaked.
+fn report_leaked_entities_system(
+  mut transitions: MessageReader<StateTransitionEvent<ContextScope>>,
+  mut snapshot: ResMut<ScopeSnapshot>,
+  entity_query: Query<(Entity, Option<&Name>), (Without<Unscoped>, Without<Observer>)>,
+  scope_query: Query<&DespawnOnExit<ContextScope>>,
+  parent_query: Query<&ChildOf>,
+) {
+  let Some(transition) = transitions.read().last() else {
+    return;
+  };
+  let (Some(exited), Some(entered)) = (transition.exited, transition.entered) else {
+    return;
+  };
+
+  // Загрузочный экран ничего не оставляет, отсчёт начинается с первого входа в лобби
+  if exited != ContextScope::None {
+    let owned_by_entered = |entity: Entity| {
+      std::iter::once(entity)
+        .chain(parent_query.iter_ancestors(entity))
+        .any(|entity| {
+          scope_query
+            .get(entity)
+            .is_ok_and(|scope| scope.0 == entered)
+        })
+    };
+
+    for (entity, name) in entity_query.iter() {
+      if snapshot.entities.contains(&entity) || owned_by_entered(entity) {
+        continue;
+      }
+
+      warn!(
+        "{entity} ({}) outlived {exited:?}: tag it with DespawnOnExit or Unscoped",
+        name.map_or("unnamed", Name::as_str)
+      );
+    }
+  }
+
+  snapshot.entities = entity_query
+    .iter()
+    .map(|(entity, _)| entity)
+    .collect();
+}
@@ -0,0 +1,560 @@
+use std::{env, fs, path::PathBuf};
+
+use bevy::{
+  camera::ScalingMode,
+  prelude::*,
+  window::{MonitorSelection, PrimaryWindow, VideoModeSelection, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  achievements::trophy_wall_is_open,
+  audio::AudioBus,
+  dialogue::{DialogueSystems, dialogue_is_active},
+  game::FontAssets,
+  games::{CurrentGameState, SNAKE_SKIN_NAMES},
+  state::{AppState, ContextScope},
+};
+
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+
+const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+const CAMERA_SCALE_STEP: f32 = 0.05;
+const CAMERA_SCALE_MIN: f32 = 0.1;
+const CAMERA_SCALE_MAX: f32 = 1.0;
+
+const VOLUME_STEP: f32 = 0.1;
+
+const SNAKE_STEP_SECONDS_STEP: f32 = 0.05;
+const SNAKE_STEP_SECONDS_MIN: f32 = 0.1;
+const SNAKE_STEP_SECONDS_MAX: f32 = 1.0;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(Settings::load());
+    app.init_resource::<SettingsMenuState>();
+
+    app.add_systems(
+      Update,
+      (
+        toggle_settings_menu_system
+          .after(DialogueSystems)
+          .run_if(
+            in_state(AppState::Lobby)
+              .and(in_lobby)
+              .and(not(dialogue_is_active))
+              .and(not(trophy_wall_is_open)),
+          ),
+        navigate_settings_menu_system.run_if(settings_menu_is_open),
+        update_settings_menu_ui_system.run_if(settings_menu_is_open),
+      )
+        .chain(),
+    );
+
+    // Пока открыто меню, E не должна запускать диалоги и автоматы за ним
+    app.configure_sets(Update, DialogueSystems.run_if(not(settings_menu_is_open)));
+
+    app.add_systems(
+      Update,
+      apply_window_settings_system.run_if(resource_changed::<Settings>),
+    );
+
+    app.add_systems(
+      Last,
+      save_settings_system
+        .run_if(resource_changed::<Settings>.and(not(resource_added::<Settings>))),
+    );
+  }
+}
+
+fn in_lobby(game_state: Res<CurrentGameState>) -> bool {
+  game_state.current_game.is_none()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowModeSetting {
+  Windowed,
+  Borderless,
+  Fullscreen,
+}
+
+impl WindowModeSetting {
+  fn to_window_mode(self) -> WindowMode {
+    match self {
+      WindowModeSetting::Windowed => WindowMode::Windowed,
+      WindowModeSetting::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+      WindowModeSetting::Fullscreen => {
+        WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+      }
+    }
+  }
+
+  fn cycle(self, delta: i32) -> Self {
+    const MODES: [WindowModeSetting; 3] = [
+      WindowModeSetting::Windowed,
+      WindowModeSetting::Borderless,
+      WindowModeSetting::Fullscreen,
+    ];
+
+    let index = MODES
+      .iter()
+      .position(|mode| *mode == self)
+      .unwrap_or(0);
+    MODES[(index as i32 + delta).rem_euclid(MODES.len() as i32) as usize]
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraScaling {
+  /// Keeps at least 1200x800 world units visible, so the lobby looks the same on every window size.
+  AutoMin,
+  /// One world unit per pixel: bigger windows show more of the lobby.
+  WindowSize,
+}
+
+impl CameraScaling {
+  pub fn to_scaling_mode(self) -> ScalingMode {
+    match self {
+      CameraScaling::AutoMin => ScalingMode::AutoMin {
+        min_width: 1200.0,
+        min_height: 800.0,
+      },
+      CameraScaling::WindowSize => ScalingMode::WindowSize,
+    }
+  }
+}
+
+/// User settings, persisted to `settings.ron` and applied as soon as they change.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+  pub window_mode: WindowModeSetting,
+  pub window_width: u32,
+  pub window_height: u32,
+  pub camera_scale: f32,
+  pub camera_scaling: CameraScaling,
+  pub master_volume: f32,
+  pub sfx_volume: f32,
+  pub music_volume: f32,
+  pub ui_volume: f32,
+  /// Seconds per Snake step at the start of a run.
+  pub snake_step_seconds: f32,
+  /// Row of the snake skin atlas, see [`SNAKE_SKIN_NAMES`].
+  pub snake_skin: usize,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      window_mode: WindowModeSetting::Windowed,
+      window_width: 1280,
+      window_height: 720,
+      camera_scale: 0.3,
+      camera_scaling: CameraScaling::AutoMin,
+      master_volume: 1.0,
+      sfx_volume: 1.0,
+      music_volume: 0.6,
+      ui_volume: 1.0,
+      snake_step_seconds: 0.5,
+      snake_skin: 0,
+    }
+  }
+}
+
+impl Settings {
+  fn path() -> PathBuf {
+    env::current_dir()
+      .unwrap()
+      .join(SETTINGS_FILE_NAME)
+  }
+
+  fn load() -> Self {
+    let path = Self::path();
+
+    let Ok(contents) = fs::read_to_string(&path) else {
+      info!("No settings found at {:?}, using defaults", path);
+      return Self::default();
+    };
+
+    match ron::from_str::<Self>(&contents) {
+      Ok(settings) => settings.sanitized(),
+      Err(error) => {
+        error!("Failed to parse settings {:?}: {}", path, error);
+        Self::default()
+      }
+    }
+  }
+
+  /// Brings hand-edited values back into the ranges the menu allows. A value that is not a
+  /// number falls back to its default.
+  fn sanitized(self) -> Self {
+    let default = Self::default();
+    let clamp = |value: f32, min: f32, max: f32, default: f32| {
+      if value.is_nan() {
+        default
+      } else {
+        value.clamp(min, max)
+      }
+    };
+    let volume = |value: f32, default: f32| clamp(value, 0.0, 1.0, default);
+
+    Self {
+      camera_scale: clamp(
+        self.camera_scale,
+        CAMERA_SCALE_MIN,
+        CAMERA_SCALE_MAX,
+        default.camera_scale,
+      ),
+      master_volume: volume(self.master_volume, default.master_volume),
+      sfx_volume: volume(self.sfx_volume, default.sfx_volume),
+      music_volume: volume(self.music_volume, default.music_volume),
+      ui_volume: volume(self.ui_volume, default.ui_volume),
+      snake_step_seconds: clamp(
+        self.snake_step_seconds,
+        SNAKE_STEP_SECONDS_MIN,
+        SNAKE_STEP_SECONDS_MAX,
+        default.snake_step_seconds,
+      ),
+      ..self
+    }
+  }
+
+  fn save(&self) {
+    let path = Self::path();
+
+    let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+      Ok(contents) => contents,
+      Err(error) => {
+        error!("Failed to serialize settings: {}", error);
+        return;
+      }
+    };
+
+    if let Err(error) = fs::write(&path, contents) {
+      error!("Failed to write settings {:?}: {}", path, error);
+    }
+  }
+
+  /// Linear volume of a mixer bus with the master volume already applied.
+  pub fn bus_volume(&self, bus: AudioBus) -> f32 {
+    let bus_volume = match bus {
+      AudioBus::Music => self.music_volume,
+      AudioBus::Sfx => self.sfx_volume,
+      AudioBus::Ui => self.ui_volume,
+    };
+
+    self.master_volume * bus_volume
+  }
+}
+
+#[derive(Resource, Default)]
+pub struct SettingsMenuState {
+  is_open: bool,
+  selected_row: usize,
+}
+
+pub fn settings_menu_is_open(state: Res<SettingsMenuState>) -> bool {
+  state.is_open
+}
+
+#[derive(Clone, Copy)]
+enum SettingsRow {
+  WindowMode,
+  Resolution,
+  CameraScale,
+  CameraScaling,
+  MasterVolume,
+  SfxVolume,
+  MusicVolume,
+  UiVolume,
+  SnakeSpeed,
+  SnakeSkin,
+}
+
+const SETTINGS_ROWS: [SettingsRow; 10] = [
+  SettingsRow::WindowMode,
+  SettingsRow::Resolution,
+  SettingsRow::CameraScale,
+  SettingsRow::CameraScaling,
+  SettingsRow::MasterVolume,
+  SettingsRow::SfxVolume,
+  SettingsRow::MusicVolume,
+  SettingsRow::UiVolume,
+  SettingsRow::SnakeSpeed,
+  SettingsRow::SnakeSkin,
+];
+
+impl SettingsRow {
+  fn label(&self, settings: &Settings) -> String {
+    let percent = |value: f32| (value * 100.0).round() as u32;
+
+    match self {
+      SettingsRow::WindowMode => format!("Window mode: {:?}", settings.window_mode),
+      SettingsRow::Resolution => format!(
+        "Resolution: {}x{}",
+        settings.window_width, settings.window_height
+      ),
+      SettingsRow::CameraScale => format!("Camera zoom: {:.2}", settings.camera_scale),
+      SettingsRow::CameraScaling => format!("Camera scaling: {:?}", settings.camera_scaling),
+      SettingsRow::MasterVolume => format!("Master volume: {}%", percent(settings.master_volume)),
+      SettingsRow::SfxVolume => format!("SFX volume: {}%", percent(settings.sfx_volume)),
+      SettingsRow::MusicVolume => format!("Music volume: {}%", percent(settings.music_volume)),
+      SettingsRow::UiVolume => format!("UI volume: {}%", percent(settings.ui_volume)),
+      SettingsRow::SnakeSpeed => format!("Snake step: {:.2}s", settings.snake_step_seconds),
+      SettingsRow::SnakeSkin => format!(
+        "Snake skin: {}",
+        SNAKE_SKIN_NAMES
+          .get(settings.snake_skin)
+          .unwrap_or(&SNAKE_SKIN_NAMES[0])
+      ),
+    }
+  }
+
+  fn adjust(&self, settings: &mut Settings, delta: i32) {
+    let step_volume = |value: f32| (value + VOLUME_STEP * delta as f32).clamp(0.0, 1.0);
+
+    match self {
+      SettingsRow::WindowMode => {
+        settings.window_mode = settings.window_mode.cycle(delta);
+      }
+      SettingsRow::Resolution => {
+        let index = RESOLUTIONS
+          .iter()
+          .position(|resolution| *resolution == (settings.window_width, settings.window_height))
+          .unwrap_or(0);
+        let index = (index as i32 + delta).rem_euclid(RESOLUTIONS.len() as i32) as usize;
+        (settings.window_width, settings.window_height) = RESOLUTIONS[index];
+      }
+      SettingsRow::CameraScale => {
+        settings.camera_scale = (settings.camera_scale + CAMERA_SCALE_STEP * delta as f32)
+          .clamp(CAMERA_SCALE_MIN, CAMERA_SCALE_MAX);
+      }
+      SettingsRow::CameraScaling => {
+        settings.camera_scaling = match settings.camera_scaling {
+          CameraScaling::AutoMin => CameraScaling::WindowSize,
+          CameraScaling::WindowSize => CameraScaling::AutoMin,
+        };
+      }
+      SettingsRow::MasterVolume => settings.master_volume = step_volume(settings.master_volume),
+      SettingsRow::SfxVolume => settings.sfx_volume = step_volume(settings.sfx_volume),
+      SettingsRow::MusicVolume => settings.music_volume = step_volume(settings.music_volume),
+      SettingsRow::UiVolume => settings.ui_volume = step_volume(settings.ui_volume),
+      SettingsRow::SnakeSpeed => {
+        settings.snake_step_seconds = (settings.snake_step_seconds
+          + SNAKE_STEP_SECONDS_STEP * delta as f32)
+          .clamp(SNAKE_STEP_SECONDS_MIN, SNAKE_STEP_SECONDS_MAX);
+      }
+      SettingsRow::SnakeSkin => {
+        let count = SNAKE_SKIN_NAMES.len() as i32;
+        settings.snake_skin = (settings.snake_skin as i32 + delta).rem_euclid(count) as usize;
+      }
+    }
+  }
+}
+
+#[derive(Component)]
+struct SettingsMenuUi;
+
+#[derive(Component)]
+struct SettingsMenuRowsText;
+
+fn toggle_settings_menu_system(
+  mut commands: Commands,
+  mut state: ResMut<SettingsMenuState>,
+  mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+  font_assets: Res<FontAssets>,
+  settings_menu_ui_query: Query<Entity, With<SettingsMenuUi>>,
+) {
+  if !keyboard_input.just_pressed(KeyCode::Escape) {
+    return;
+  }
+
+  keyboard_input.clear_just_pressed(KeyCode::Escape);
+
+  if state.is_open {
+    for entity in settings_menu_ui_query.iter() {
+      commands.entity(entity).despawn();
+    }
+    state.is_open = false;
+    return;
+  }
+
+  spawn_settings_menu_ui(&mut commands, &font_assets);
+  state.is_open = true;
+  state.selected_row = 0;
+}
+
+fn navigate_settings_menu_system(
+  mut state: ResMut<SettingsMenuState>,
+  mut settings: ResMut<Settings>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+  if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+    state.selected_row = state
+      .selected_row
+      .checked_sub(1)
+      .unwrap_or(SETTINGS_ROWS.len() - 1);
+  } else if keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+    state.selected_row = (state.selected_row + 1) % SETTINGS_ROWS.len();
+  }
+
+  let delta = if keyboard_input.any_just_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+    -1
+  } else if keyboard_input.any_just_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+    1
+  } else {
+    return;
+  };
+
+  SETTINGS_ROWS[state.selected_row].adjust(&mut settings, delta);
+}
+
+fn spawn_settings_menu_ui(commands: &mut Commands, font_assets: &FontAssets) {
+  commands.spawn((
+    Name::new("SettingsMenuUi"),
+    SettingsMenuUi,
+    DespawnOnExit(ContextScope::Lobby),
+    Node {
+      width: percent(100),
+      height: percent(100),
+      align_items: AlignItems::Center,
+      justify_content: JustifyContent::Center,
+      ..default()
+    },
+    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+    GlobalZIndex(5),
+    children![(
+      Node {
+        flex_direction: FlexDirection::Column,
+        row_gap: px(12.),
+        padding: UiRect::all(px(24.)),
+        border: UiRect::all(px(2.)),
+        ..default()
+      },
+      BackgroundColor(Color::srgba(0.05, 0.05, 0.1, 0.95)),
+      BorderColor::all(Color::WHITE),
+      children![
+        (
+          Text::new("Settings"),
+          TextFont {
+            font: font_assets.regular.clone(),
+            font_size: 36.,
+            ..Default::default()
+          },
+          TextColor(Color::srgb(1.0, 0.85, 0.3)),
+        ),
+        (
+          SettingsMenuRowsText,
+          Text::default(),
+          TextFont {
+            font: font_assets.regular.clone(),
+            font_size: 26.,
+            ..Default::default()
+          },
+          TextColor(Color::WHITE),
+        ),
+        (
+          Text::new("Up/Down: select  Left/Right: change  Esc: close"),
+          TextFont {
+            font: font_assets.regular.clone(),
+            font_size: 18.,
+            ..Default::default()
+          },
+          TextColor(Color::srgb(0.6, 0.6, 0.6)),
+        ),
+      ],
+    )],
+  ));
+}
+
+fn update_settings_menu_ui_system(
+  state: Res<SettingsMenuState>,
+  settings: Res<Settings>,
+  mut rows_text_query: Query<&mut Text, With<SettingsMenuRowsText>>,
+) {
+  for mut text in &mut rows_text_query {
+    text.0 = SETTINGS_ROWS
+      .iter()
+      .enumerate()
+      .map(|(index, row)| {
+        let marker = if index == state.selected_row {
+          ">"
+        } else {
+          " "
+        };
+        format!("{marker} {}", row.label(&settings))
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+  }
+}
+
+fn apply_window_settings_system(
+  settings: Res<Settings>,
+  mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+  let window_mode = settings.window_mode.to_window_mode();
+  if window.mode != window_mode {
+    window.mode = window_mode;
+  }
+
+  if settings.window_mode == WindowModeSetting::Windowed {
+    window
+      .resolution
+      .set(settings.window_width as f32, settings.window_height as f32);
+  }
+}
+
+fn save_settings_system(settings: Res<Settings>) {
+  settings.save();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn out_of_range_values_are_clamped() {
+    let settings = Settings {
+      camera_scale: 0.0,
+      master_volume: 3.0,
+      sfx_volume: -1.0,
+      snake_step_seconds: -0.5,
+      ..default()
+    }
+    .sanitized();
+
+    assert_eq!(settings.camera_scale, CAMERA_SCALE_MIN);
+    assert_eq!(settings.master_volume, 1.0);
+    assert_eq!(settings.sfx_volume, 0.0);
+    assert_eq!(settings.snake_step_seconds, SNAKE_STEP_SECONDS_MIN);
+  }
+
+  #[test]
+  fn nan_falls_back_to_the_default() {
+    let settings = Settings {
+      camera_scale: f32::NAN,
+      music_volume: f32::NAN,
+      snake_step_seconds: f32::NAN,
+      ..default()
+    }
+    .sanitized();
+
+    assert_eq!(settings, Settings::default());
+  }
+
+  #[test]
+  fn valid_settings_are_kept() {
+    let settings = Settings {
+      camera_scale: 0.5,
+      music_volume: 0.2,
+      snake_step_seconds: 0.3,
+      ..default()
+    };
+
+    assert_eq!(settings.clone().sanitized(), settings);
+  }
+}
@@ -1,14 +1,33 @@
-use bevy::state::state::States;
+use bevy::prelude::*;
+
+use crate::games::GameType;
 
 #[derive(States, Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
 pub enum AppState {
   #[default]
-  Menu,
+  Loading,
+  Lobby,
 }
 
-#[derive(States, Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+/// Whether the current minigame is running. Exists only inside a minigame and starts over as
+/// [`GameState::Playing`] on every launch.
+#[derive(SubStates, Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+#[source(ContextScope = ContextScope::Minigame(_))]
 pub enum GameState {
   #[default]
   Playing,
   Paused,
 }
+
+/// Context that owns the entities spawned in it. Entities tagged with
+/// `DespawnOnExit(ContextScope::...)` are despawned when the context is left,
+/// see [`crate::scope::ScopePlugin`].
+#[derive(States, Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+pub enum ContextScope {
+  /// Loading screen, before the lobby is entered for the first time.
+  #[default]
+  None,
+  Lobby,
+  /// One session at a minigame cabinet, from launch to the return to the lobby.
+  Minigame(GameType),
+}
@@ -1,28 +1,25 @@
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 
+use crate::{game::LobbyAssets, state::ContextScope};
+
 pub struct TilemapPlugin;
 
 impl Plugin for TilemapPlugin {
   fn build(&self, app: &mut App) {
     app
       .add_message::<SpawnTilemapMessage>()
-      .add_message::<DespawnTilemapMessage>()
-      .add_systems(Update, spawn_map)
-      .add_systems(Update, despawn_map);
+      .add_systems(Update, spawn_map);
   }
 }
 
 #[derive(Message)]
 pub struct SpawnTilemapMessage;
 
-#[derive(Message)]
-pub struct DespawnTilemapMessage;
-
 fn spawn_map(
   mut commands: Commands,
   mut spawn_tilemap_messages: MessageReader<SpawnTilemapMessage>,
-  asset_server: Res<AssetServer>,
+  lobby_assets: Res<LobbyAssets>,
 ) {
   if spawn_tilemap_messages.is_empty() {
     return;
@@ -31,8 +28,9 @@ fn spawn_map(
   for _ in spawn_tilemap_messages.read() {
     commands
       .spawn((
-        TiledMap(asset_server.load("maps/map.tmx")),
+        TiledMap(lobby_assets.map.clone()),
         TilemapAnchor::Center,
+        DespawnOnExit(ContextScope::Lobby),
       ))
       .observe(
         |map_created: On<TiledEvent<MapCreated>>,
@@ -58,17 +56,3 @@ fn spawn_map(
       );
   }
 }
-
-fn despawn_map(
-  mut commands: Commands,
-  despawn_tilemap_messages: MessageReader<DespawnTilemapMessage>,
-  query: Query<Entity, With<TiledMap>>,
-) {
-  if despawn_tilemap_messages.is_empty() {
-    return;
-  }
-
-  for entity in query.iter() {
-    commands.entity(entity).despawn();
-  }
-}